@@ -58,11 +58,19 @@ pub fn frechet_distance(curve1: &Contour, curve2: &Contour) -> f32 {
         .flat_map(|segment| vec![segment.baseline.from(), segment.baseline.to()])
         .collect();
 
+    frechet_distance_points(&points1, &points2)
+}
+
+/// Discrete Fréchet distance between two point lists.
+pub fn frechet_distance_points(points1: &[Vector2F], points2: &[Vector2F]) -> f32 {
     let (longcalcurve, shortcalcurve) = if points1.len() > points2.len() {
-        (&points1[..], &points2[..])
+        (points1, points2)
     } else {
-        (&points2[..], &points1[..])
+        (points2, points1)
     };
+    if shortcalcurve.is_empty() {
+        return f32::INFINITY;
+    }
 
     let mut prev_resultscalcol = vec![];
     for i in 0..longcalcurve.len() {
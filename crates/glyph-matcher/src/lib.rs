@@ -8,6 +8,7 @@ use std::{
 use inkfont::{ opentype::cmap::CMap, CffFont, Font, GlyphId, OpenTypeFont, TrueTypeFont };
 use istring::SmallString;
 use pathfinder_content::outline::{ Contour, ContourIterFlags, Outline };
+use pathfinder_geometry::{ rect::RectF, vector::Vector2F };
 use inkencoding::glyphname_to_unicode;
 use serde::{ Deserialize, Serialize };
 
@@ -16,10 +17,67 @@ pub mod frechet;
 #[derive(Serialize, Deserialize)]
 struct Entry<I> {
     contour_sets: Vec<HashSet<(u16, u16)>>,
-    // outline: Outline,
+    /// Computed from `contour_sets` when the entry is added or loaded, it is not stored.
+    #[serde(skip)]
+    shape: Shape,
     data: I,
 }
 
+/// The contours of a glyph, scaled into the unit square, for fuzzy matching.
+///
+/// Computed once when a glyph is added or loaded, so matching only needs to extract the query glyph.
+#[derive(Default)]
+struct Shape {
+    contours: Vec<Vec<Vector2F>>,
+    /// width / height of the bounding box
+    aspect: f32,
+}
+impl Shape {
+    /// The shape of the contours in `contour_sets`, as `ShapeDb` stores them.
+    fn from_sets(contour_sets: &[HashSet<(u16, u16)>]) -> Self {
+        let contours: Vec<_> = contour_sets.iter().map(ordered_points).collect();
+        Shape::new(&contours)
+    }
+    fn new(contours: &[Vec<Vector2F>]) -> Self {
+        let Some(b) = contours
+            .iter()
+            .flatten()
+            .map(|&p| RectF::new(p, Vector2F::zero()))
+            .reduce(|a, b| a.union_rect(b))
+        else {
+            return Shape::default();
+        };
+        let scale = max(b.width(), b.height());
+        if scale <= 0.0 {
+            return Shape::default();
+        }
+        let contours = contours
+            .iter()
+            .map(|c| c.iter().map(|&p| (p - b.origin()) * (1.0 / scale)).collect())
+            .collect();
+        Shape {
+            contours,
+            aspect: b.width() / b.height(),
+        }
+    }
+    /// Average distance of every contour to the closest contour of `other`.
+    fn distance(&self, other: &Shape) -> f32 {
+        if self.contours.len() != other.contours.len() || self.contours.is_empty() {
+            return f32::INFINITY;
+        }
+        let sum: f32 = self.contours
+            .iter()
+            .map(|a| {
+                other.contours
+                    .iter()
+                    .map(|b| frechet::frechet_distance_points(a, b))
+                    .fold(f32::INFINITY, min)
+            })
+            .sum();
+        sum / self.contours.len() as f32
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ShapeDb<I> {
     entries: Vec<Entry<I>>,
@@ -32,6 +90,32 @@ impl<I> ShapeDb<I> {
             points: HashMap::new(),
         }
     }
+    /// Normalize the contours of every entry for `best_match`.
+    fn compute_shapes(&mut self) {
+        for e in &mut self.entries {
+            e.shape = Shape::from_sets(&e.contour_sets);
+        }
+    }
+}
+
+/// The points of a contour set, ordered by their angle around the center.
+///
+/// The sets don't keep the order of the points, this gives stored and query glyphs the same one.
+fn ordered_points(set: &HashSet<(u16, u16)>) -> Vec<Vector2F> {
+    let mut points: Vec<_> = set.iter().map(|&(x, y)| Vector2F::new(x as f32, y as f32)).collect();
+    if points.is_empty() {
+        return points;
+    }
+    let center = points.iter().fold(Vector2F::zero(), |a, &b| a + b) * (1.0 / points.len() as f32);
+    let key = |p: Vector2F| {
+        let d = p - center;
+        (d.y().atan2(d.x()), d.square_length())
+    };
+    points.sort_by(|&a, &b| {
+        let (a, b) = (key(a), key(b));
+        a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+    });
+    points
 }
 
 fn add_font(db_dir: &Path, font_file: &Path) {
@@ -173,12 +257,28 @@ impl<I: Display + PartialEq> ShapeDb<I> {
                 }
             }
         }
-        let contours = outline.contours().iter().map(points_set).collect();
+        let contour_sets: Vec<_> = outline.contours().iter().map(points_set).collect();
         self.entries.push(Entry {
             data: value,
-            contour_sets: contours,
+            shape: Shape::from_sets(&contour_sets),
+            contour_sets,
         });
     }
+    /// Find the entry with the most similar shape to `outline`.
+    ///
+    /// Unlike `get`, this does not require the points to match exactly.
+    /// Returns the entry and its distance (relative to the glyph size).
+    pub fn best_match(&self, outline: &Outline) -> Option<(&I, f32)> {
+        let contour_sets: Vec<_> = outline.contours().iter().map(points_set).collect();
+        let query = Shape::from_sets(&contour_sets);
+        self.entries
+            .iter()
+            // very different proportions can't be the same glyph
+            .filter(|e| (e.shape.aspect - query.aspect).abs() < 0.5 * max(e.shape.aspect, query.aspect))
+            .map(|e| (&e.data, query.distance(&e.shape)))
+            .filter(|&(_, dist)| dist.is_finite())
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
     pub fn get(
         &self,
         outline: &pathfinder_content::outline::Outline,
@@ -406,15 +506,33 @@ impl FontDb {
             return cached.clone();
         }
 
-        let file_path = self.path.join(ps_name);
-        let db = if file_path.is_file() {
-            Some(Arc::new(postcard::from_bytes(&std::fs::read(&file_path).unwrap()).unwrap()))
-        } else {
-            None
-        };
+        let db = load_db(&self.path.join(ps_name));
         self.cache.write().unwrap().insert(ps_name.into(), db.clone());
         db
     }
+    /// Load every font in the database and compute its glyph shapes, so later lookups
+    /// don't touch the disk or normalize candidates.
+    pub fn warm_cache(&self) {
+        let Ok(dir) = std::fs::read_dir(&self.path) else {
+            return;
+        };
+        for e in dir.filter_map(|r| r.ok()) {
+            let Some(ps_name) = e.file_name().to_str().map(String::from) else {
+                continue;
+            };
+            if self.cache.read().unwrap().contains_key(&ps_name) {
+                continue;
+            }
+            // load without holding the lock, lookups of other fonts go on meanwhile
+            let db = load_db(&e.path());
+            self.cache.write().unwrap().entry(ps_name).or_insert(db);
+        }
+    }
+    /// The best fuzzy match for `outline` in the font `ps_name`.
+    pub fn best_match(&self, ps_name: &str, outline: &Outline) -> Option<SmallString> {
+        let db = self.get_db(ps_name)?;
+        db.best_match(outline).map(|(s, _)| s.clone())
+    }
     pub fn font_report(&self, ps_name: &str, font: &(dyn Font + Sync + Send)) -> String {
         let mut report = String::new();
         let db = self.get_db(ps_name).unwrap();
@@ -435,6 +553,23 @@ impl FontDb {
     }
 }
 
+fn load_db(file_path: &Path) -> Option<Arc<ShapeDb<SmallString>>> {
+    if !file_path.is_file() {
+        return None;
+    }
+    let data = std::fs::read(file_path).ok()?;
+    match postcard::from_bytes::<ShapeDb<SmallString>>(&data) {
+        Ok(mut db) => {
+            db.compute_shapes();
+            Some(Arc::new(db))
+        }
+        Err(e) => {
+            log::warn!("can't read {file_path:?}: {e}");
+            None
+        }
+    }
+}
+
 pub fn max(a: f32, b: f32) -> f32 {
     if a > b { a } else { b }
 }
@@ -448,3 +583,58 @@ pub struct UnicodeEntry {
     pub unicode: Vec<u32>,
 }
 pub type UnicodeList = Vec<UnicodeEntry>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(size: f32) -> Outline {
+        let mut contour = Contour::new();
+        contour.push_endpoint(Vector2F::new(0.0, 0.0));
+        contour.push_endpoint(Vector2F::new(size, 0.0));
+        contour.push_endpoint(Vector2F::new(0.5 * size, size));
+        contour.close();
+        let mut outline = Outline::new();
+        outline.push_contour(contour);
+        outline
+    }
+
+    fn square(size: f32) -> Outline {
+        Outline::from_rect(RectF::new(Vector2F::splat(10.0), Vector2F::splat(size)))
+    }
+
+    #[test]
+    fn test_best_match_uses_cached_shapes() {
+        let mut db = ShapeDb::new();
+        db.add_outline(&square(500.0), SmallString::from("o"));
+        db.add_outline(&triangle(500.0), SmallString::from("v"));
+
+        // without the points of the candidates only their cached shapes are left to match
+        for e in &mut db.entries {
+            e.contour_sets.clear();
+        }
+        for _ in 0..2 {
+            assert_eq!(db.best_match(&square(80.0)).map(|(s, _)| s.as_str()), Some("o"));
+            assert_eq!(db.best_match(&triangle(70.0)).map(|(s, _)| s.as_str()), Some("v"));
+        }
+    }
+
+    #[test]
+    fn test_warm_cache() {
+        let dir = std::env::temp_dir().join(format!("glyphmatcher-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut db = ShapeDb::new();
+        db.add_outline(&triangle(500.0), SmallString::from("v"));
+        std::fs::write(dir.join("Test"), postcard::to_allocvec(&db).unwrap()).unwrap();
+
+        let font_db = FontDb::new(&dir);
+        font_db.warm_cache();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // loaded once, with its shapes computed
+        let a = font_db.get_db("Test").unwrap();
+        let b = font_db.get_db("Test").unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(font_db.best_match("Test", &triangle(70.0)).as_ref().map(|s| s.as_str()), Some("v"));
+    }
+}
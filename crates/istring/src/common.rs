@@ -19,6 +19,50 @@ impl $name {
         &mut self.union.heap
     }
 
+    /// view as Inline, without checking the variant.
+    ///
+    /// # Safety
+    /// `self.is_inline()` must be true, otherwise the heap pointer is read as inline data.
+    #[inline(always)]
+    pub unsafe fn get_inline_unchecked(&self) -> &Inline {
+        debug_assert!(self.is_inline());
+        unsafe { &self.union.inline }
+    }
+
+    /// view as Heap, without checking the variant.
+    ///
+    /// # Safety
+    /// `self.is_inline()` must be false, otherwise inline data is read as a pointer and length.
+    #[inline(always)]
+    pub unsafe fn get_heap_unchecked(&self) -> &Heap {
+        debug_assert!(!self.is_inline());
+        unsafe { &self.union.heap }
+    }
+
+    /// like `as_slice`, but skips the `is_inline` check.
+    ///
+    /// # Safety
+    /// `self.is_inline()` must be true, see `get_inline_unchecked`.
+    #[inline(always)]
+    pub unsafe fn as_inline_slice_unchecked(&self) -> &[u8] {
+        unsafe {
+            let inline = self.get_inline_unchecked();
+            inline.data.get_unchecked(.. (inline.len & LEN_MASK) as usize)
+        }
+    }
+
+    /// like `as_slice`, but skips the `is_inline` check.
+    ///
+    /// # Safety
+    /// `self.is_inline()` must be false, see `get_heap_unchecked`.
+    #[inline(always)]
+    pub unsafe fn as_heap_slice_unchecked(&self) -> &[u8] {
+        unsafe {
+            let heap = self.get_heap_unchecked();
+            slice::from_raw_parts(heap.ptr, heap.len)
+        }
+    }
+
     //#[inline]
     //pub fn as_inline_or_heap(self) 
     
@@ -40,6 +84,10 @@ impl $name {
         }
     }
     #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline(always)]
     pub fn as_mut_ptr(&mut self) -> *mut u8 {
         unsafe {
             if self.is_inline() {
@@ -238,6 +286,10 @@ impl Borrow<[u8]> for $name {
 macro_rules! define_common_string {
     ($name:ident, $union:ident) => {
 impl $name {
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
     #[inline(always)]
     pub fn as_str(&self) -> &str {
         unsafe {
@@ -245,14 +297,88 @@ impl $name {
         }
     }
     
+    /// like `as_str`, but skips the `is_inline` check.
+    ///
+    /// # Safety
+    /// The string must be inlined (`self.bytes.is_inline()`).
+    #[inline(always)]
+    pub unsafe fn as_inline_str_unchecked(&self) -> &str {
+        unsafe {
+            str::from_utf8_unchecked(self.bytes.as_inline_slice_unchecked())
+        }
+    }
+
+    /// like `as_str`, but skips the `is_inline` check.
+    ///
+    /// # Safety
+    /// The string must be on the Heap (`!self.bytes.is_inline()`).
+    #[inline(always)]
+    pub unsafe fn as_heap_str_unchecked(&self) -> &str {
+        unsafe {
+            str::from_utf8_unchecked(self.bytes.as_heap_slice_unchecked())
+        }
+    }
+
     #[inline(always)]
     pub fn as_mut_str(&mut self) -> &mut str {
         unsafe {
             str::from_utf8_unchecked_mut(self.bytes.as_mut_slice())
         }
     }
-    
-    
+
+    /// Converts all ASCII letters to lowercase in place.
+    ///
+    /// Non-ASCII bytes are left untouched, so the length does not change.
+    #[inline]
+    pub fn make_ascii_lowercase(&mut self) {
+        self.bytes.as_mut_slice().make_ascii_lowercase();
+    }
+
+    /// Converts all ASCII letters to uppercase in place.
+    ///
+    /// Non-ASCII bytes are left untouched, so the length does not change.
+    #[inline]
+    pub fn make_ascii_uppercase(&mut self) {
+        self.bytes.as_mut_slice().make_ascii_uppercase();
+    }
+
+    #[inline]
+    pub fn to_ascii_lowercase(&self) -> $name {
+        let mut s = self.clone();
+        s.make_ascii_lowercase();
+        s
+    }
+
+    #[inline]
+    pub fn to_ascii_uppercase(&self) -> $name {
+        let mut s = self.clone();
+        s.make_ascii_uppercase();
+        s
+    }
+
+    // The following forward to `str` explicitly, so generic code does not
+    // have to pick between the `str` and `[u8]` methods.
+
+    #[inline]
+    pub fn starts_with(&self, pat: &str) -> bool {
+        self.as_str().starts_with(pat)
+    }
+
+    #[inline]
+    pub fn ends_with(&self, pat: &str) -> bool {
+        self.as_str().ends_with(pat)
+    }
+
+    #[inline]
+    pub fn contains(&self, pat: &str) -> bool {
+        self.as_str().contains(pat)
+    }
+
+    /// Byte index of the first match of `pat`.
+    #[inline]
+    pub fn find(&self, pat: &str) -> Option<usize> {
+        self.as_str().find(pat)
+    }
 }
 impl $name {
     #[inline(always)]
@@ -328,11 +454,13 @@ impl PartialEq for $name {
     }
 }
 impl Eq for $name {}
+// has to hash exactly like `str`, otherwise lookups via `Borrow<str>` fail
 impl core::hash::Hash for $name {
     fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.as_str().hash(state);
     }
 }
+// has to order exactly like `str`, `BTreeMap` lookups and ranges via `Borrow<str>` rely on it
 impl core::cmp::PartialOrd for $name {
     #[inline(always)]
     fn partial_cmp(&self, rhs: &Self) -> Option<core::cmp::Ordering> {
@@ -416,5 +544,14 @@ impl Borrow<str> for $name {
     }
 }
 
+impl str::FromStr for $name {
+    type Err = convert::Infallible;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<$name, convert::Infallible> {
+        Ok($name::from(s))
+    }
+}
+
     }
 }
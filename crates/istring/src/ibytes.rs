@@ -1,6 +1,8 @@
+use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::ops::Index;
+use core::mem::MaybeUninit;
 use core::{cmp, convert, fmt, mem, ops, ptr, slice};
 
 #[cfg(feature = "ts")]
@@ -10,9 +12,9 @@ const IS_INLINE: u8 = 1 << 7;
 const LEN_MASK: u8 = !IS_INLINE;
 
 #[cfg(target_pointer_width = "64")]
-const INLINE_CAPACITY: usize = 23;
+pub(crate) const INLINE_CAPACITY: usize = 23;
 #[cfg(target_pointer_width = "32")]
-const INLINE_CAPACITY: usize = 11;
+pub(crate) const INLINE_CAPACITY: usize = 11;
 
 #[cfg(target_pointer_width = "64")]
 const MAX_CAPACITY: usize = (1 << 63) - 1;
@@ -129,6 +131,8 @@ impl IBytes {
             }
         }
     }
+    /// Like `Vec::set_len`: the first `new_len` bytes have to be initialized,
+    /// e.g. through `spare_capacity_mut`. Panics if `new_len` exceeds the capacity.
     #[inline(always)]
     pub unsafe fn set_len(&mut self, new_len: usize) {
         assert!(new_len <= self.capacity());
@@ -146,6 +150,18 @@ impl IBytes {
             unsafe { self.union.heap.cap }
         }
     }
+    /// The unused part of the buffer, like `Vec::spare_capacity_mut`.
+    ///
+    /// Write into it and call `set_len` to append the bytes without zeroing them first.
+    /// Inline that is `INLINE_CAPACITY - len` bytes, `reserve` for more.
+    #[inline]
+    pub fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.len();
+        let spare = self.capacity() - len;
+        unsafe {
+            slice::from_raw_parts_mut(self.as_mut_ptr().add(len) as *mut MaybeUninit<u8>, spare)
+        }
+    }
     /// un-inline the string and expand the capacity to `cap`.
     ///
     /// does nothing if it isn't inlined.
@@ -214,6 +230,39 @@ impl IBytes {
             self.resize(new_cap);
         }
     }
+
+    /// Make room for at least `additional` more bytes, like `Vec::try_reserve`.
+    ///
+    /// Returns an error instead of aborting if the capacity would exceed
+    /// `MAX_CAPACITY` or the allocation fails. `self` is unchanged in that case.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let len = self.len();
+        let needed = match len.checked_add(additional) {
+            Some(n) if n <= MAX_CAPACITY => n,
+            _ => return Err(capacity_overflow()),
+        };
+        if needed <= self.capacity() {
+            return Ok(());
+        }
+        unsafe {
+            if self.is_inline() {
+                let mut data = Vec::new();
+                data.try_reserve(needed)?;
+                let (ptr, _, cap) = vec_into_raw_parts(data);
+                ptr::copy_nonoverlapping(self.union.inline.data.as_ptr(), ptr, len);
+                self.union.heap = Heap { ptr, len, cap };
+                Ok(())
+            } else {
+                let heap = self.union.heap;
+                let mut data = Vec::from_raw_parts(heap.ptr, len, heap.cap);
+                // leaves `data` untouched on failure
+                let result = data.try_reserve(additional);
+                let (ptr, _, cap) = vec_into_raw_parts(data);
+                self.union.heap = Heap { ptr, len, cap };
+                result
+            }
+        }
+    }
     #[inline]
     pub fn push(&mut self, byte: u8) {
         self.extend_from_slice(&[byte]);
@@ -240,6 +289,18 @@ impl IBytes {
             self.set_len(new_len);
         }
     }
+    /// Move all bytes of `other` to the end of `self`, leaving `other` empty, like `Vec::append`.
+    ///
+    /// If `self` is empty and too small, the buffers are swapped instead of copying.
+    /// `other` keeps its capacity otherwise.
+    pub fn append(&mut self, other: &mut IBytes) {
+        if self.is_empty() && other.len() > self.capacity() {
+            mem::swap(self, other);
+        } else {
+            self.extend_from_slice(other.as_slice());
+        }
+        unsafe { other.set_len(0) }
+    }
 }
 
 impl Drop for IBytes {
@@ -295,12 +356,20 @@ impl convert::From<Vec<u8>> for IBytes {
         }
     }
 }
+/// `TryReserveError` can't be constructed directly
+#[cold]
+fn capacity_overflow() -> TryReserveError {
+    Vec::<u8>::new().try_reserve(usize::MAX).unwrap_err()
+}
+
 impl convert::From<alloc::string::String> for IBytes {
     #[inline]
     fn from(s: alloc::string::String) -> IBytes {
         IBytes::from(s.into_bytes())
     }
 }
+/// Hands over the buffer if it is on the heap, without copying. Inline bytes are copied
+/// into a new `Vec`.
 impl convert::Into<Vec<u8>> for IBytes {
     #[inline]
     fn into(mut self) -> Vec<u8> {
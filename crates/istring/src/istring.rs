@@ -5,6 +5,11 @@ use core::ops::{self, Index, Add, AddAssign};
 use core::borrow::Borrow;
 use alloc::{string::String, vec::Vec};
 use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::collections::TryReserveError;
 
 #[cfg(feature="ts")]
 use alloc::{borrow::ToOwned, format};
@@ -21,6 +26,15 @@ pub struct IString {
 
 
 impl IString {
+    /// The longest string (in bytes) stored without a heap allocation:
+    /// 23 on 64 bit targets, 11 on 32 bit.
+    pub const INLINE_CAPACITY: usize = crate::ibytes::INLINE_CAPACITY;
+
+    /// Whether `s` fits into `INLINE_CAPACITY`.
+    #[inline]
+    pub const fn fits_inline(s: &str) -> bool {
+        s.len() <= Self::INLINE_CAPACITY
+    }
     #[inline]
     pub fn new() -> IString {
         IString {
@@ -58,12 +72,34 @@ impl IString {
     pub fn shrink(&mut self) {
         self.bytes.shrink();
     }
+
+    /// Same as `shrink`, named like `String::shrink_to_fit`.
+    #[inline(always)]
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink();
+    }
+
+    /// A clone that is inline if the content fits, and without spare capacity otherwise.
+    ///
+    /// `clone` currently does the same: a heap-backed string short enough to be inline,
+    /// e.g. after `move_to_heap` or `truncate`, is cloned inline. Use this where the
+    /// compact result is relied upon.
+    #[inline]
+    pub fn clone_compact(&self) -> IString {
+        IString::from(self.as_str())
+    }
     
     #[inline]
     pub fn push_str(&mut self, s: &str) {
         self.bytes.extend_from_slice(s.as_bytes());
     }
     
+    /// Move `other` to the end of `self`, leaving it empty. See `IBytes::append`.
+    #[inline]
+    pub fn append(&mut self, other: &mut IString) {
+        self.bytes.append(&mut other.bytes);
+    }
+
     #[inline(always)]
     pub unsafe fn from_raw_parts(buf: *mut u8, length: usize, capacity: usize) -> IString {
         String::from_raw_parts(buf, length, capacity).into()
@@ -78,6 +114,12 @@ impl IString {
     pub fn reserve_exact(&mut self, additional: usize) {
         self.bytes.reserve_exact(additional);
     }
+
+    /// See `IBytes::try_reserve`.
+    #[inline]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.bytes.try_reserve(additional)
+    }
     
     #[inline]
     pub fn push(&mut self, ch: char) {
@@ -92,6 +134,82 @@ impl IString {
         }
     }
 
+    /// Keep at most `max_chars` characters. Unlike `truncate` this can't split a character.
+    pub fn truncate_chars(&mut self, max_chars: usize) {
+        if let Some((end, _)) = self.as_str().char_indices().nth(max_chars) {
+            unsafe { self.set_len(end) }
+        }
+    }
+
+    /// Like `str::repeat`. Allocates once, and not at all if the result fits inline.
+    ///
+    /// Panics if the length overflows.
+    pub fn repeat(&self, n: usize) -> IString {
+        let len = self.len().checked_mul(n).expect("capacity overflow");
+        let mut out = IString::with_capacity(len);
+        for _ in 0..n {
+            out.push_str(self);
+        }
+        out
+    }
+
+    /// Like `str::split`, but yields owned pieces.
+    ///
+    /// Pieces that fit inline don't allocate.
+    pub fn split_iter(&self, sep: char) -> impl Iterator<Item = IString> + '_ {
+        self.as_str().split(sep).map(IString::from)
+    }
+
+    /// Like `str::lines`, but yields owned lines.
+    pub fn lines_owned(&self) -> impl Iterator<Item = IString> + '_ {
+        self.as_str().lines().map(IString::from)
+    }
+
+    /// Like `String::from_utf8_unchecked`.
+    ///
+    /// # Safety
+    /// `bytes` has to be valid UTF-8.
+    #[inline]
+    pub unsafe fn from_utf8_unchecked(bytes: IBytes) -> IString {
+        IString { bytes }
+    }
+
+    /// Like `String::as_mut_vec`, but the length can't change.
+    ///
+    /// # Safety
+    /// The content has to be valid UTF-8 when the borrow ends.
+    #[inline]
+    pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.bytes.as_mut_slice()
+    }
+
+    /// Like `String::into_boxed_str`. A heap buffer is kept if it has no spare capacity.
+    #[inline]
+    pub fn into_boxed_str(self) -> Box<str> {
+        let s: String = self.into();
+        s.into_boxed_str()
+    }
+
+    /// Copy a shared string. Short strings are stored inline.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn from_arc_str(s: &Arc<str>) -> IString {
+        IString::from(&**s)
+    }
+
+    /// Copy into a shared string, e.g. to hand it to several threads.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    pub fn into_arc_str(self) -> Arc<str> {
+        Arc::from(self.as_str())
+    }
+
+    /// Copy into a reference counted string.
+    #[inline]
+    pub fn into_rc_str(self) -> Rc<str> {
+        Rc::from(self.as_str())
+    }
+
     pub fn from_utf8(bytes: IBytes) -> Result<IString, FromUtf8Error<IBytes>> {
         match str::from_utf8(bytes.as_slice()) {
             Ok(_) => Ok(IString { bytes }),
@@ -101,7 +219,39 @@ impl IString {
             })
         }
     }
+
+    /// The canonical composition (NFC) of the string, e.g. to compare text that may
+    /// contain decomposed accents. A copy if it is in NFC already.
+    #[cfg(feature="unicode-norm")]
+    pub fn nfc(&self) -> IString {
+        if unicode_normalization::is_nfc(self.as_str()) {
+            self.clone()
+        } else {
+            unicode_normalization::UnicodeNormalization::nfc(self.as_str()).collect()
+        }
+    }
+
+    /// The compatibility composition (NFKC) of the string, which also folds ligatures
+    /// and width variants. A copy if it is in NFKC already.
+    #[cfg(feature="unicode-norm")]
+    pub fn nfkc(&self) -> IString {
+        if unicode_normalization::is_nfkc(self.as_str()) {
+            self.clone()
+        } else {
+            unicode_normalization::UnicodeNormalization::nfkc(self.as_str()).collect()
+        }
+    }
+}
+/// Concatenate `parts`, allocating at most once.
+pub fn concat(parts: &[&str]) -> IString {
+    let len = parts.iter().map(|s| s.len()).sum();
+    let mut out = IString::with_capacity(len);
+    for s in parts {
+        out.push_str(s);
+    }
+    out
 }
+
 impl<'a> convert::From<&'a str> for IString {
     #[inline]
     fn from(s: &'a str) -> IString {
@@ -118,6 +268,13 @@ impl convert::From<String> for IString {
         }
     }
 }
+/// Takes over the allocation.
+impl convert::From<Box<str>> for IString {
+    #[inline]
+    fn from(s: Box<str>) -> IString {
+        IString::from(s.into_string())
+    }
+}
 impl<'a> convert::From<Cow<'a, str>> for IString {
     #[inline]
     fn from(s: Cow<'a, str>) -> IString {
@@ -127,6 +284,23 @@ impl<'a> convert::From<Cow<'a, str>> for IString {
         }
     }
 }
+impl<'a> convert::TryFrom<&'a [u8]> for IString {
+    type Error = FromUtf8Error<IBytes>;
+    /// Short input is stored inline.
+    #[inline]
+    fn try_from(bytes: &'a [u8]) -> Result<IString, Self::Error> {
+        IString::from_utf8(IBytes::from(bytes))
+    }
+}
+impl convert::TryFrom<Vec<u8>> for IString {
+    type Error = FromUtf8Error<IBytes>;
+    /// Keeps the allocation, like `From<String>`.
+    #[inline]
+    fn try_from(bytes: Vec<u8>) -> Result<IString, Self::Error> {
+        IString::from_utf8(IBytes::from(bytes))
+    }
+}
+/// Like `Into<Vec<u8>>` for `IBytes`: a heap buffer is handed over with its capacity.
 impl convert::Into<String> for IString {
     #[inline]
     fn into(self) -> String {
@@ -177,6 +351,22 @@ impl<'a> Extend<Cow<'a, str>> for IString {
         }
     }
 }
+impl Extend<String> for IString {
+    #[inline(always)]
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(&s)
+        }
+    }
+}
+impl Extend<IString> for IString {
+    #[inline(always)]
+    fn extend<I: IntoIterator<Item = IString>>(&mut self, iter: I) {
+        for s in iter {
+            self.push_str(&s)
+        }
+    }
+}
 
 impl Default for IString {
     #[inline(always)]
@@ -194,6 +384,33 @@ impl<'a> Add<&'a str> for IString {
         self
     }
 }
+impl Add<IString> for IString {
+    type Output = IString;
+
+    #[inline(always)]
+    fn add(mut self, other: IString) -> IString {
+        self.push_str(&other);
+        self
+    }
+}
+impl<'a> Add<&'a IString> for IString {
+    type Output = IString;
+
+    #[inline(always)]
+    fn add(mut self, other: &IString) -> IString {
+        self.push_str(other);
+        self
+    }
+}
+impl Add<String> for IString {
+    type Output = IString;
+
+    #[inline(always)]
+    fn add(mut self, other: String) -> IString {
+        self.push_str(&other);
+        self
+    }
+}
 impl<'a> AddAssign<&'a str> for IString {
     #[inline]
     fn add_assign(&mut self, other: &str) {
@@ -215,5 +432,29 @@ impl<'a> FromIterator<&'a str> for IString {
         s
     }
 }
+/// Reuses the allocation of the first string.
+impl FromIterator<String> for IString {
+    fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=String> {
+        let mut iter = iter.into_iter();
+        let mut s = match iter.next() {
+            Some(first) => IString::from(first),
+            None => return IString::new()
+        };
+        s.extend(iter);
+        s
+    }
+}
+/// Reuses the allocation of the first string, if it is on the heap.
+impl FromIterator<IString> for IString {
+    fn from_iter<T>(iter: T) -> Self where T: IntoIterator<Item=IString> {
+        let mut iter = iter.into_iter();
+        let mut s = match iter.next() {
+            Some(first) => first,
+            None => return IString::new()
+        };
+        s.extend(iter);
+        s
+    }
+}
 
 define_common_string!(IString, IStringUnion);
@@ -34,10 +34,10 @@ pub mod tiny;
 #[cfg(feature="serialize")]
 use core::marker::PhantomData;
 
-pub use crate::istring::IString;
+pub use crate::istring::{IString, concat};
 pub use crate::ibytes::IBytes;
-pub use crate::small::{SmallBytes, SmallString};
-pub use crate::tiny::{TinyBytes, TinyString};
+pub use crate::small::{SmallBytes, SmallBytesBuilder, SmallString, SmallStringBuilder};
+pub use crate::tiny::{TinyBytes, TinyString, TooLong};
 
 #[derive(Debug)]
 pub struct FromUtf8Error<T> {
@@ -227,4 +227,528 @@ mod tests {
         assert_eq!(s2, p2);
     }
 
+    #[test]
+    fn test_ascii_case() {
+        let mut s = IString::from("Grüße, WORLD");
+        assert!(s.bytes.is_inline());
+        s.make_ascii_lowercase();
+        assert_eq!(s, "grüße, world");
+        assert!(s.bytes.is_inline());
+        assert_eq!(s.to_ascii_uppercase(), "GRüßE, WORLD");
+
+        let long = IString::from("ÄÖÜ and a much longer Text on the heap");
+        assert_eq!(long.to_ascii_lowercase(), "ÄÖÜ and a much longer text on the heap");
+
+        let mut s = crate::small::SmallString::from("Straße");
+        s.make_ascii_uppercase();
+        assert_eq!(s, "STRAßE");
+        assert_eq!(s.to_ascii_lowercase(), "straße");
+    }
+
+    #[test]
+    fn test_collect_strings() {
+        let mut first = String::with_capacity(64);
+        first.push_str("a string long enough for the heap, ");
+        let parts: Vec<String> = vec![first, "foo ".into(), "bar".into()];
+        let concat = parts.concat();
+        let first = parts[0].as_ptr();
+
+        let s: IString = parts.into_iter().collect();
+        assert_eq!(s, concat.as_str());
+        assert!(!s.bytes.is_inline());
+        // the buffer of the first string was large enough and reused
+        assert_eq!(s.as_ptr(), first);
+
+        let parts = vec![IString::from("foo"), IString::from(" "), IString::from("bar")];
+        let s: IString = parts.into_iter().collect();
+        assert_eq!(s, "foo bar");
+
+        let mut s = IString::new();
+        s.extend(vec![String::from("a"), String::from("b")]);
+        assert_eq!(s, "ab");
+    }
+
+    #[test]
+    fn test_unchecked_access() {
+        let short = IString::from("inline");
+        let long = IString::from("this one is too long to be inlined");
+        assert!(short.bytes.is_inline());
+        assert!(!long.bytes.is_inline());
+
+        // checked once, then accessed without branching in the loop
+        let mut checked = 0;
+        let mut unchecked = 0;
+        for _ in 0..1000 {
+            checked += short.as_str().len() + long.as_str().len();
+            unsafe {
+                unchecked += short.as_inline_str_unchecked().len() + long.as_heap_str_unchecked().len();
+            }
+        }
+        assert_eq!(checked, unchecked);
+        unsafe {
+            assert_eq!(short.as_inline_str_unchecked(), "inline");
+            assert_eq!(long.as_heap_str_unchecked(), long.as_str());
+        }
+
+        let small = SmallString::from("small");
+        assert_eq!(unsafe { small.as_inline_str_unchecked() }, "small");
+    }
+
+    #[test]
+    fn test_small_with_capacity() {
+        let data: Vec<u8> = (0..40).collect();
+        let mut b = SmallBytes::with_capacity(data.len());
+        let ptr = b.as_slice().as_ptr();
+        for &byte in &data {
+            b.push(byte);
+            // no reallocation while filling
+            assert_eq!(b.as_slice().as_ptr(), ptr);
+        }
+        let bytes = b.finish();
+        assert!(!bytes.is_inline());
+        assert_eq!(bytes.as_slice(), &data[..]);
+        assert_eq!(bytes.as_slice().as_ptr(), ptr);
+
+        let mut b = SmallBytes::with_capacity(4);
+        b.extend_from_slice(b"abcd");
+        assert!(b.finish().is_inline());
+
+        let mut s = SmallString::with_capacity(5);
+        s.push_str("abc");
+        s.push('ß');
+        assert_eq!(s.finish(), "abcß");
+
+        // exceeding the capacity still works
+        let mut s = SmallString::with_capacity(2);
+        s.push_str("longer than the inline capacity");
+        assert_eq!(s.finish(), "longer than the inline capacity");
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut s = IString::from("inline");
+        assert!(s.try_reserve(usize::MAX).is_err());
+        assert!(s.try_reserve(usize::MAX / 2).is_err());
+        assert!(s.bytes.is_inline());
+        assert_eq!(s, "inline");
+
+        s.try_reserve(100).unwrap();
+        assert!(!s.bytes.is_inline());
+        assert!(s.bytes.capacity() >= 106);
+        assert_eq!(s, "inline");
+
+        assert!(s.try_reserve(usize::MAX).is_err());
+        assert_eq!(s, "inline");
+        s.push_str(" and on the heap");
+        assert_eq!(s, "inline and on the heap");
+    }
+
+    #[test]
+    fn test_add() {
+        let a = IString::from("short");
+        let b = IString::from(" and a fragment that does not fit inline");
+        let c = IString::from("!");
+        let s = a.clone() + b.clone() + &c;
+        assert_eq!(s, "short and a fragment that does not fit inline!");
+        assert!(!s.bytes.is_inline());
+
+        // two inline halves that only fit on the heap together
+        let s = IString::from("0123456789abcde") + IString::from("fghijklmnopqrst");
+        assert_eq!(s, "0123456789abcdefghijklmnopqrst");
+        assert!(!s.bytes.is_inline());
+
+        // appending to a heap string keeps its buffer
+        let mut long = IString::with_capacity(128);
+        long.push_str("this string lives on the heap already");
+        let ptr = long.as_ptr();
+        let long = long + &a + String::from(", ") + c;
+        assert_eq!(long.as_ptr(), ptr);
+        assert_eq!(long, "this string lives on the heap alreadyshort, !");
+
+        assert!((IString::from("a") + IString::from("b")).bytes.is_inline());
+    }
+
+    #[test]
+    fn test_hash_borrow() {
+        use std::collections::HashMap;
+        use std::hash::{BuildHasher, RandomState};
+
+        // lengths around the inline capacities of all types
+        let keys: std::vec::Vec<std::string::String> = [0, 1, 7, 14, 15, 16, 22, 23, 24, 40]
+            .iter()
+            .map(|&n| "abcdefghijklmnopqrstuvwxyz0123456789ABCDEF"[..n].into())
+            .collect();
+
+        let state = RandomState::new();
+        let mut istrings = HashMap::new();
+        let mut smalls = HashMap::new();
+        let mut tinies = HashMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            let s = IString::from(key.as_str());
+            std::assert_eq!(state.hash_one(&s), state.hash_one(key.as_str()));
+            istrings.insert(s, i);
+
+            let s = SmallString::from(key.as_str());
+            std::assert_eq!(state.hash_one(&s), state.hash_one(key.as_str()));
+            smalls.insert(s, i);
+
+            if let Some(s) = TinyString::new(key) {
+                std::assert_eq!(state.hash_one(s), state.hash_one(key.as_str()));
+                tinies.insert(s, i);
+            }
+        }
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(istrings.get(key.as_str()), Some(&i));
+            assert_eq!(smalls.get(key.as_str()), Some(&i));
+            if TinyString::new(key).is_some() {
+                assert_eq!(tinies.get(key.as_str()), Some(&i));
+            }
+        }
+        assert!(!tinies.is_empty());
+        assert_eq!(istrings.get("missing"), None);
+    }
+
+    #[test]
+    fn test_str_search() {
+        let inline = IString::from("Hello World");
+        let heap = IString::from("Hello World, this does not fit inline");
+        assert!(inline.bytes.is_inline() && !heap.bytes.is_inline());
+
+        for s in [&inline, &heap] {
+            assert!(s.starts_with("Hello"));
+            assert!(!s.starts_with("World"));
+            assert!(s.starts_with(""));
+            assert!(s.contains("o W"));
+            assert!(!s.contains("xyz"));
+            assert_eq!(s.find("World"), Some(6));
+            assert_eq!(s.find("xyz"), None);
+        }
+        assert!(inline.ends_with("World"));
+        assert!(heap.ends_with("inline"));
+        assert!(!heap.ends_with("World"));
+        assert_eq!(heap.find("inline"), Some(31));
+
+        let small = SmallString::from("Hello World");
+        assert!(small.starts_with("Hell") && small.ends_with("rld") && small.contains("lo W"));
+        assert_eq!(small.find("o"), Some(4));
+    }
+
+    #[test]
+    fn test_split_owned() {
+        let list = IString::from("red, green,blue,,a rather long name that lives on the heap");
+        let pieces: Vec<IString> = list.split_iter(',').collect();
+        drop(list);
+        assert_eq!(pieces, ["red", " green", "blue", "", "a rather long name that lives on the heap"]);
+        assert!(pieces[..4].iter().all(|s| s.bytes.is_inline()));
+        assert!(!pieces[4].bytes.is_inline());
+
+        let text = IString::from("first\nsecond\r\n\nlast");
+        let lines: Vec<IString> = text.lines_owned().collect();
+        assert_eq!(lines, ["first", "second", "", "last"]);
+        assert!(lines.iter().all(|s| s.bytes.is_inline()));
+    }
+
+    #[test]
+    fn test_repeat() {
+        let s = IString::from("-=-").repeat(4);
+        // 12 bytes stay inline
+        assert_eq!(s, "-=--=--=--=-");
+        assert!(s.bytes.is_inline());
+
+        let s = IString::from("abc").repeat(10);
+        assert_eq!(s, "abc".repeat(10));
+        assert!(!s.bytes.is_inline());
+        assert_eq!(s.capacity(), 30);
+
+        assert_eq!(IString::from("abc").repeat(0), "");
+
+        let s = concat(&["a", ", ", "b"]);
+        assert_eq!(s, "a, b");
+        assert!(s.bytes.is_inline());
+        let s = concat(&["a fragment that", " does not fit inline"]);
+        assert_eq!(s, "a fragment that does not fit inline");
+        assert_eq!(s.capacity(), s.len());
+    }
+
+    #[test]
+    fn test_unchecked() {
+        for text in ["inline", "long enough to be stored on the heap"] {
+            let mut s = IString::from(text);
+            // ASCII stays valid UTF-8
+            unsafe { s.as_bytes_mut() }.make_ascii_uppercase();
+            assert_eq!(s, text.to_uppercase().as_str());
+
+            let bytes = IBytes::from(text.as_bytes());
+            let s = unsafe { IString::from_utf8_unchecked(bytes) };
+            assert_eq!(s, text);
+            assert_eq!(s.len(), text.len());
+        }
+    }
+    #[test]
+    fn test_truncate_chars() {
+        let text = "naïve café ünïcode";
+        let mut s = IString::from(text);
+        s.truncate_chars(5);
+        assert_eq!(s, "naïve");
+        assert_eq!(s.len(), text.char_indices().nth(5).unwrap().0);
+
+        let mut s = IString::from("short");
+        s.truncate_chars(10);
+        assert_eq!(s, "short");
+        s.truncate_chars(0);
+        assert_eq!(s, "");
+    }
+    #[test]
+    fn test_try_from_bytes() {
+        let s = IString::try_from(&b"ascii"[..]).unwrap();
+        assert_eq!(s, "ascii");
+        assert!(s.bytes.is_inline());
+
+        let text = "Grüße, 世界";
+        let s = IString::try_from(text.as_bytes()).unwrap();
+        assert_eq!(s, text);
+        let s = IString::try_from(text.as_bytes().to_vec()).unwrap();
+        assert_eq!(s, text);
+
+        let invalid = b"ab\xffcd";
+        let e = IString::try_from(&invalid[..]).unwrap_err();
+        assert_eq!(e.utf8_error().valid_up_to(), 2);
+        assert_eq!(e.as_bytes(), invalid);
+        let e = IString::try_from(invalid.to_vec()).unwrap_err();
+        assert_eq!(&*e.into_bytes(), invalid);
+    }
+    #[test]
+    fn test_clone_compact() {
+        let mut s = IString::from("short");
+        s.move_to_heap(64);
+        assert!(!s.bytes.is_inline());
+        let c = s.clone_compact();
+        assert_eq!(c, "short");
+        assert!(c.bytes.is_inline());
+
+        let mut s = IString::from("a string that is stored on the heap");
+        s.reserve(100);
+        let c = s.clone_compact();
+        assert_eq!(c, s);
+        assert_eq!(c.capacity(), c.len());
+
+        s.truncate(5);
+        s.shrink_to_fit();
+        assert!(s.bytes.is_inline());
+    }
+
+    #[test]
+    fn test_small_into_string() {
+        // too long to be inline
+        let s = SmallString::from("a string that is stored on the heap");
+        let ptr = s.as_str().as_ptr();
+        let string = s.into_string();
+        assert_eq!(string, "a string that is stored on the heap");
+        // the buffer was handed over
+        assert_eq!(string.as_ptr(), ptr);
+
+        assert_eq!(SmallString::from("short").into_string(), "short");
+        assert_eq!(SmallBytes::from(&b"bytes"[..]).into_vec(), b"bytes");
+
+        let lossy = SmallString::from_utf8_lossy(b"ab\xffc");
+        assert_eq!(lossy, "ab\u{FFFD}c");
+        assert_eq!(SmallString::from_utf8_lossy(b"valid"), "valid");
+        let long = SmallString::from_utf8_lossy(b"\xf0\x28 a longer invalid string");
+        assert_eq!(long, "\u{FFFD}( a longer invalid string");
+    }
+
+    #[test]
+    fn test_append() {
+        let mut a = IString::from("a string that is stored on the heap");
+        let mut b = IString::from(", and another one on the heap");
+        let cap = b.capacity();
+        a.append(&mut b);
+        assert_eq!(a, "a string that is stored on the heap, and another one on the heap");
+        assert!(b.is_empty());
+        assert_eq!(b.capacity(), cap);
+
+        // an empty destination takes over the buffer
+        let mut c = IString::new();
+        let ptr = a.as_ptr();
+        c.append(&mut a);
+        assert_eq!(c.as_ptr(), ptr);
+        assert_eq!(c, "a string that is stored on the heap, and another one on the heap");
+        assert!(a.is_empty());
+
+        let mut d = IString::from("ab");
+        d.append(&mut IString::from("cd"));
+        assert_eq!(d, "abcd");
+        assert!(d.bytes.is_inline());
+    }
+
+    #[test]
+    fn test_from_str() {
+        use crate::TooLong;
+
+        let s: IString = "a string that is stored on the heap".parse().unwrap();
+        assert_eq!(s, "a string that is stored on the heap");
+        assert_eq!("abc".parse::<IString>().unwrap(), "abc");
+        assert_eq!("Straße".parse::<SmallString>().unwrap(), "Straße");
+
+        assert_eq!("abc".parse::<crate::TinyString>().unwrap(), "abc");
+        assert_eq!("1234567".parse::<crate::TinyString>().unwrap(), "1234567");
+        assert_eq!("12345678".parse::<crate::TinyString>().unwrap_err(), TooLong { len: 8 });
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        // a heap string keeps its buffer both ways
+        let mut string = String::with_capacity(64);
+        string.push_str("a string that is stored on the heap");
+        let ptr = string.as_ptr();
+        let s = IString::from(string);
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s.capacity(), 64);
+        let string: String = s.into();
+        assert_eq!(string.as_ptr(), ptr);
+        assert_eq!(string.capacity(), 64);
+        assert_eq!(string, "a string that is stored on the heap");
+
+        // inline content has to be copied out
+        let s = IString::from("short");
+        assert!(s.bytes.is_inline());
+        let string: String = s.into();
+        assert_eq!(string, "short");
+    }
+
+    #[test]
+    fn test_box_and_arc() {
+        let boxed: Box<str> = "a string that is stored on the heap".into();
+        let ptr = boxed.as_ptr();
+        let s = IString::from(boxed);
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(s, "a string that is stored on the heap");
+        // no spare capacity, the buffer goes back as it is
+        let boxed = s.into_boxed_str();
+        assert_eq!(boxed.as_ptr(), ptr);
+
+        assert_eq!(IString::from(Box::<str>::from("short")), "short");
+
+        let shared = IString::from("shared").into_arc_str();
+        assert_eq!(&*shared, "shared");
+        assert_eq!(IString::from_arc_str(&shared), "shared");
+        assert_eq!(&*IString::from("counted").into_rc_str(), "counted");
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    const _: () = {
+        assert!(IString::INLINE_CAPACITY == 23);
+        assert!(SmallString::INLINE_CAPACITY == 15);
+        assert!(TinyString::CAPACITY == 7);
+    };
+
+    #[test]
+    fn test_fits_inline() {
+        let s = "a".repeat(IString::INLINE_CAPACITY);
+        assert!(IString::fits_inline(&s));
+        assert!(!IString::fits_inline(&(s.clone() + "a")));
+        assert!(SmallString::fits_inline(&s[..SmallString::INLINE_CAPACITY]));
+        assert!(!SmallString::fits_inline(&s[..SmallString::INLINE_CAPACITY + 1]));
+        assert!(TinyString::fits_inline("tiny"));
+        assert!(!TinyString::fits_inline("not tiny"));
+        assert!(TinyString::new(&s[..TinyString::CAPACITY]).is_some());
+    }
+
+    #[cfg(feature="unicode-norm")]
+    #[test]
+    fn test_normalization() {
+        let decomposed = IString::from("caf\u{65}\u{301}");
+        let composed = decomposed.nfc();
+        assert_eq!(composed, "caf\u{e9}");
+        assert_eq!(composed.len(), 5);
+        assert_eq!(composed.nfc(), composed);
+        assert_eq!(decomposed.nfkc(), composed);
+        assert_eq!(IString::from("\u{fb01}le").nfkc(), "file");
+        assert_eq!(IString::from("\u{fb01}le").nfc(), "\u{fb01}le");
+    }
+
+    #[test]
+    fn test_spare_capacity() {
+        let mut b = IBytes::new();
+        b.push(b'a');
+        let spare = b.spare_capacity_mut();
+        assert_eq!(spare.len(), IString::INLINE_CAPACITY - 1);
+        for (i, byte) in spare[..4].iter_mut().enumerate() {
+            byte.write(b'b' + i as u8);
+        }
+        unsafe { b.set_len(5) };
+        assert!(b.is_inline());
+        assert_eq!(b.as_slice(), b"abcde");
+
+        b.reserve(100);
+        assert!(!b.is_inline());
+        let spare = b.spare_capacity_mut();
+        assert!(spare.len() >= 100);
+        for byte in &mut spare[..100] {
+            byte.write(b'z');
+        }
+        unsafe { b.set_len(105) };
+        assert_eq!(&b.as_slice()[..6], b"abcdez");
+        assert_eq!(b.as_slice()[104], b'z');
+        assert_eq!(b.spare_capacity_mut().len(), b.capacity() - 105);
+    }
+
+    #[test]
+    fn test_ord_borrow() {
+        use std::collections::BTreeMap;
+        use std::ops::Bound::{Excluded, Included, Unbounded};
+
+        // `"a".."m"` is no `RangeBounds<str>`, a pair of bounds is
+        let a_to_m = (Included("a"), Excluded("m"));
+
+        // inline and heap keys, and non-ASCII after all of ASCII
+        let keys = ["", "a", "abc", "b", "banana", "lemon", "m", "mango", "zebra", "\u{e4}pfel",
+            "a rather long key that is stored on the heap", "lots of keys that start with an l"];
+        let mut istrings = BTreeMap::new();
+        let mut smalls = BTreeMap::new();
+        let mut tinies = BTreeMap::new();
+        let mut strings = BTreeMap::new();
+        for (i, &key) in keys.iter().enumerate() {
+            istrings.insert(IString::from(key), i);
+            smalls.insert(SmallString::from(key), i);
+            if let Some(s) = TinyString::new(key) {
+                tinies.insert(s, i);
+            }
+            strings.insert(std::string::String::from(key), i);
+        }
+
+        let expected: std::vec::Vec<usize> = strings.range::<str, _>(a_to_m).map(|(_, &i)| i).collect();
+        std::assert_eq!(expected.len(), 7);
+        let found: std::vec::Vec<usize> = istrings.range::<str, _>(a_to_m).map(|(_, &i)| i).collect();
+        std::assert_eq!(found, expected);
+        let found: std::vec::Vec<usize> = smalls.range::<str, _>(a_to_m).map(|(_, &i)| i).collect();
+        std::assert_eq!(found, expected);
+        let found: std::vec::Vec<&str> = tinies.range::<str, _>(a_to_m).map(|(k, _)| k.as_str()).collect();
+        std::assert_eq!(found, ["a", "abc", "b", "banana", "lemon"]);
+
+        std::assert_eq!(istrings.range::<str, _>((Included("zebra"), Unbounded)).count(), 2);
+        for &key in keys.iter() {
+            std::assert_eq!(istrings.get(key), strings.get(key));
+        }
+        // the same order as `String`
+        std::assert!(istrings.keys().map(|k| k.as_str()).eq(strings.keys().map(|k| k.as_str())));
+    }
+
+    #[test]
+    fn test_tiny_try_from() {
+        use crate::{TinyBytes, TooLong};
+
+        std::assert_eq!(TinyString::try_from_str("1234567").unwrap(), "1234567");
+        std::assert_eq!(TinyString::try_from_str("12345678").unwrap_err(), TooLong { len: 8 });
+        std::assert_eq!(TinyBytes::try_from_slice(b"1234567").unwrap(), b"1234567");
+        std::assert_eq!(TinyBytes::try_from_slice(b"12345678").unwrap_err(), TooLong { len: 8 });
+
+        // empty is not absent
+        const EMPTY: TinyString = TinyString::empty();
+        std::assert!(EMPTY.is_empty());
+        std::assert_eq!(Some(EMPTY), Some(TinyString::new("").unwrap()));
+        std::assert_ne!(Some(EMPTY), None);
+        std::assert!(TinyBytes::empty().is_empty());
+        std::assert_eq!(core::mem::size_of::<Option<TinyString>>(), 8);
+    }
 }
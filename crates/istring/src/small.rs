@@ -4,6 +4,7 @@ use core::ops::{self, Index};
 use core::borrow::Borrow;
 use alloc::{string::String, vec::Vec};
 use alloc::boxed::Box;
+use alloc::borrow::Cow;
 use crate::FromUtf8Error;
 
 #[cfg(feature="ts")]
@@ -13,9 +14,9 @@ const IS_INLINE: u8 = 1 << 7;
 const LEN_MASK: u8 = !IS_INLINE;
 
 #[cfg(target_pointer_width="64")]
-const INLINE_CAPACITY: usize = 15;
+pub(crate) const INLINE_CAPACITY: usize = 15;
 #[cfg(target_pointer_width="32")]
-const INLINE_CAPACITY: usize = 7;
+pub(crate) const INLINE_CAPACITY: usize = 7;
 
 #[allow(unused)]
 #[cfg(target_pointer_width="64")]
@@ -137,6 +138,26 @@ fn test_layout() {
     assert_eq!(heap.len, MAX_CAPACITY + 1);
 }
 
+#[test]
+fn test_default() {
+    #[derive(Default)]
+    struct Named {
+        name: SmallString,
+        tag: crate::TinyString,
+    }
+    let n = Named::default();
+    assert!(n.name.is_empty());
+    assert!(n.name.bytes.is_inline());
+    assert_eq!(n.name, "");
+    assert!(n.tag.is_empty());
+    assert_eq!(n.tag.as_bytes(), b"");
+
+    assert!(SmallBytes::default().is_empty());
+    assert!(!SmallString::from("x").is_empty());
+    assert!(!crate::TinyString::from('x').is_empty());
+    assert!(crate::IString::default().is_empty());
+}
+
 #[inline(always)]
 fn box_slice(s: &[u8]) -> Box<[u8]> {
     Box::from(s)
@@ -163,6 +184,83 @@ impl SmallBytes {
             )
         }
     }
+    /// Start building a `SmallBytes` of (up to) `capacity` bytes.
+    ///
+    /// The heap buffer is allocated upfront if `capacity > INLINE_CAPACITY`,
+    /// so pushing exactly `capacity` bytes does not reallocate.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> SmallBytesBuilder {
+        SmallBytesBuilder::with_capacity(capacity)
+    }
+    /// Convert into a `Vec<u8>`. Heap backed bytes hand over their buffer without copying.
+    #[inline]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.into()
+    }
+}
+
+/// Builder for a `SmallBytes`, which can't grow once created.
+pub enum SmallBytesBuilder {
+    Inline(Inline),
+    Heap(Vec<u8>),
+}
+impl SmallBytesBuilder {
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> SmallBytesBuilder {
+        if capacity > INLINE_CAPACITY {
+            SmallBytesBuilder::Heap(Vec::with_capacity(capacity))
+        } else {
+            SmallBytesBuilder::Inline(Inline { data: [0; INLINE_CAPACITY], len: 0 })
+        }
+    }
+    #[inline]
+    pub fn len(&self) -> usize {
+        match *self {
+            SmallBytesBuilder::Inline(ref inline) => inline.len as usize,
+            SmallBytesBuilder::Heap(ref v) => v.len(),
+        }
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        match *self {
+            SmallBytesBuilder::Inline(ref inline) => &inline.data[.. inline.len as usize],
+            SmallBytesBuilder::Heap(ref v) => v.as_slice(),
+        }
+    }
+    #[inline]
+    pub fn push(&mut self, byte: u8) {
+        self.extend_from_slice(&[byte]);
+    }
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        match *self {
+            SmallBytesBuilder::Inline(ref mut inline) => {
+                let len = inline.len as usize;
+                let new_len = len + bytes.len();
+                if new_len <= INLINE_CAPACITY {
+                    inline.data[len .. new_len].copy_from_slice(bytes);
+                    inline.len = new_len as u8;
+                } else {
+                    let mut v = Vec::with_capacity(new_len);
+                    v.extend_from_slice(&inline.data[.. len]);
+                    v.extend_from_slice(bytes);
+                    *self = SmallBytesBuilder::Heap(v);
+                }
+            }
+            SmallBytesBuilder::Heap(ref mut v) => v.extend_from_slice(bytes),
+        }
+    }
+    /// shrinks the buffer, if less than the capacity was used
+    #[inline]
+    pub fn finish(self) -> SmallBytes {
+        match self {
+            SmallBytesBuilder::Inline(inline) => unsafe { SmallBytes::from_inline(inline) },
+            SmallBytesBuilder::Heap(v) => SmallBytes::from(v),
+        }
+    }
 }
 impl<'a> From<&'a [u8]> for SmallBytes {
     #[inline]
@@ -189,13 +287,44 @@ impl<'a> From<&'a [u8]> for SmallBytes {
     }
 }
 
+impl Default for SmallBytes {
+    #[inline(always)]
+    fn default() -> SmallBytes {
+        SmallBytes::new()
+    }
+}
+impl Default for SmallString {
+    #[inline(always)]
+    fn default() -> SmallString {
+        SmallString::new()
+    }
+}
+
 impl SmallString {
+    /// The longest string (in bytes) stored without a heap allocation:
+    /// 15 on 64 bit targets, 7 on 32 bit.
+    pub const INLINE_CAPACITY: usize = INLINE_CAPACITY;
+
+    /// Whether `s` fits into `INLINE_CAPACITY`.
+    #[inline]
+    pub const fn fits_inline(s: &str) -> bool {
+        s.len() <= Self::INLINE_CAPACITY
+    }
     #[inline(always)]
     pub fn new() -> SmallString {
         SmallString {
             bytes: SmallBytes::new()
         }
     }
+    /// Start building a `SmallString` of (up to) `capacity` bytes.
+    ///
+    /// See `SmallBytes::with_capacity`.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> SmallStringBuilder {
+        SmallStringBuilder {
+            bytes: SmallBytesBuilder::with_capacity(capacity)
+        }
+    }
     pub fn from_utf8(bytes: SmallBytes) -> Result<SmallString, FromUtf8Error<SmallBytes>> {
         match str::from_utf8(bytes.as_slice()) {
             Ok(_) => Ok(SmallString { bytes }),
@@ -205,7 +334,58 @@ impl SmallString {
             })
         }
     }
+    /// Like `String::from_utf8_lossy`, invalid sequences are replaced with U+FFFD.
+    pub fn from_utf8_lossy(bytes: &[u8]) -> SmallString {
+        match String::from_utf8_lossy(bytes) {
+            Cow::Borrowed(s) => SmallString::from(s),
+            Cow::Owned(s) => SmallString::from(s),
+        }
+    }
+    /// Convert into a `String`. A heap backed string hands over its buffer without copying.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.into()
+    }
+}
+/// Builder for a `SmallString`, which can't grow once created.
+pub struct SmallStringBuilder {
+    bytes: SmallBytesBuilder,
+}
+impl SmallStringBuilder {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(self.bytes.as_slice()) }
+    }
+    #[inline]
+    pub fn push_str(&mut self, s: &str) {
+        self.bytes.extend_from_slice(s.as_bytes());
+    }
+    #[inline]
+    pub fn push(&mut self, c: char) {
+        let mut buf = [0; 4];
+        self.push_str(c.encode_utf8(&mut buf));
+    }
+    #[inline]
+    pub fn finish(self) -> SmallString {
+        SmallString { bytes: self.bytes.finish() }
+    }
 }
+impl fmt::Write for SmallStringBuilder {
+    #[inline(always)]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
 impl Drop for SmallBytes {
     #[inline]
     fn drop(&mut self) {
@@ -1,19 +1,53 @@
-use core::{borrow::Borrow, fmt::Debug, hash::Hash, ops::Deref};
+use core::{borrow::Borrow, fmt::{self, Debug}, hash::Hash, ops::Deref, str::FromStr};
 
 #[cfg(feature="ts")]
 use alloc::{borrow::ToOwned, string::String, format};
 
-#[derive(Copy, Clone)]
+// the length of a `TinyBytes`, the values above 7 are the niche of `Option<TinyBytes>`
+#[derive(Copy, Clone, Default)]
+#[repr(u8)]
+enum Len {
+    #[default]
+    L0, L1, L2, L3, L4, L5, L6, L7
+}
+impl Len {
+    // `len` has to be at most 7
+    #[inline(always)]
+    const fn new(len: usize) -> Len {
+        match len {
+            0 => Len::L0,
+            1 => Len::L1,
+            2 => Len::L2,
+            3 => Len::L3,
+            4 => Len::L4,
+            5 => Len::L5,
+            6 => Len::L6,
+            _ => Len::L7,
+        }
+    }
+}
+
+/// Up to 7 bytes, stored inline in 8 bytes.
+///
+/// `Option<TinyBytes>` is 8 bytes as well, so `None` and `empty()` are both cheap and distinct.
+#[derive(Copy, Clone, Default)]
 #[cfg_attr(feature="ts", derive(ts_rs::TS), ts(type="Vec<u8>"))]
 pub struct TinyBytes {
-    len: u8,
+    len: Len,
     buf: [u8; 7]
 }
 
-#[derive(Copy, Clone)]
+/// A string of up to 7 bytes, see `TinyBytes`. `Option<TinyString>` is 8 bytes.
+#[derive(Copy, Clone, Default)]
 #[cfg_attr(feature="ts", derive(ts_rs::TS), ts(type="String"))]
 pub struct TinyString(TinyBytes);
 
+const _: () = {
+    assert!(core::mem::size_of::<TinyString>() == 8);
+    assert!(core::mem::size_of::<Option<TinyString>>() == 8);
+    assert!(core::mem::size_of::<Option<TinyBytes>>() == 8);
+};
+
 impl TinyBytes {
     #[inline]
     pub const fn new(s: &[u8]) -> Option<Self> {
@@ -29,22 +63,39 @@ impl TinyBytes {
         }
         
         Some(TinyBytes {
-            len: s.len() as u8,
+            len: Len::new(len),
             buf
         })
     }
+    /// No bytes, unlike `None`.
+    #[inline]
+    pub const fn empty() -> Self {
+        TinyBytes { len: Len::L0, buf: [0; 7] }
+    }
+    /// Like `new`, with the length in the error.
+    #[inline]
+    pub const fn try_from_slice(s: &[u8]) -> Result<Self, TooLong> {
+        match TinyBytes::new(s) {
+            Some(b) => Ok(b),
+            None => Err(TooLong { len: s.len() })
+        }
+    }
 
     #[inline(always)]
     pub fn as_bytes(&self) -> &[u8] {
         &**self
     }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        matches!(self.len, Len::L0)
+    }
 }
 impl Deref for TinyBytes {
     type Target = [u8];
 
     #[inline(always)]
     fn deref(&self) -> &Self::Target {
-        &self.buf[.. self.len as usize]
+        &self.buf[.. self.len as u8 as usize]
     }
 }
 impl Deref for TinyString {
@@ -77,25 +128,71 @@ impl AsRef<str> for TinyString {
 }
 
 impl TinyString {
+    /// The longest string (in bytes) a `TinyString` holds, on every target.
+    pub const CAPACITY: usize = 7;
+
+    /// Whether `s` fits into `CAPACITY`, i.e. `new` succeeds.
+    #[inline]
+    pub const fn fits_inline(s: &str) -> bool {
+        s.len() <= Self::CAPACITY
+    }
     pub const fn new(s: &str) -> Option<Self> {
         match TinyBytes::new(s.as_bytes()) {
             Some(b) => Some(TinyString(b)),
             None => None
         }
     }
+    /// The empty string, unlike `None`.
+    #[inline]
+    pub const fn empty() -> Self {
+        TinyString(TinyBytes::empty())
+    }
+    /// Like `new`, with the length in the error. Same as `from_str`, but `const`.
+    #[inline]
+    pub const fn try_from_str(s: &str) -> Result<Self, TooLong> {
+        match TinyBytes::try_from_slice(s.as_bytes()) {
+            Ok(b) => Ok(TinyString(b)),
+            Err(e) => Err(e)
+        }
+    }
     #[inline]
     pub fn as_str(&self) -> &str {
         &**self
     }
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The error of `TinyString::try_from_str` and `TinyBytes::try_from_slice`: more than 7 bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TooLong {
+    pub len: usize,
+}
+impl fmt::Display for TooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bytes do not fit in a TinyString (at most 7)", self.len)
+    }
+}
+impl core::error::Error for TooLong {}
+
+impl FromStr for TinyString {
+    type Err = TooLong;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<TinyString, TooLong> {
+        TinyString::try_from_str(s)
+    }
 }
 
 impl Debug for TinyBytes {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         (**self).fmt(f)
     }
 }
 impl Debug for TinyString {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         (**self).fmt(f)
     }
 }
@@ -169,7 +266,7 @@ impl From<char> for TinyString {
     #[inline]
     fn from(value: char) -> Self {
         let mut buf = [0; 7];
-        let len = value.encode_utf8(&mut buf).len() as u8;
+        let len = Len::new(value.encode_utf8(&mut buf).len());
         TinyString(TinyBytes { len, buf })
     }
 }
@@ -0,0 +1,516 @@
+use std::collections::HashMap;
+
+use image::RgbaImage;
+use pathfinder_content::{
+    effects::BlendMode,
+    fill::FillRule,
+    gradient::{Gradient, GradientGeometry, GradientWrap},
+    outline::{ContourIterFlags, Outline},
+    pattern::{Image, PatternSource},
+    segment::SegmentKind,
+};
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_renderer::{
+    paint::{Paint as PfPaint, PaintContents},
+    scene::{ClipPathId, DrawPathId, Scene},
+};
+use inkrender::{ClearMode, RenderOptions};
+use tiny_skia::{
+    Color, FilterQuality, GradientStop, IntSize, LinearGradient, Mask, Paint, Path, PathBuilder, Pixmap, Point,
+    RadialGradient, Shader, SpreadMode, Stroke, Transform,
+};
+
+use crate::{RasterizeError, RasterizeResult, Rasterizer, RenderBackendKind};
+
+/// Which rasterizer `AnyRasterizer::new` creates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RasterizeBackend {
+    /// OpenGL, fails without a context.
+    Gpu,
+    /// `CpuRasterizer`, works everywhere but is a lot slower.
+    Cpu,
+    /// OpenGL if a context can be created, the CPU otherwise.
+    #[default]
+    Auto,
+}
+
+/// Rasterizes scenes without a GPU, for headless machines.
+///
+/// Radial gradients start at a point, so shadings whose first circle has a radius look a
+/// little different. Render targets are not composited: what was drawn into them is drawn
+/// in place, build scenes with `Cache::set_offscreen(false)` to apply the opacity of groups.
+/// Of the `RenderOptions` only the dilation is used, the larger of its components.
+#[derive(Default)]
+pub struct CpuRasterizer {
+    // unsupported paints were reported
+    warned: bool,
+    rendered: bool,
+    render_options: RenderOptions,
+}
+
+impl CpuRasterizer {
+    pub fn new() -> Self {
+        CpuRasterizer::default()
+    }
+
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.render_options = options;
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        self.render_options
+    }
+
+    /// `Cpu` after the first render, `None` before.
+    pub fn last_render_backend(&self) -> Option<RenderBackendKind> {
+        self.rendered.then_some(RenderBackendKind::Cpu)
+    }
+
+    pub fn rasterize(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RgbaImage {
+        self.rasterize_with_warnings(scene, background).image
+    }
+
+    /// Like `rasterize`, and report the content that was simplified.
+    pub fn rasterize_with_warnings(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RasterizeResult {
+        let mut warnings = vec![];
+        self.rendered = true;
+        let view_box = scene.view_box();
+        let size = view_box.size().ceil().to_i32();
+        let (width, height) = (size.x().max(1) as u32, size.y().max(1) as u32);
+
+        let mut target = Pixmap::new(width, height).expect("image too large");
+        let bg = background.into().color();
+        target.fill(Color::from_rgba(bg.r(), bg.g(), bg.b(), bg.a()).unwrap_or(Color::WHITE));
+
+        let transform = Transform::from_translate(-view_box.origin_x(), -view_box.origin_y());
+        let dilation = self.render_options.dilation.x().max(self.render_options.dilation.y());
+        // growing the outline by `dilation` on each side
+        let dilate = Stroke { width: 2.0 * dilation, ..Stroke::default() };
+        let mut masks = HashMap::new();
+        for i in 0..scene.draw_path_count() {
+            let draw_path = scene.get_draw_path(DrawPathId(i));
+            let path = match to_path(draw_path.outline()) {
+                Some(path) => path,
+                None => continue,
+            };
+            let mask = match draw_path.clip_path() {
+                Some(id) => match clip_mask(&scene, id, width, height, transform, &mut masks) {
+                    Some(mask) => Some(mask),
+                    // clipped away entirely
+                    None => continue,
+                },
+                None => None,
+            };
+
+            // the pixels of an image pattern, the shader borrows them
+            let mut pixmap = None;
+            let shader = match self.shader(scene.get_paint(draw_path.paint()), &mut pixmap, &mut warnings) {
+                Some(shader) => shader,
+                None => continue,
+            };
+            let mut paint = Paint::default();
+            paint.shader = shader;
+            paint.blend_mode = blend_mode(draw_path.blend_mode());
+            paint.anti_alias = true;
+            target.fill_path(&path, &paint, fill_rule(draw_path.fill_rule()), transform, mask);
+            if dilation > 0.0 {
+                // translucent paths get darker where the stroke overlaps the fill
+                target.stroke_path(&path, &paint, &dilate, transform, mask);
+            }
+        }
+
+        let mut image = RgbaImage::new(width, height);
+        for (out, pixel) in image.pixels_mut().zip(target.pixels()) {
+            let c = pixel.demultiply();
+            out.0 = [c.red(), c.green(), c.blue(), c.alpha()];
+        }
+        RasterizeResult { image, warnings }
+    }
+
+    /// The shader of `paint`, in scene space. `None` for paths that are not drawn.
+    ///
+    /// The base color of a gradient or pattern paint only contributes its alpha.
+    fn shader<'p>(
+        &mut self,
+        paint: &PfPaint,
+        pixmap: &'p mut Option<Pixmap>,
+        warnings: &mut Vec<String>,
+    ) -> Option<Shader<'p>> {
+        const UNSUPPORTED: &str =
+            "the CPU rasterizer does not draw render targets (transparency groups, soft masks, tiling patterns)";
+        let base = paint.base_color();
+        let solid = Shader::SolidColor(Color::from_rgba8(base.r, base.g, base.b, base.a));
+        let opacity = base.a as f32 / 255.0;
+        match paint.overlay().map(|overlay| overlay.contents()) {
+            None => Some(solid),
+            Some(PaintContents::Gradient(gradient)) => Some(gradient_shader(gradient, opacity).unwrap_or(solid)),
+            Some(PaintContents::Pattern(pattern)) => match pattern.source() {
+                PatternSource::Image(image) => {
+                    let pixmap = pixmap.insert(image_pixmap(image)?);
+                    let spread = match pattern.repeat_x() || pattern.repeat_y() {
+                        true => SpreadMode::Repeat,
+                        false => SpreadMode::Pad,
+                    };
+                    let quality = match pattern.smoothing_enabled() {
+                        true => FilterQuality::Bilinear,
+                        false => FilterQuality::Nearest,
+                    };
+                    Some(tiny_skia::Pattern::new(pixmap.as_ref(), spread, quality, opacity, to_transform(pattern.transform())))
+                }
+                // the content of the target was drawn in place, the composite would cover it
+                PatternSource::RenderTarget { .. } => {
+                    if !self.warned {
+                        log::warn!("{}", UNSUPPORTED);
+                        self.warned = true;
+                    }
+                    if !warnings.iter().any(|w| w == UNSUPPORTED) {
+                        warnings.push(UNSUPPORTED.into());
+                    }
+                    None
+                }
+            },
+        }
+    }
+}
+
+/// `gradient` (in scene space) as a tiny-skia shader, with `opacity` applied to its stops.
+///
+/// `None` for degenerate gradients, e.g. with both ends at the same point.
+fn gradient_shader(gradient: &Gradient, opacity: f32) -> Option<Shader<'static>> {
+    let stops = gradient
+        .stops()
+        .iter()
+        .map(|stop| {
+            let c = stop.color;
+            GradientStop::new(stop.offset, Color::from_rgba8(c.r, c.g, c.b, (c.a as f32 * opacity).round() as u8))
+        })
+        .collect();
+    let spread = match gradient.wrap {
+        GradientWrap::Clamp => SpreadMode::Pad,
+        GradientWrap::Repeat => SpreadMode::Repeat,
+    };
+    let point = |p: pathfinder_geometry::vector::Vector2F| Point::from_xy(p.x(), p.y());
+    match gradient.geometry {
+        GradientGeometry::Linear(line) => {
+            LinearGradient::new(point(line.from()), point(line.to()), stops, spread, Transform::identity())
+        }
+        // tiny-skia has no radius for the start circle
+        GradientGeometry::Radial { line, radii, transform } => {
+            RadialGradient::new(point(line.from()), point(line.to()), radii.y(), stops, spread, to_transform(transform))
+        }
+    }
+}
+
+/// The pixels of `image`, premultiplied for tiny-skia.
+fn image_pixmap(image: &Image) -> Option<Pixmap> {
+    let size = image.size();
+    let data = image
+        .pixels()
+        .iter()
+        .flat_map(|c| {
+            let a = c.a as u32;
+            let premultiply = |v: u8| ((v as u32 * a + 127) / 255) as u8;
+            [premultiply(c.r), premultiply(c.g), premultiply(c.b), c.a]
+        })
+        .collect();
+    Pixmap::from_vec(data, IntSize::from_wh(size.x() as u32, size.y() as u32)?)
+}
+
+fn to_transform(t: Transform2F) -> Transform {
+    Transform::from_row(t.m11(), t.m21(), t.m12(), t.m22(), t.m13(), t.m23())
+}
+
+/// The mask of a clip path, intersected with its parents. `None` if nothing is visible.
+fn clip_mask<'a>(
+    scene: &Scene,
+    id: ClipPathId,
+    width: u32,
+    height: u32,
+    transform: Transform,
+    masks: &'a mut HashMap<u32, Option<Box<Mask>>>,
+) -> Option<&'a Mask> {
+    if !masks.contains_key(&id.0) {
+        let clip_path = scene.get_clip_path(id);
+        let mask = to_path(clip_path.outline()).and_then(|path| {
+            let mut mask = match clip_path.clip_path() {
+                Some(parent) => clip_mask(scene, parent, width, height, transform, masks)?.clone(),
+                None => {
+                    let mut mask = Mask::new(width, height)?;
+                    mask.data_mut().fill(255);
+                    mask
+                }
+            };
+            mask.intersect_path(&path, fill_rule(clip_path.fill_rule()), true, transform);
+            Some(Box::new(mask))
+        });
+        masks.insert(id.0, mask);
+    }
+    masks[&id.0].as_deref()
+}
+
+fn to_path(outline: &Outline) -> Option<Path> {
+    let mut builder = PathBuilder::new();
+    for contour in outline.contours() {
+        for (i, segment) in contour.iter(ContourIterFlags::empty()).enumerate() {
+            let (from, to) = (segment.baseline.from(), segment.baseline.to());
+            if i == 0 {
+                builder.move_to(from.x(), from.y());
+            }
+            match segment.kind {
+                SegmentKind::None => {}
+                SegmentKind::Line => builder.line_to(to.x(), to.y()),
+                SegmentKind::Quadratic => {
+                    let c = segment.ctrl.from();
+                    builder.quad_to(c.x(), c.y(), to.x(), to.y());
+                }
+                SegmentKind::Cubic => {
+                    let (c1, c2) = (segment.ctrl.from(), segment.ctrl.to());
+                    builder.cubic_to(c1.x(), c1.y(), c2.x(), c2.y(), to.x(), to.y());
+                }
+            }
+        }
+        if contour.is_closed() {
+            builder.close();
+        }
+    }
+    builder.finish()
+}
+
+fn fill_rule(rule: FillRule) -> tiny_skia::FillRule {
+    match rule {
+        FillRule::Winding => tiny_skia::FillRule::Winding,
+        FillRule::EvenOdd => tiny_skia::FillRule::EvenOdd,
+    }
+}
+
+fn blend_mode(mode: BlendMode) -> tiny_skia::BlendMode {
+    use tiny_skia::BlendMode as B;
+    match mode {
+        BlendMode::Clear => B::Clear,
+        BlendMode::Copy => B::Source,
+        BlendMode::SrcIn => B::SourceIn,
+        BlendMode::SrcOut => B::SourceOut,
+        BlendMode::SrcOver => B::SourceOver,
+        BlendMode::SrcAtop => B::SourceAtop,
+        BlendMode::DestIn => B::DestinationIn,
+        BlendMode::DestOut => B::DestinationOut,
+        BlendMode::DestOver => B::DestinationOver,
+        BlendMode::DestAtop => B::DestinationAtop,
+        BlendMode::Xor => B::Xor,
+        BlendMode::Lighter => B::Plus,
+        BlendMode::Darken => B::Darken,
+        BlendMode::Lighten => B::Lighten,
+        BlendMode::Multiply => B::Multiply,
+        BlendMode::Screen => B::Screen,
+        BlendMode::HardLight => B::HardLight,
+        BlendMode::Overlay => B::Overlay,
+        BlendMode::ColorDodge => B::ColorDodge,
+        BlendMode::ColorBurn => B::ColorBurn,
+        BlendMode::SoftLight => B::SoftLight,
+        BlendMode::Difference => B::Difference,
+        BlendMode::Exclusion => B::Exclusion,
+        BlendMode::Hue => B::Hue,
+        BlendMode::Saturation => B::Saturation,
+        BlendMode::Color => B::Color,
+        BlendMode::Luminosity => B::Luminosity,
+    }
+}
+
+/// A GPU or CPU rasterizer, see `RasterizeBackend`.
+pub enum AnyRasterizer {
+    Gpu(Rasterizer),
+    Cpu(CpuRasterizer),
+}
+
+impl AnyRasterizer {
+    /// Only fails for `RasterizeBackend::Gpu` without an OpenGL context.
+    pub fn new(backend: RasterizeBackend) -> Result<Self, RasterizeError> {
+        match backend {
+            RasterizeBackend::Gpu => Rasterizer::try_new().map(AnyRasterizer::Gpu),
+            RasterizeBackend::Cpu => Ok(AnyRasterizer::Cpu(CpuRasterizer::new())),
+            RasterizeBackend::Auto => Ok(match Rasterizer::try_new() {
+                Ok(rasterizer) => AnyRasterizer::Gpu(rasterizer),
+                Err(e) => {
+                    log::warn!("{}, falling back to the CPU rasterizer", e);
+                    AnyRasterizer::Cpu(CpuRasterizer::new())
+                }
+            }),
+        }
+    }
+
+    /// The backend in use, never `Auto`.
+    pub fn backend(&self) -> RasterizeBackend {
+        match self {
+            AnyRasterizer::Gpu(_) => RasterizeBackend::Gpu,
+            AnyRasterizer::Cpu(_) => RasterizeBackend::Cpu,
+        }
+    }
+
+    pub fn rasterize(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RgbaImage {
+        match self {
+            AnyRasterizer::Gpu(rasterizer) => rasterizer.rasterize(scene, background),
+            AnyRasterizer::Cpu(rasterizer) => rasterizer.rasterize(scene, background),
+        }
+    }
+
+    pub fn rasterize_with_warnings(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RasterizeResult {
+        match self {
+            AnyRasterizer::Gpu(rasterizer) => rasterizer.rasterize_with_warnings(scene, background),
+            AnyRasterizer::Cpu(rasterizer) => rasterizer.rasterize_with_warnings(scene, background),
+        }
+    }
+
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        match self {
+            AnyRasterizer::Gpu(rasterizer) => rasterizer.set_render_options(options),
+            AnyRasterizer::Cpu(rasterizer) => rasterizer.set_render_options(options),
+        }
+    }
+
+    /// What drew the last image, `None` before the first render.
+    pub fn last_render_backend(&self) -> Option<RenderBackendKind> {
+        match self {
+            AnyRasterizer::Gpu(rasterizer) => rasterizer.last_render_backend(),
+            AnyRasterizer::Cpu(rasterizer) => rasterizer.last_render_backend(),
+        }
+    }
+}
+
+#[cfg(test)]
+use pathfinder_color::ColorU;
+
+#[test]
+fn test_cpu_rasterizer() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+    use pathfinder_renderer::{paint::Paint as PfPaint, scene::{ClipPath, DrawPath}};
+
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::new(10.0, 10.0), Vector2F::new(20.0, 10.0)));
+    let red = scene.push_paint(&PfPaint::from_color(ColorU::new(255, 0, 0, 255)));
+    let blue = scene.push_paint(&PfPaint::from_color(ColorU::new(0, 0, 255, 255)));
+    // left half red
+    scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::new(10.0, 10.0), Vector2F::new(10.0, 10.0))), red));
+    // blue everywhere, clipped to the bottom right quarter
+    let clip = scene.push_clip_path(ClipPath::new(Outline::from_rect(RectF::new(Vector2F::new(20.0, 15.0), Vector2F::new(10.0, 5.0)))));
+    let mut path = DrawPath::new(Outline::from_rect(RectF::new(Vector2F::zero(), Vector2F::splat(100.0))), blue);
+    path.set_clip_path(Some(clip));
+    scene.push_draw_path(path);
+
+    let mut rasterizer = AnyRasterizer::new(RasterizeBackend::Cpu).unwrap();
+    assert_eq!(rasterizer.backend(), RasterizeBackend::Cpu);
+    assert_eq!(rasterizer.last_render_backend(), None);
+    let img = rasterizer.rasterize(scene, ClearMode::OpaqueWhite);
+    assert_eq!(rasterizer.last_render_backend(), Some(RenderBackendKind::Cpu));
+    assert!(rasterizer.last_render_backend().unwrap().is_software());
+    assert_eq!(img.dimensions(), (20, 10));
+    assert_eq!(img.get_pixel(2, 2).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(15, 2).0, [255, 255, 255, 255]);
+    assert_eq!(img.get_pixel(15, 8).0, [0, 0, 255, 255]);
+    assert_eq!(img.get_pixel(2, 8).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_cpu_warnings() {
+    use pathfinder_content::gradient::Gradient;
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+    use pathfinder_renderer::{paint::Paint as PfPaint, scene::DrawPath};
+
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+    let mut gradient = Gradient::linear_from_points(Vector2F::zero(), Vector2F::new(10.0, 0.0));
+    gradient.add_color_stop(ColorU::new(255, 0, 0, 255), 0.0);
+    gradient.add_color_stop(ColorU::new(0, 0, 255, 255), 1.0);
+    let paint = scene.push_paint(&PfPaint::from_gradient(gradient));
+    let rect = Outline::from_rect(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+    scene.push_draw_path(DrawPath::new(rect.clone(), paint));
+    scene.push_draw_path(DrawPath::new(rect, paint));
+
+    let result = CpuRasterizer::new().rasterize_with_warnings(scene, ClearMode::OpaqueWhite);
+    assert_eq!(result.image.dimensions(), (10, 10));
+    assert!(result.warnings.is_empty());
+    let left = result.image.get_pixel(0, 5).0;
+    let right = result.image.get_pixel(9, 5).0;
+    assert!(left[0] > 200 && left[2] < 50, "{:?}", left);
+    assert!(right[2] > 200 && right[0] < 50, "{:?}", right);
+}
+
+#[test]
+fn test_cpu_render_target() {
+    use pathfinder_content::pattern::Pattern;
+    use pathfinder_geometry::{rect::RectF, vector::{Vector2F, Vector2I}};
+    use pathfinder_renderer::scene::{DrawPath, RenderTarget};
+
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+    let rect = Outline::from_rect(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+    let id = scene.push_render_target(RenderTarget::new(Vector2I::splat(10), "group".into()));
+    let red = scene.push_paint(&PfPaint::from_color(ColorU::new(255, 0, 0, 255)));
+    scene.push_draw_path(DrawPath::new(rect.clone(), red));
+    scene.pop_render_target();
+    let group = scene.push_paint(&PfPaint::from_pattern(Pattern::from_render_target(id, Vector2I::splat(10))));
+    scene.push_draw_path(DrawPath::new(rect.clone(), group));
+    scene.push_draw_path(DrawPath::new(rect, group));
+
+    let result = CpuRasterizer::new().rasterize_with_warnings(scene, ClearMode::OpaqueWhite);
+    // the content is drawn in place, not covered by the composite
+    assert_eq!(result.image.get_pixel(5, 5).0, [255, 0, 0, 255]);
+    // reported once, however many paths use it
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].contains("render targets"));
+}
+
+#[test]
+fn test_cpu_dilation() {
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+    use pathfinder_renderer::{paint::Paint as PfPaint, scene::DrawPath};
+
+    // a 0.5pt rule rendered at 36 dpi: 0.25 px wide
+    let scene = || {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+        let black = scene.push_paint(&PfPaint::from_color(ColorU::black()));
+        let rule = RectF::new(Vector2F::new(0.0, 5.0), Vector2F::new(10.0, 0.25));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rule), black));
+        scene
+    };
+    let visible = |img: &RgbaImage| img.get_pixel(5, 5).0[0] < 128;
+
+    let mut rasterizer = CpuRasterizer::new();
+    let img = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    assert!(!visible(&img), "{:?}", img.get_pixel(5, 5));
+
+    let options = RenderOptions::preserve_hairlines(0.5);
+    assert_eq!(options.dilation, Vector2F::splat(0.375));
+    rasterizer.set_render_options(options);
+    let img = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    assert!(visible(&img), "{:?}", img.get_pixel(5, 5));
+
+    // high resolutions are left alone
+    assert_eq!(RenderOptions::preserve_hairlines(2.0).dilation, Vector2F::zero());
+}
+
+#[test]
+fn test_cpu_clear_mode() {
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+    use pathfinder_renderer::{paint::Paint as PfPaint, scene::DrawPath};
+
+    let scene = || {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+        let red = scene.push_paint(&PfPaint::from_color(ColorU::new(255, 0, 0, 255)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::zero(), Vector2F::splat(5.0))), red));
+        scene
+    };
+
+    let mut rasterizer = CpuRasterizer::new();
+    let img = rasterizer.rasterize(scene(), ClearMode::Transparent);
+    assert_eq!(img.get_pixel(2, 2).0, [255, 0, 0, 255]);
+    // untouched pixels stay transparent
+    assert_eq!(img.get_pixel(8, 8).0[3], 0);
+
+    let img = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    assert_eq!(img.get_pixel(8, 8).0, [255, 255, 255, 255]);
+    let img = rasterizer.rasterize(scene(), pathfinder_color::ColorF::new(0.0, 0.0, 1.0, 1.0));
+    assert_eq!(img.get_pixel(8, 8).0, [0, 0, 255, 255]);
+}
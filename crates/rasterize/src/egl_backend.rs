@@ -13,14 +13,16 @@ pub struct EGLContext {
 }
 
 impl EGLContext {
-    pub fn new() -> Self {
+    /// Fails if EGL is missing or broken.
+    pub fn try_new() -> Result<Self, String> {
         let egl = egl::Instance::new(egl::Static);
 
         let display = unsafe {
             egl.get_display(egl::DEFAULT_DISPLAY)
-                .expect("Failed to get EGL display")
+                .ok_or("Failed to get EGL display")?
         };
-        let (_major, _minor) = egl.initialize(display).expect("Failed to initialize EGL");
+        let (_major, _minor) = egl.initialize(display)
+            .map_err(|e| format!("Failed to initialize EGL: {}", e))?;
 
         let attrib_list = [
             egl::SURFACE_TYPE,
@@ -40,30 +42,30 @@ impl EGLContext {
 
         let config = egl
             .choose_first_config(display, &attrib_list)
-            .expect("Failed to choose EGL config")
-            .expect("No suitable EGL config found");
+            .map_err(|e| format!("Failed to choose EGL config: {}", e))?
+            .ok_or("No suitable EGL config found")?;
 
         let pbuffer_attrib_list = [egl::WIDTH, 1, egl::HEIGHT, 1, egl::NONE];
         let surface = egl
             .create_pbuffer_surface(display, config, &pbuffer_attrib_list)
-            .expect("Failed to create pbuffer surface");
+            .map_err(|e| format!("Failed to create pbuffer surface: {}", e))?;
 
         egl.bind_api(egl::OPENGL_API)
-            .expect("Failed to bind OpenGL API");
+            .map_err(|e| format!("Failed to bind OpenGL API: {}", e))?;
 
         let context = egl
             .create_context(display, config, None, &[egl::NONE])
-            .expect("Failed to create EGL context");
+            .map_err(|e| format!("Failed to create EGL context: {}", e))?;
 
         egl.make_current(display, Some(surface), Some(surface), Some(context))
-            .expect("Failed to make EGL context current");
+            .map_err(|e| format!("Failed to make EGL context current: {}", e))?;
 
         // Load OpenGL function pointers
         gl::load_with(|name| egl.get_proc_address(name).unwrap() as *const std::ffi::c_void);
 
         log::info!("✓ EGL context created successfully");
 
-        EGLContext {
+        Ok(EGLContext {
             egl,
             display,
             surface,
@@ -71,7 +73,7 @@ impl EGLContext {
             previous_context: None,
             previous_draw_surface: None,
             previous_read_surface: None,
-        }
+        })
     }
 
     pub fn make_current(&mut self) {
@@ -123,6 +125,8 @@ impl Drop for EGLContext {
         // Restore previous context before destroying
         self.restore_previous();
 
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.destroy_surface(self.display, self.surface);
         let _ = self.egl.terminate(self.display);
     }
 }
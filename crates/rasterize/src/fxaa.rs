@@ -0,0 +1,169 @@
+//! FXAA post-processing of the framebuffer, see `Rasterizer::set_fxaa`.
+
+use std::ffi::CString;
+
+use pathfinder_geometry::vector::Vector2I;
+
+const VERTEX_SHADER: &str = "#version 330
+out vec2 vUv;
+void main() {
+    // one triangle covering the viewport
+    vec2 p = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    vUv = p;
+    gl_Position = vec4(p * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+// the reduced FXAA variant: blend along the edge direction found from the luma of the
+// diagonal neighbours, unless that leaves the local luma range
+const FRAGMENT_SHADER: &str = "#version 330
+uniform sampler2D uSource;
+uniform vec2 uTexel;
+in vec2 vUv;
+out vec4 oColor;
+
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+float luma(vec4 c) {
+    return dot(c.rgb, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+    float lM = luma(texture(uSource, vUv));
+    float lNW = luma(texture(uSource, vUv + vec2(-1.0, -1.0) * uTexel));
+    float lNE = luma(texture(uSource, vUv + vec2(1.0, -1.0) * uTexel));
+    float lSW = luma(texture(uSource, vUv + vec2(-1.0, 1.0) * uTexel));
+    float lSE = luma(texture(uSource, vUv + vec2(1.0, 1.0) * uTexel));
+    float lMin = min(lM, min(min(lNW, lNE), min(lSW, lSE)));
+    float lMax = max(lM, max(max(lNW, lNE), max(lSW, lSE)));
+
+    vec2 dir = vec2(-((lNW + lNE) - (lSW + lSE)), (lNW + lSW) - (lNE + lSE));
+    float reduce = max((lNW + lNE + lSW + lSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * uTexel;
+
+    vec4 a = 0.5 * (texture(uSource, vUv + dir * (1.0 / 3.0 - 0.5)) + texture(uSource, vUv + dir * (2.0 / 3.0 - 0.5)));
+    vec4 b = 0.5 * a + 0.25 * (texture(uSource, vUv - dir * 0.5) + texture(uSource, vUv + dir * 0.5));
+    float lB = luma(b);
+    oColor = (lB < lMin || lB > lMax) ? a : b;
+}
+";
+
+/// The shader and a copy of the color buffer it samples.
+///
+/// Belongs to the GL context of the `Rasterizer`, which has to be current for every call.
+pub struct Fxaa {
+    program: u32,
+    vao: u32,
+    source: u32,
+    source_size: Vector2I,
+    source_format: u32,
+    texel_location: i32,
+}
+
+impl Fxaa {
+    pub unsafe fn new() -> Fxaa {
+        let program = gl::CreateProgram();
+        let vertex = compile(gl::VERTEX_SHADER, VERTEX_SHADER);
+        let fragment = compile(gl::FRAGMENT_SHADER, FRAGMENT_SHADER);
+        gl::AttachShader(program, vertex);
+        gl::AttachShader(program, fragment);
+        gl::LinkProgram(program);
+        let mut status = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+        if status == 0 {
+            panic!("FXAA shader does not link: {}", program_log(program));
+        }
+        gl::DeleteShader(vertex);
+        gl::DeleteShader(fragment);
+
+        let name = CString::new("uSource").unwrap();
+        let source_location = gl::GetUniformLocation(program, name.as_ptr());
+        let name = CString::new("uTexel").unwrap();
+        let texel_location = gl::GetUniformLocation(program, name.as_ptr());
+        gl::UseProgram(program);
+        gl::Uniform1i(source_location, 0);
+        gl::UseProgram(0);
+
+        // core profiles need a vertex array even without attributes
+        let mut vao = 0;
+        gl::GenVertexArrays(1, &mut vao);
+        let mut source = 0;
+        gl::GenTextures(1, &mut source);
+
+        Fxaa { program, vao, source, source_size: Vector2I::zero(), source_format: 0, texel_location }
+    }
+
+    /// Smooth the color buffer of the bound framebuffer, which is `size` big.
+    ///
+    /// `internal_format` is used for the copy, with sRGB if the color buffer has it,
+    /// so the edges are blended in linear space like the renderer does.
+    pub unsafe fn apply(&mut self, size: Vector2I, internal_format: u32) {
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::BindTexture(gl::TEXTURE_2D, self.source);
+        if size != self.source_size || internal_format != self.source_format {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                size.x(),
+                size.y(),
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            self.source_size = size;
+            self.source_format = internal_format;
+        }
+        gl::CopyTexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, 0, 0, size.x(), size.y());
+
+        gl::Disable(gl::BLEND);
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Disable(gl::STENCIL_TEST);
+        gl::Disable(gl::SCISSOR_TEST);
+        gl::Viewport(0, 0, size.x(), size.y());
+        gl::UseProgram(self.program);
+        gl::Uniform2f(self.texel_location, 1.0 / size.x() as f32, 1.0 / size.y() as f32);
+        gl::BindVertexArray(self.vao);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        gl::BindVertexArray(0);
+        gl::UseProgram(0);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+
+    pub unsafe fn delete(self) {
+        gl::DeleteProgram(self.program);
+        gl::DeleteVertexArrays(1, &self.vao);
+        gl::DeleteTextures(1, &self.source);
+    }
+}
+
+unsafe fn compile(kind: u32, source: &str) -> u32 {
+    let shader = gl::CreateShader(kind);
+    let source = CString::new(source).unwrap();
+    gl::ShaderSource(shader, 1, &source.as_ptr(), std::ptr::null());
+    gl::CompileShader(shader);
+    let mut status = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        let mut log = vec![0u8; 1024];
+        let mut len = 0;
+        gl::GetShaderInfoLog(shader, log.len() as i32, &mut len, log.as_mut_ptr() as *mut _);
+        panic!("FXAA shader does not compile: {}", String::from_utf8_lossy(&log[..len as usize]));
+    }
+    shader
+}
+
+unsafe fn program_log(program: u32) -> String {
+    let mut log = vec![0u8; 1024];
+    let mut len = 0;
+    gl::GetProgramInfoLog(program, log.len() as i32, &mut len, log.as_mut_ptr() as *mut _);
+    String::from_utf8_lossy(&log[..len as usize]).into_owned()
+}
@@ -1,18 +1,30 @@
 use pathfinder_gl::{ GLDevice, GLVersion };
 use pathfinder_renderer::{
+    concurrent::executor::SequentialExecutor,
     concurrent::rayon::RayonExecutor,
     concurrent::scene_proxy::SceneProxy,
     gpu::{
         options::{ DestFramebuffer, RendererLevel, RendererMode, RendererOptions },
         renderer::Renderer,
     },
-    scene::Scene,
-    options::{ BuildOptions, RenderTransform },
+    paint::PaintContents,
+    scene::{ DrawPathId, Scene },
 };
-use pathfinder_geometry::{ vector::{ Vector2F, Vector2I }, transform2d::Transform2F };
-use pathfinder_color::ColorF;
+use pathfinder_content::pattern::PatternSource;
+use pathfinder_geometry::{ vector::{ Vector2F, Vector2I }, rect::{ RectF, RectI }, transform2d::Transform2F };
+use pathfinder_color::{ ColorF, ColorU };
 use pathfinder_resources::embedded::EmbeddedResourceLoader;
-use image::RgbaImage;
+use image::{ RgbImage, RgbaImage };
+use inkrender::RenderOptions;
+
+mod watchdog;
+mod cpu;
+mod worker;
+mod fxaa;
+pub use watchdog::{RasterizeError, WatchdogRasterizer};
+pub use cpu::{AnyRasterizer, CpuRasterizer, RasterizeBackend};
+pub use worker::{RasterizeWorker, RenderFuture};
+pub use inkrender::ClearMode;
 
 // Platform-specific OpenGL context management
 #[cfg(target_os = "macos")]
@@ -28,139 +40,684 @@ use macos::MacOSGLContext as GLContext;
 #[cfg(not(target_os = "macos"))]
 use egl_backend::EGLContext as GLContext;
 
+/// Information about the OpenGL context, for diagnostics.
+#[derive(Clone, Debug, Default)]
+pub struct GlInfo {
+    pub vendor: String,
+    pub renderer: String,
+    pub version: String,
+    pub max_texture_size: u32,
+    pub max_samples: u32,
+}
+
+impl GlInfo {
+    /// Query the current context.
+    fn query() -> GlInfo {
+        unsafe fn string(name: gl::types::GLenum) -> String {
+            let ptr = gl::GetString(name);
+            if ptr.is_null() {
+                return String::new();
+            }
+            std::ffi::CStr::from_ptr(ptr as *const _).to_string_lossy().into_owned()
+        }
+        unsafe fn integer(name: gl::types::GLenum) -> u32 {
+            let mut value = 0;
+            gl::GetIntegerv(name, &mut value);
+            value.max(0) as u32
+        }
+        unsafe {
+            GlInfo {
+                vendor: string(gl::VENDOR),
+                renderer: string(gl::RENDERER),
+                version: string(gl::VERSION),
+                max_texture_size: integer(gl::MAX_TEXTURE_SIZE),
+                max_samples: integer(gl::MAX_SAMPLES),
+            }
+        }
+    }
+}
+
+/// Storage format of the color buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8 bits per channel with alpha.
+    #[default]
+    Rgba8,
+    /// 8 bits per channel, no alpha. Read back with `Rasterizer::rasterize_rgb`.
+    Rgb8,
+    /// 16 bits per channel with alpha. Read back with `Rasterizer::rasterize_rgba16`.
+    Rgba16,
+}
+
+/// What drew an image, e.g. for a "software rendering" notice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderBackendKind {
+    /// OpenGL through EGL
+    Egl,
+    /// OpenGL through CGL (macOS)
+    Cgl,
+    /// `CpuRasterizer`
+    Cpu,
+}
+
+impl RenderBackendKind {
+    /// The kind of `Rasterizer` on this platform.
+    #[cfg(target_os = "macos")]
+    pub const GL: RenderBackendKind = RenderBackendKind::Cgl;
+    #[cfg(not(target_os = "macos"))]
+    pub const GL: RenderBackendKind = RenderBackendKind::Egl;
+
+    /// Rendering without the GPU, which is a lot slower.
+    pub fn is_software(self) -> bool {
+        self == RenderBackendKind::Cpu
+    }
+}
+
+/// Size of the preview of `Rasterizer::rasterize_progressive`, relative to the image.
+pub const PREVIEW_SCALE: f32 = 0.25;
+
 pub struct Rasterizer {
-    context: GLContext,
-    renderer: Option<(Renderer<GLDevice>, Vector2I, Option<ColorF>, u32, u32, u32)>, // FBO, color_tex, depth_rb
+    // `None` with the context of the embedder, see `from_renderer`
+    context: Option<GLContext>,
+    // the framebuffer an embedder's renderer draws to, and the renderer while it isn't ours
+    external_framebuffer: Option<u32>,
+    spare_renderer: Option<Renderer<GLDevice>>,
+    gl_info: GlInfo,
+    srgb: bool,
+    format: PixelFormat,
+    depth_buffer: bool,
+    deterministic: bool,
+    render_options: RenderOptions,
+    clip_to_view_box: bool,
+    fxaa: bool,
+    // the FXAA shader, compiled on first use
+    fxaa_pass: Option<fxaa::Fxaa>,
+    // how often the shaders were compiled
+    renderers_created: usize,
+    // problems of the last render, see `rasterize_with_warnings`
+    warnings: Vec<String>,
+    last_backend: Option<RenderBackendKind>,
+    renderer: Option<(Renderer<GLDevice>, Vector2I, ClearMode, u32, u32, u32)>, // FBO, color_tex, depth_rb
 }
 
 impl Rasterizer {
+    /// Panics if no OpenGL context can be created, see `try_new`.
     pub fn new() -> Self {
-        let context = GLContext::new();
+        Self::try_new().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Like `new`, but fails without a working OpenGL context (e.g. on headless CI).
+    pub fn try_new() -> Result<Self, RasterizeError> {
+        let mut context = GLContext::try_new().map_err(RasterizeError::NoContext)?;
+
+        context.make_current();
+        let gl_info = GlInfo::query();
+        context.restore_previous();
+        log::info!("GL: {} {} ({})", gl_info.vendor, gl_info.renderer, gl_info.version);
         
+        Ok(Rasterizer::with_context(Some(context), gl_info))
+    }
+
+    /// Render with the pathfinder renderer of an embedder instead of creating a second one.
+    ///
+    /// The GL context of `renderer` has to be current whenever the rasterizer is used
+    /// (and dropped), and the `gl` function pointers loaded for it. The rasterizer draws into
+    /// its own framebuffer; `default_framebuffer` is the one `renderer` was created for,
+    /// it gets it back with `into_renderer`.
+    pub fn from_renderer(renderer: Renderer<GLDevice>, default_framebuffer: u32) -> Self {
+        let gl_info = GlInfo::query();
+        let mut rasterizer = Rasterizer::with_context(None, gl_info);
+        rasterizer.external_framebuffer = Some(default_framebuffer);
+        rasterizer.spare_renderer = Some(renderer);
+        rasterizer
+    }
+
+    /// Render with the GL context of an embedder, without creating a context or loading
+    /// the `gl` function pointers.
+    ///
+    /// The caller's context has to be current whenever the rasterizer is used and when it is
+    /// dropped (the renderer, framebuffer and textures it creates belong to that context),
+    /// `gl::load_with` has to have been called for it, and it has to support OpenGL 3.3.
+    /// The rasterizer binds its own framebuffer while drawing and framebuffer 0 afterwards.
+    pub fn new_with_loaded_gl() -> Self {
+        let gl_info = GlInfo::query();
+        log::info!("GL: {} {} ({})", gl_info.vendor, gl_info.renderer, gl_info.version);
+        Rasterizer::with_context(None, gl_info)
+    }
+
+    /// The renderer passed to `from_renderer`, drawing to its default framebuffer again.
+    ///
+    /// Its options are left at the last image, set `dest` before drawing to the window.
+    /// `None` if the rasterizer created its own.
+    pub fn into_renderer(mut self) -> Option<Renderer<GLDevice>> {
+        self.external_framebuffer?;
+        self.delete_renderer();
+        self.spare_renderer.take()
+    }
+
+    fn with_context(context: Option<GLContext>, gl_info: GlInfo) -> Self {
         Rasterizer {
             context,
+            external_framebuffer: None,
+            spare_renderer: None,
+            gl_info,
+            srgb: false,
+            format: PixelFormat::default(),
+            depth_buffer: true,
+            deterministic: false,
+            render_options: RenderOptions::default(),
+            clip_to_view_box: true,
+            fxaa: false,
+            fxaa_pass: None,
+            renderers_created: 0,
+            warnings: vec![],
+            last_backend: None,
             renderer: None,
         }
     }
 
+    /// Vendor, renderer and limits of the OpenGL context.
+    pub fn gl_info(&self) -> &GlInfo {
+        &self.gl_info
+    }
+
+    /// What drew the last image, `None` before the first render.
+    pub fn last_render_backend(&self) -> Option<RenderBackendKind> {
+        self.last_backend
+    }
+
+    /// Treat the framebuffer as sRGB.
+    ///
+    /// Off by default: colors are written as they are, like the WebGL viewer does.
+    /// When enabled, the output of the renderer is considered linear and encoded
+    /// to sRGB when it is stored.
+    pub fn set_srgb(&mut self, srgb: bool) {
+        if srgb != self.srgb {
+            self.srgb = srgb;
+            // the color texture has to be recreated with the new format
+            self.make_current();
+            self.delete_renderer();
+            self.restore_context();
+        }
+    }
+
+    pub fn srgb(&self) -> bool {
+        self.srgb
+    }
+
+    /// Allocate a depth buffer for the framebuffer. On by default.
+    ///
+    /// Pathfinder culls occluded tiles itself and doesn't depth test against the
+    /// destination framebuffer, so turning this off saves the memory of the depth buffer.
+    /// Drivers that refuse a framebuffer without depth still get one.
+    pub fn set_depth_buffer(&mut self, depth: bool) {
+        if depth != self.depth_buffer {
+            self.depth_buffer = depth;
+            self.make_current();
+            self.delete_renderer();
+            self.restore_context();
+        }
+    }
+
+    pub fn depth_buffer(&self) -> bool {
+        self.depth_buffer
+    }
+
+    /// Format of the color buffer, `PixelFormat::Rgba8` by default.
+    ///
+    /// sRGB encoding only applies to the 8 bit formats.
+    pub fn set_pixel_format(&mut self, format: PixelFormat) {
+        if format != self.format {
+            self.format = format;
+            self.make_current();
+            self.delete_renderer();
+            self.restore_context();
+        }
+    }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Produce the same bytes for the same scene, e.g. for golden-image tests.
+    ///
+    /// GL dithering is disabled and the scene is built on the calling thread instead of
+    /// the rayon pool. The `RenderOptions` apply as usual.
+    ///
+    /// This only holds for the same GPU and driver. Different hardware, drivers or
+    /// renderer levels can still round differently in the shaders.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Only draw inside the view box of the scene. On by default.
+    ///
+    /// The image covers the view box rounded up to whole pixels, so paths outside
+    /// of it (bleed, crop marks) can show up in the last column and row.
+    /// When clipping, pixels with their center outside the view box get the background.
+    pub fn set_clip_to_view_box(&mut self, clip: bool) {
+        self.clip_to_view_box = clip;
+    }
+
+    pub fn clip_to_view_box(&self) -> bool {
+        self.clip_to_view_box
+    }
+
+    /// Smooth edges with an FXAA pass over the rendered image before it is read back. Off by default.
+    ///
+    /// Softens the edges of thin and diagonal lines a little more than the coverage based
+    /// anti-aliasing of the renderer, without the memory of a multisampled framebuffer
+    /// (which this rasterizer doesn't use). The image keeps its size.
+    /// Not applied to `rasterize_update`, it would change pixels around the dirty rect.
+    pub fn set_fxaa(&mut self, fxaa: bool) {
+        self.fxaa = fxaa;
+    }
+
+    pub fn fxaa(&self) -> bool {
+        self.fxaa
+    }
+
+    /// Anti-aliasing and dilation, shared with the web viewer.
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.render_options = options;
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        self.render_options
+    }
+
+    /// Render many small images, e.g. page thumbnails, into one framebuffer of `max_dim`².
+    ///
+    /// Pages of different sizes don't reallocate the framebuffer, which `rasterize` does.
+    pub fn thumbnail_renderer(&mut self, max_dim: u32) -> ThumbnailRenderer<'_> {
+        ThumbnailRenderer { rasterizer: self, max_dim }
+    }
+
+    fn delete_renderer(&mut self) {
+        if let Some(pass) = self.fxaa_pass.take() {
+            unsafe {
+                pass.delete();
+            }
+        }
+        if let Some((mut renderer, _, _, old_fbo, old_tex, old_rb)) = self.renderer.take() {
+            unsafe {
+                gl::DeleteFramebuffers(1, &old_fbo);
+                gl::DeleteTextures(1, &old_tex);
+                if old_rb != 0 {
+                    gl::DeleteRenderbuffers(1, &old_rb);
+                }
+            }
+            // an embedder's renderer is kept for the next framebuffer
+            if let Some(framebuffer) = self.external_framebuffer {
+                renderer.device_mut().set_default_framebuffer(framebuffer);
+                self.spare_renderer = Some(renderer);
+            }
+        }
+    }
+
     fn make_current(&mut self) {
-        self.context.make_current();
+        if let Some(ref mut context) = self.context {
+            context.make_current();
+        }
     }
     
     fn restore_context(&mut self) {
-        self.context.restore_previous();
+        if let Some(ref mut context) = self.context {
+            context.restore_previous();
+        }
     }
 
     fn renderer_for_size(
         &mut self,
         size: Vector2I,
-        background: Option<ColorF>
+        background: ClearMode
     ) -> &mut Renderer<GLDevice> {
         let size = Vector2I::new((size.x() + 15) & !15, (size.y() + 15) & !15);
+        let format = self.format;
+        let srgb = self.srgb;
+        let depth = self.depth_buffer;
 
-        // Check if we need to recreate the renderer
-        let needs_recreation = self.renderer
-            .as_ref()
-            .map_or(true, |(_, current_size, current_bg, _, _, _)| {
-                size != *current_size || background != *current_bg
-            });
-
-        if needs_recreation {
-            // Clean up old FBO if it exists
-            if let Some((_, _, _, old_fbo, old_tex, old_rb)) = self.renderer.take() {
+        match self.renderer {
+            Some((_, current_size, current_bg, _, _, _)) if size == current_size && background == current_bg => {}
+            Some((ref mut renderer, ref mut current_size, ref mut current_bg, _, color_texture, depth_renderbuffer)) => {
+                // keep the renderer and its shaders, only resize the attachments
                 unsafe {
-                    gl::DeleteFramebuffers(1, &old_fbo);
-                    gl::DeleteTextures(1, &old_tex);
-                    gl::DeleteRenderbuffers(1, &old_rb);
+                    allocate_attachments(format, srgb, color_texture, depth_renderbuffer, size);
                 }
+                let options = renderer.options_mut();
+                options.dest = DestFramebuffer::full_window(size);
+                options.background_color = background.background_color();
+                renderer.dest_framebuffer_size_changed();
+                *current_size = size;
+                *current_bg = background;
             }
+            None => {
+                // Create FBO with color and depth attachments before renderer
+                let (fbo, color_texture, depth_renderbuffer) = unsafe {
+                    let mut fbo = 0;
+                    gl::GenFramebuffers(1, &mut fbo);
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
 
-            // Create FBO with color and depth attachments before renderer
-            let (fbo, color_texture, depth_renderbuffer) = unsafe {
-                let mut fbo = 0;
-                gl::GenFramebuffers(1, &mut fbo);
-                gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
-
-                // Create color texture
-                let mut color_texture = 0;
-                gl::GenTextures(1, &mut color_texture);
-                gl::BindTexture(gl::TEXTURE_2D, color_texture);
-                gl::TexImage2D(
-                    gl::TEXTURE_2D,
-                    0,
-                    gl::RGBA as i32,
-                    size.x(),
-                    size.y(),
-                    0,
-                    gl::RGBA,
-                    gl::UNSIGNED_BYTE,
-                    std::ptr::null(),
-                );
-                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-                gl::FramebufferTexture2D(
-                    gl::FRAMEBUFFER,
-                    gl::COLOR_ATTACHMENT0,
-                    gl::TEXTURE_2D,
-                    color_texture,
-                    0,
-                );
-
-                // Create depth renderbuffer
-                let mut depth_renderbuffer = 0;
-                gl::GenRenderbuffers(1, &mut depth_renderbuffer);
-                gl::BindRenderbuffer(gl::RENDERBUFFER, depth_renderbuffer);
-                gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, size.x(), size.y());
-                gl::FramebufferRenderbuffer(
-                    gl::FRAMEBUFFER,
-                    gl::DEPTH_ATTACHMENT,
-                    gl::RENDERBUFFER,
-                    depth_renderbuffer,
-                );
-
-                // Check framebuffer status
-                let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
-                if status != gl::FRAMEBUFFER_COMPLETE {
-                    panic!("Framebuffer is not complete: 0x{:x}", status);
-                }
+                    let mut color_texture = 0;
+                    gl::GenTextures(1, &mut color_texture);
+                    let mut depth_renderbuffer = 0;
+                    if depth {
+                        gl::GenRenderbuffers(1, &mut depth_renderbuffer);
+                    }
+                    allocate_attachments(format, srgb, color_texture, depth_renderbuffer, size);
 
-                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                    gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        gl::COLOR_ATTACHMENT0,
+                        gl::TEXTURE_2D,
+                        color_texture,
+                        0,
+                    );
+                    if depth_renderbuffer != 0 {
+                        attach_depth(depth_renderbuffer);
+                    }
 
-                (fbo, color_texture, depth_renderbuffer)
-            };
+                    // Check framebuffer status
+                    let mut status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+                    if status != gl::FRAMEBUFFER_COMPLETE && depth_renderbuffer == 0 {
+                        // some drivers insist on a depth buffer
+                        log::warn!("Framebuffer without depth is not complete (0x{:x}), adding one", status);
+                        gl::GenRenderbuffers(1, &mut depth_renderbuffer);
+                        allocate_attachments(format, srgb, color_texture, depth_renderbuffer, size);
+                        attach_depth(depth_renderbuffer);
+                        status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+                    }
+                    if status != gl::FRAMEBUFFER_COMPLETE {
+                        panic!("Framebuffer is not complete: 0x{:x}", status);
+                    }
 
-            let resource_loader = EmbeddedResourceLoader::new();
-            let renderer_gl_version = GLVersion::GL3;
-            let device = GLDevice::new(renderer_gl_version, fbo);
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
 
-            let render_mode = RendererMode {
-                level: RendererLevel::D3D9,
-            };
-            let dest = DestFramebuffer::full_window(size);
-            let render_options = RendererOptions {
-                dest,
-                background_color: background,
-                show_debug_ui: false,
-            };
+                    (fbo, color_texture, depth_renderbuffer)
+                };
+
+                let dest = DestFramebuffer::full_window(size);
+                let render_options = RendererOptions {
+                    dest,
+                    background_color: background.background_color(),
+                    show_debug_ui: false,
+                };
+
+                let renderer = match self.spare_renderer.take() {
+                    Some(mut renderer) => {
+                        renderer.device_mut().set_default_framebuffer(fbo);
+                        *renderer.options_mut() = render_options;
+                        renderer.dest_framebuffer_size_changed();
+                        renderer
+                    }
+                    None => {
+                        let resource_loader = EmbeddedResourceLoader::new();
+                        let renderer_gl_version = GLVersion::GL3;
+                        let device = GLDevice::new(renderer_gl_version, fbo);
 
-            let renderer = Renderer::new(device, &resource_loader, render_mode, render_options);
-            self.renderer = Some((renderer, size, background, fbo, color_texture, depth_renderbuffer));
+                        let render_mode = RendererMode {
+                            level: RendererLevel::D3D9,
+                        };
+
+                        // compiles all shaders
+                        self.renderers_created += 1;
+                        Renderer::new(device, &resource_loader, render_mode, render_options)
+                    }
+                };
+                self.renderer = Some((renderer, size, background, fbo, color_texture, depth_renderbuffer));
+            }
         }
 
         &mut self.renderer.as_mut().unwrap().0
     }
 
-    pub fn rasterize(&mut self, scene: Scene, background: Option<ColorF>) -> RgbaImage {
-        // Make our CGL context current
+    /// Compile the shaders and set up the framebuffer ahead of time.
+    ///
+    /// Renders a tiny scene, so the first real page doesn't pay for it.
+    /// Changing the sRGB mode or the pixel format afterwards undoes the work.
+    pub fn warm_up(&mut self) {
+        use pathfinder_content::outline::Outline;
+        use pathfinder_geometry::rect::RectF;
+        use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+        let mut scene = Scene::new();
+        let rect = RectF::new(Vector2F::zero(), Vector2F::splat(16.0));
+        scene.set_view_box(rect);
+        let paint = scene.push_paint(&Paint::from_color(ColorU::black()));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(4.0), Vector2F::splat(8.0))), paint));
+        self.rasterize(scene, ClearMode::OpaqueWhite);
+    }
+
+    /// Like `rasterize`, with the background as an 8 bit color.
+    pub fn rasterize_on(&mut self, scene: Scene, background: ColorU) -> RgbaImage {
+        self.rasterize(scene, ClearMode::Color(background.to_f32()))
+    }
+
+    pub fn rasterize(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RgbaImage {
+        let size = self.render(scene, background);
+        let pixels = self.read_pixels(size, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+        RgbaImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap()
+    }
+
+    /// Like `rasterize`, encoded as a PNG file.
+    #[cfg(feature = "png")]
+    pub fn rasterize_to_png(&mut self, scene: Scene, background: impl Into<ClearMode>) -> Result<Vec<u8>, image::ImageError> {
+        let image = self.rasterize(scene, background);
+        let mut png = Vec::new();
+        image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)?;
+        Ok(png)
+    }
+
+    /// Like `rasterize`, framing the scene with `view_box` instead of its own view box.
+    ///
+    /// The image has the size of `view_box` and shows that part of the scene: a larger box
+    /// adds a margin of background around the content, a smaller one crops it.
+    pub fn rasterize_with_view_box(&mut self, mut scene: Scene, view_box: RectF, background: impl Into<ClearMode>) -> RgbaImage {
+        scene.set_view_box(view_box);
+        self.rasterize(scene, background)
+    }
+
+    /// Like `rasterize`, and report what may be missing from the image.
+    pub fn rasterize_with_warnings(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RasterizeResult {
+        let image = self.rasterize(scene, background);
+        RasterizeResult { image, warnings: std::mem::take(&mut self.warnings) }
+    }
+
+    /// Like `rasterize`, but without the alpha channel (3 bytes per pixel).
+    ///
+    /// Meant for `PixelFormat::Rgb8`, works with the other formats too.
+    pub fn rasterize_rgb(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RgbImage {
+        let size = self.render(scene, background);
+        let pixels = self.read_pixels(size, gl::RGB, gl::UNSIGNED_BYTE, 3, 0u8);
+        RgbImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap()
+    }
+
+    /// Render with 16 bits per channel.
+    ///
+    /// Returns the size and the RGBA samples, rows from top to bottom.
+    /// Only `PixelFormat::Rgba16` keeps the extra precision, other formats
+    /// are read back as 8 bit values scaled to 16 bit.
+    pub fn rasterize_rgba16(&mut self, scene: Scene, background: impl Into<ClearMode>) -> (Vector2I, Vec<u16>) {
+        let size = self.render(scene, background);
+        let pixels = self.read_pixels(size, gl::RGBA, gl::UNSIGNED_SHORT, 4, 0u16);
+        (size, pixels)
+    }
+
+    /// Render a quick preview at `PREVIEW_SCALE` of the size first, then the full image,
+    /// so a large page can be shown (scaled up, blurry) before it is done.
+    ///
+    /// `on_stage` is called with the preview and `false`, then with the image `rasterize`
+    /// returns and `true`.
+    ///
+    /// Both are drawn into the framebuffer of the full image, the preview into its corner.
+    /// The scene is handed to pathfinder once and built twice.
+    ///
+    /// Scenes with render targets (transparency groups) only get the full image: the targets
+    /// have the resolution the scene was built for, see `RenderOptions::resolution`.
+    pub fn rasterize_progressive(
+        &mut self,
+        scene: Scene,
+        background: impl Into<ClearMode>,
+        mut on_stage: impl FnMut(RgbaImage, bool),
+    ) {
+        let background = background.into();
+        let view_box = scene.view_box();
+        let full_size = view_box.size().ceil().to_i32();
+        let preview = !has_render_targets(&scene);
+        let mut proxy = self.scene_proxy(scene, view_box.size());
+
+        if preview {
+            let size = self.render_proxy(&mut proxy, view_box, background, full_size, PREVIEW_SCALE);
+            let pixels = self.read_pixels(size, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+            on_stage(RgbaImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap(), false);
+        }
+
+        let size = self.render_proxy(&mut proxy, view_box, background, full_size, 1.0);
+        let pixels = self.read_pixels(size, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+        on_stage(RgbaImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap(), true);
+    }
+
+    /// Re-render only `dirty` (in pixels of the image `rasterize` returns) and return that part.
+    ///
+    /// For small changes like a blinking cursor or a highlight: blit the result over the previous
+    /// frame at `dirty.origin()`. The scene needs the view box and `background` the background of
+    /// the previous frame. Only the tiles touching `dirty` are built and drawn, the clear is
+    /// limited to `dirty` by a scissor and only `dirty` is read back.
+    ///
+    /// Without a previous frame of the same size and background the whole scene is drawn, the
+    /// result is the same. `dirty` is cut to the view box.
+    pub fn rasterize_update(&mut self, mut scene: Scene, dirty: RectI, background: impl Into<ClearMode>) -> RgbaImage {
+        let background = background.into();
+        let size = scene.view_box().size().ceil().to_i32();
+        let dirty = match dirty.intersection(RectI::new(Vector2I::zero(), size)) {
+            Some(dirty) if dirty.width() > 0 && dirty.height() > 0 => dirty,
+            _ => return RgbaImage::new(0, 0),
+        };
+        // rows are flipped when reading back
+        let gl_dirty = RectI::new(Vector2I::new(dirty.min_x(), size.y() - dirty.max_y()), dirty.size());
+        let fbo_size = Vector2I::new((size.x() + 15) & !15, (size.y() + 15) & !15);
+
+        match self.renderer {
+            Some((_, current_size, current_bg, _, _, _)) if current_size == fbo_size && current_bg == background => {}
+            _ => {
+                self.render(scene, background);
+                let pixels = self.read_rect(gl_dirty, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+                return RgbaImage::from_raw(dirty.width() as u32, dirty.height() as u32, pixels).unwrap();
+            }
+        }
+
         self.make_current();
-        
+        self.warnings.clear();
+        self.last_backend = Some(RenderBackendKind::GL);
+
+        let transform = Transform2F::from_translation(-scene.view_box().origin());
+        // only tiles touching the dirty rect are built
+        scene.set_view_box(dirty.to_f32());
+
+        let fbo = self.renderer.as_ref().map(|(_, _, _, fbo, _, _)| *fbo).unwrap();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            if self.srgb {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            }
+            if self.deterministic {
+                gl::Disable(gl::DITHER);
+            }
+            // the rest of the previous frame stays
+            set_clear_color(background);
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(gl_dirty.min_x(), gl_dirty.min_y(), gl_dirty.width(), gl_dirty.height());
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            gl::Disable(gl::SCISSOR_TEST);
+        }
+
+        let options = self.render_options.build_options(transform);
+        let renderer = &mut self.renderer.as_mut().unwrap().0;
+        // it would clear the whole framebuffer. Tiles on the edge draw a bit outside of `dirty`,
+        // those pixels are not read back and cleared by the next update that covers them.
+        renderer.options_mut().background_color = None;
+        if self.deterministic {
+            let mut proxy = SceneProxy::from_scene(scene, RendererLevel::D3D9, SequentialExecutor);
+            proxy.build_and_render(renderer, options);
+        } else {
+            let mut proxy = SceneProxy::from_scene(scene, RendererLevel::D3D9, RayonExecutor);
+            proxy.build_and_render(renderer, options);
+        }
+        renderer.options_mut().background_color = background.background_color();
+        let error = unsafe { gl::GetError() };
+        if error != gl::NO_ERROR {
+            self.warnings.push(format!("GL error 0x{:x} while rendering, some content may be missing", error));
+        }
+
+        let pixels = self.read_rect(gl_dirty, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+        RgbaImage::from_raw(dirty.width() as u32, dirty.height() as u32, pixels).unwrap()
+    }
+
+    /// Draw the scene into our framebuffer.
+    ///
+    /// Leaves the context current and the framebuffer bound, `read_pixels` undoes that.
+    fn render(&mut self, scene: Scene, background: impl Into<ClearMode>) -> Vector2I {
+        let size = scene.view_box().size().ceil().to_i32();
+        self.render_into(scene, background.into(), size, 1.0)
+    }
+
+    /// Like `render`, with the scene scaled by `scale` and a framebuffer of at least `fbo_size`.
+    ///
+    /// Returns the size of the scaled view box, which is cut to `fbo_size`.
+    fn render_into(&mut self, scene: Scene, background: ClearMode, fbo_size: Vector2I, scale: f32) -> Vector2I {
         let view_box = scene.view_box();
-        let size = view_box.size().ceil().to_i32();
-        let transform = Transform2F::from_translation(-view_box.origin());
+        let mut proxy = self.scene_proxy(scene, view_box.size() * scale);
+        self.render_proxy(&mut proxy, view_box, background, fbo_size, scale)
+    }
+
+    /// Hand the scene to a `SceneProxy`, with its view box moved to the origin and sized `page`.
+    ///
+    /// The proxy keeps the scene, it can be built again with another transform.
+    fn scene_proxy(&self, mut scene: Scene, page: Vector2F) -> SceneProxy {
+        // the renderer only draws tiles inside the view box
+        scene.set_view_box(RectF::new(Vector2F::zero(), page));
+        if self.deterministic {
+            SceneProxy::from_scene(scene, RendererLevel::D3D9, SequentialExecutor)
+        } else {
+            SceneProxy::from_scene(scene, RendererLevel::D3D9, RayonExecutor)
+        }
+    }
+
+    /// The part of `render_into` after the scene is handed over, `view_box` is the
+    /// original view box of the scene.
+    fn render_proxy(
+        &mut self,
+        proxy: &mut SceneProxy,
+        view_box: RectF,
+        background: ClearMode,
+        fbo_size: Vector2I,
+        scale: f32,
+    ) -> Vector2I {
+        // Make our CGL context current
+        self.make_current();
+        self.warnings.clear();
+        self.last_backend = Some(RenderBackendKind::GL);
+
+        let page = view_box.size() * scale;
+        let full_size = page.ceil().to_i32();
+        let size = Vector2I::new(full_size.x().min(fbo_size.x()), full_size.y().min(fbo_size.y()));
+        if size != full_size {
+            self.warnings.push(format!(
+                "the page ({}x{}) was cut to {}x{} pixels",
+                full_size.x(), full_size.y(), size.x(), size.y()
+            ));
+        }
+        let transform = Transform2F::from_scale(scale) * Transform2F::from_translation(-view_box.origin());
 
         // Get renderer and FBO separately to avoid borrow issues
         {
-            let _ = self.renderer_for_size(size, background);
+            let _ = self.renderer_for_size(fbo_size, background);
         }
         
         let fbo = self.renderer.as_ref().map(|(_, _, _, fbo, _, _)| *fbo).unwrap();
@@ -168,37 +725,99 @@ impl Rasterizer {
         // Bind and clear the framebuffer
         unsafe {
             gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            if self.srgb {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            }
+            if self.deterministic {
+                // enabled by default, and allowed to vary between draws
+                gl::Disable(gl::DITHER);
+            }
             
             // Clear the framebuffer
-            if let Some(bg) = background {
-                gl::ClearColor(bg.r(), bg.g(), bg.b(), bg.a());
-            } else {
-                gl::ClearColor(1.0, 1.0, 1.0, 1.0);
-            }
+            set_clear_color(background);
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
         }
 
-        let options = BuildOptions {
-            transform: RenderTransform::Transform2D(transform),
-            dilation: Vector2F::default(),
-            subpixel_aa_enabled: false,
-        };
+        let options = self.render_options.build_options(transform);
 
-        // Use SceneProxy for building and rendering
-        let mut proxy = SceneProxy::from_scene(scene, RendererLevel::D3D9, RayonExecutor);
         let renderer = &mut self.renderer.as_mut().unwrap().0;
         proxy.build_and_render(renderer, options);
+        let error = unsafe { gl::GetError() };
+        if error != gl::NO_ERROR {
+            self.warnings.push(format!("GL error 0x{:x} while rendering, some content may be missing", error));
+        }
+
+        if self.fxaa {
+            let fbo_size = self.renderer.as_ref().unwrap().1;
+            let internal_format = match (self.format, self.srgb) {
+                (PixelFormat::Rgba16, _) => gl::RGBA16,
+                (_, true) => gl::SRGB8_ALPHA8,
+                (_, false) => gl::RGBA8,
+            };
+            unsafe {
+                let pass = self.fxaa_pass.get_or_insert_with(|| fxaa::Fxaa::new());
+                pass.apply(fbo_size, internal_format);
+            }
+        }
+
+        if self.clip_to_view_box {
+            let fbo_size = self.renderer.as_ref().unwrap().1;
+            // pixels are kept if their center is inside the view box
+            let keep = Vector2I::new(page.x().round() as i32, page.y().round() as i32);
+            unsafe {
+                set_clear_color(background);
+                gl::Enable(gl::SCISSOR_TEST);
+                // right of the view box
+                gl::Scissor(keep.x(), 0, fbo_size.x() - keep.x(), fbo_size.y());
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+                // below it, rows are flipped when reading back
+                gl::Scissor(0, 0, fbo_size.x(), size.y() - keep.y());
+                gl::Clear(gl::COLOR_BUFFER_BIT);
+                gl::Disable(gl::SCISSOR_TEST);
+            }
+        }
+
+        size
+    }
+
+    /// Read back the framebuffer bound by `render` and restore the previous context.
+    ///
+    /// `channels` has to match `format`, `zero` selects the sample type matching `ty`.
+    fn read_pixels<T: Copy>(
+        &mut self,
+        size: Vector2I,
+        format: gl::types::GLenum,
+        ty: gl::types::GLenum,
+        channels: usize,
+        zero: T
+    ) -> Vec<T> {
+        self.read_rect(RectI::new(Vector2I::zero(), size), format, ty, channels, zero)
+    }
+
+    /// Like `read_pixels`, for `rect` in framebuffer coordinates (y up).
+    fn read_rect<T: Copy>(
+        &mut self,
+        rect: RectI,
+        format: gl::types::GLenum,
+        ty: gl::types::GLenum,
+        channels: usize,
+        zero: T
+    ) -> Vec<T> {
+        let size = rect.size();
+        let row_len = size.x() as usize * channels;
 
         // Read pixels from the framebuffer
-        let pixels = unsafe {
-            let mut pixels = vec![0u8; (size.x() * size.y() * 4) as usize];
+        let mut pixels = unsafe {
+            let mut pixels = vec![zero; row_len * size.y() as usize];
+            // rows of RGB pixels are not 4 byte aligned
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
             gl::ReadPixels(
-                0,
-                0,
+                rect.min_x(),
+                rect.min_y(),
                 size.x(),
                 size.y(),
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
+                format,
+                ty,
                 pixels.as_mut_ptr() as *mut _,
             );
             
@@ -207,12 +826,19 @@ impl Rasterizer {
             if error != gl::NO_ERROR {
                 panic!("GL error after ReadPixels: 0x{:x}", error);
             }
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 4);
             
             pixels
         };
 
         // Unbind framebuffer
         unsafe {
+            if self.srgb {
+                gl::Disable(gl::FRAMEBUFFER_SRGB);
+            }
+            if self.deterministic {
+                gl::Enable(gl::DITHER);
+            }
             gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
             
             // Flush and finish all GL commands before returning
@@ -222,25 +848,609 @@ impl Rasterizer {
         // Restore the previous OpenGL context
         self.restore_context();
 
-        // Create image and flip it vertically to correct OpenGL coordinate system
+        // Flip vertically to correct OpenGL coordinate system
         // OpenGL has origin at bottom-left, but images have origin at top-left
-        let mut img = RgbaImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap();
-        image::imageops::flip_vertical_in_place(&mut img);
-        img
+        if row_len > 0 {
+            let rows = pixels.len() / row_len;
+            for y in 0..rows / 2 {
+                let (top, bottom) = pixels.split_at_mut((rows - 1 - y) * row_len);
+                top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+            }
+        }
+        pixels
+    }
+}
+
+/// (Re)allocate the color texture and depth buffer of the framebuffer.
+unsafe fn allocate_attachments(format: PixelFormat, srgb: bool, color_texture: u32, depth_renderbuffer: u32, size: Vector2I) {
+    let (internal_format, ty) = match (format, srgb) {
+        (PixelFormat::Rgba8, false) => (gl::RGBA8, gl::UNSIGNED_BYTE),
+        (PixelFormat::Rgba8, true) => (gl::SRGB8_ALPHA8, gl::UNSIGNED_BYTE),
+        (PixelFormat::Rgb8, false) => (gl::RGB8, gl::UNSIGNED_BYTE),
+        (PixelFormat::Rgb8, true) => (gl::SRGB8, gl::UNSIGNED_BYTE),
+        (PixelFormat::Rgba16, _) => (gl::RGBA16, gl::UNSIGNED_SHORT),
+    };
+    gl::BindTexture(gl::TEXTURE_2D, color_texture);
+    gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        internal_format as i32,
+        size.x(),
+        size.y(),
+        0,
+        gl::RGBA,
+        ty,
+        std::ptr::null(),
+    );
+    if depth_renderbuffer != 0 {
+        gl::BindRenderbuffer(gl::RENDERBUFFER, depth_renderbuffer);
+        gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, size.x(), size.y());
+    }
+}
+
+/// See `Rasterizer::thumbnail_renderer`.
+pub struct ThumbnailRenderer<'a> {
+    rasterizer: &'a mut Rasterizer,
+    max_dim: u32,
+}
+
+impl ThumbnailRenderer<'_> {
+    /// Scale `scene` so the longer side of its view box is `max_dim` and render it.
+    ///
+    /// The image has the aspect ratio of the view box. Render targets (transparency groups)
+    /// keep the resolution the scene was built for and come out at the wrong size when it is scaled:
+    /// build such scenes at the size of the thumbnail, e.g. with `viewer::render_thumbnail`.
+    pub fn render(&mut self, scene: Scene, background: impl Into<ClearMode>) -> RgbaImage {
+        let page = scene.view_box().size();
+        let scale = self.max_dim as f32 / page.x().max(page.y());
+        let fbo_size = Vector2I::splat(self.max_dim as i32);
+        let scaled_targets = scale != 1.0 && has_render_targets(&scene);
+        let size = self.rasterizer.render_into(scene, background.into(), fbo_size, scale);
+        if scaled_targets {
+            let warning = "the scene was scaled, its transparency groups are drawn at the wrong size";
+            log::warn!("{}", warning);
+            self.rasterizer.warnings.push(warning.into());
+        }
+        let pixels = self.rasterizer.read_pixels(size, gl::RGBA, gl::UNSIGNED_BYTE, 4, 0u8);
+        RgbaImage::from_raw(size.x() as u32, size.y() as u32, pixels).unwrap()
     }
 }
 
+/// An image and the reasons it may be incomplete.
+///
+/// The warnings are meant for a "some content may be missing" notice, the image is usable either way.
+#[derive(Debug)]
+pub struct RasterizeResult {
+    pub image: RgbaImage,
+    pub warnings: Vec<String>,
+}
+
+/// Whether a path of `scene` is painted with a render target, e.g. a transparency group.
+fn has_render_targets(scene: &Scene) -> bool {
+    (0..scene.draw_path_count()).any(|i| {
+        let paint = scene.get_paint(scene.get_draw_path(DrawPathId(i)).paint());
+        matches!(
+            paint.overlay().map(|overlay| overlay.contents()),
+            Some(PaintContents::Pattern(pattern)) if matches!(pattern.source(), PatternSource::RenderTarget { .. })
+        )
+    })
+}
+
+/// Clear to the color of `background`.
+unsafe fn set_clear_color(background: ClearMode) {
+    let bg = background.color();
+    gl::ClearColor(bg.r(), bg.g(), bg.b(), bg.a());
+}
+
+/// Attach the depth buffer to the bound framebuffer.
+unsafe fn attach_depth(depth_renderbuffer: u32) {
+    gl::FramebufferRenderbuffer(
+        gl::FRAMEBUFFER,
+        gl::DEPTH_ATTACHMENT,
+        gl::RENDERBUFFER,
+        depth_renderbuffer,
+    );
+}
+
 impl Default for Rasterizer {
     fn default() -> Self {
         Self::new()
     }
 }
 
+impl Drop for Rasterizer {
+    fn drop(&mut self) {
+        // the renderer, framebuffer and FXAA pass belong to our context,
+        // which has to be current to free them before it is destroyed
+        self.make_current();
+        self.delete_renderer();
+        self.restore_context();
+    }
+}
+
 #[test]
 fn test_render() {
     use pathfinder_geometry::rect::RectF;
 
     let mut scene = Scene::new();
     scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(100.0, 100.0)));
-    Rasterizer::new().rasterize(scene, None);
+    Rasterizer::new().rasterize(scene, ClearMode::OpaqueWhite);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_gl_info() {
+    let rasterizer = Rasterizer::new();
+    let info = rasterizer.gl_info();
+    assert!(!info.vendor.is_empty());
+    assert!(!info.renderer.is_empty());
+    assert!(!info.version.is_empty());
+    assert!(info.max_texture_size > 0);
+    assert!(info.max_samples > 0);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_srgb() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_geometry::rect::RectF;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    fn gray_scene() -> Scene {
+        let mut scene = Scene::new();
+        let rect = RectF::new(Vector2F::zero(), Vector2F::new(16.0, 16.0));
+        scene.set_view_box(rect);
+        let paint = scene.push_paint(&Paint::from_color(ColorU::new(128, 128, 128, 255)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rect), paint));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    let plain = rasterizer.rasterize(gray_scene(), ClearMode::OpaqueWhite).get_pixel(8, 8).0;
+    assert!((plain[0] as i32 - 128).abs() <= 1, "{:?}", plain);
+
+    // linear 0.5 is encoded as ~188 in sRGB
+    rasterizer.set_srgb(true);
+    let srgb = rasterizer.rasterize(gray_scene(), ClearMode::OpaqueWhite).get_pixel(8, 8).0;
+    assert!((srgb[0] as i32 - 188).abs() <= 2, "{:?}", srgb);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_deterministic() {
+    use pathfinder_content::outline::{Contour, Outline};
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+    use pathfinder_geometry::rect::RectF;
+
+    // antialiased diagonal edges and a translucent overlap
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(100.0, 100.0)));
+        let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+        let blue = scene.push_paint(&Paint::from_color(ColorU::new(0, 0, 255, 100)));
+
+        let mut contour = Contour::new();
+        contour.push_endpoint(Vector2F::new(3.3, 5.1));
+        contour.push_endpoint(Vector2F::new(91.7, 17.9));
+        contour.push_endpoint(Vector2F::new(40.2, 96.4));
+        contour.close();
+        let mut triangle = Outline::new();
+        triangle.push_contour(contour);
+        scene.push_draw_path(DrawPath::new(triangle, red));
+        scene.push_draw_path(DrawPath::new(
+            Outline::from_rect(RectF::new(Vector2F::new(20.5, 20.5), Vector2F::new(50.25, 50.25))),
+            blue,
+        ));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.set_deterministic(true);
+    let a = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    let b = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    assert!(a.as_raw() == b.as_raw());
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_rasterize_update() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // a red square, blue in the second frame
+    fn scene(color: ColorU) -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(100.0, 80.0)));
+        let black = scene.push_paint(&Paint::from_color(ColorU::black()));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(5.0), Vector2F::splat(10.0))), black));
+        let paint = scene.push_paint(&Paint::from_color(color));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::new(40.0, 30.0), Vector2F::splat(20.0))), paint));
+        scene
+    }
+    let red = ColorU::new(255, 0, 0, 255);
+    let blue = ColorU::new(0, 0, 255, 255);
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.set_deterministic(true);
+    let mut frame = rasterizer.rasterize(scene(red), Some(ColorF::white()));
+    let expected = rasterizer.rasterize(scene(blue), Some(ColorF::white()));
+    rasterizer.rasterize(scene(red), Some(ColorF::white()));
+
+    let dirty = RectI::new(Vector2I::new(36, 26), Vector2I::splat(28));
+    let update = rasterizer.rasterize_update(scene(blue), dirty, Some(ColorF::white()));
+    assert_eq!(update.dimensions(), (28, 28));
+    assert_eq!(update.get_pixel(14, 14).0, [0, 0, 255, 255]);
+    // the border of the dirty rect is background
+    assert_eq!(update.get_pixel(1, 1).0, [255, 255, 255, 255]);
+
+    let before = frame.clone();
+    image::imageops::replace(&mut frame, &update, 36, 26);
+    assert!(frame.as_raw() == expected.as_raw());
+    // nothing outside the dirty rect changed
+    for (x, y, pixel) in frame.enumerate_pixels() {
+        if !dirty.contains_point(Vector2I::new(x as i32, y as i32)) {
+            assert_eq!(pixel, before.get_pixel(x, y), "{} {}", x, y);
+        }
+    }
+
+    // without a previous frame everything is drawn, on the given background
+    let update = Rasterizer::new().rasterize_update(scene(blue), dirty, ClearMode::Transparent);
+    assert_eq!(update.get_pixel(14, 14).0, [0, 0, 255, 255]);
+    assert_eq!(update.get_pixel(1, 1).0[3], 0);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_rasterize_with_view_box() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // a red page of 40x20
+    let page = RectF::new(Vector2F::zero(), Vector2F::new(40.0, 20.0));
+    let mut scene = Scene::new();
+    scene.set_view_box(page);
+    let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+    scene.push_draw_path(DrawPath::new(Outline::from_rect(page), red));
+
+    let mut rasterizer = Rasterizer::new();
+    let padded = page.dilate(Vector2F::splat(10.0));
+    let img = rasterizer.rasterize_with_view_box(scene, padded, Some(ColorF::white()));
+    assert_eq!(img.dimensions(), (60, 40));
+    assert_eq!(img.get_pixel(30, 20).0, [255, 0, 0, 255]);
+    // the margin is background
+    for (x, y) in [(5, 5), (30, 5), (55, 20), (30, 35), (5, 20)] {
+        assert_eq!(img.get_pixel(x, y).0, [255, 255, 255, 255], "{} {}", x, y);
+    }
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_fxaa() {
+    use pathfinder_content::outline::{Contour, Outline};
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // the lower left half black, the edge is a diagonal
+    let scene = || {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(32.0)));
+        let black = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let mut triangle = Contour::new();
+        triangle.push_endpoint(Vector2F::zero());
+        triangle.push_endpoint(Vector2F::new(0.0, 32.0));
+        triangle.push_endpoint(Vector2F::new(32.0, 32.0));
+        triangle.close();
+        let mut outline = Outline::new();
+        outline.push_contour(triangle);
+        scene.push_draw_path(DrawPath::new(outline, black));
+        scene
+    };
+    // pixels that are neither (almost) white nor black
+    let gray = |img: &RgbaImage| img.pixels().filter(|p| p.0[0] > 16 && p.0[0] < 240).count();
+
+    let mut rasterizer = Rasterizer::new();
+    let raw = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    rasterizer.set_fxaa(true);
+    let smooth = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    assert_eq!(smooth.dimensions(), raw.dimensions());
+    assert_ne!(smooth.as_raw(), raw.as_raw());
+    assert!(gray(&smooth) > gray(&raw), "{} {}", gray(&smooth), gray(&raw));
+    // away from the edge nothing changes
+    assert_eq!(smooth.get_pixel(28, 4), raw.get_pixel(28, 4));
+    assert_eq!(smooth.get_pixel(4, 28), raw.get_pixel(4, 28));
+}
+
+#[test]
+#[cfg(feature = "png")]
+#[ignore = "requires an OpenGL context"]
+fn test_rasterize_to_png() {
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(37.0, 20.0)));
+
+    let mut rasterizer = Rasterizer::new();
+    let png = rasterizer.rasterize_to_png(scene, Some(ColorF::new(1.0, 0.0, 0.0, 1.0))).unwrap();
+    let img = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (37, 20));
+    assert_eq!(img.get_pixel(10, 10).0, [255, 0, 0, 255]);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_rgb8() {
+    use pathfinder_geometry::rect::RectF;
+
+    let mut scene = Scene::new();
+    // odd width, rows are not 4 byte aligned
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(37.0, 20.0)));
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.set_pixel_format(PixelFormat::Rgb8);
+    let img = rasterizer.rasterize_rgb(scene, Some(ColorF::new(1.0, 0.0, 0.0, 1.0)));
+    assert_eq!(img.dimensions(), (37, 20));
+    assert_eq!(img.as_raw().len(), 37 * 20 * 3);
+    assert_eq!(img.get_pixel(36, 19).0, [255, 0, 0]);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_warm_up() {
+    use pathfinder_geometry::rect::RectF;
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.warm_up();
+    assert_eq!(rasterizer.renderers_created, 1);
+
+    // a page of a different size and background reuses the shaders
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(612.0, 792.0)));
+    let img = rasterizer.rasterize(scene, Some(ColorF::new(1.0, 0.0, 0.0, 1.0)));
+    assert_eq!(rasterizer.renderers_created, 1);
+    assert_eq!(img.dimensions(), (612, 792));
+    assert_eq!(img.get_pixel(300, 400).0, [255, 0, 0, 255]);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_without_depth() {
+    use pathfinder_content::outline::{Contour, Outline};
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+    use pathfinder_geometry::rect::RectF;
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(100.0, 100.0)));
+        let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+        let blue = scene.push_paint(&Paint::from_color(ColorU::new(0, 0, 255, 128)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(10.0), Vector2F::splat(60.0))), red));
+        let mut contour = Contour::new();
+        contour.push_endpoint(Vector2F::new(5.0, 90.0));
+        contour.push_endpoint(Vector2F::new(95.0, 70.0));
+        contour.push_endpoint(Vector2F::new(50.0, 3.0));
+        contour.close();
+        let mut outline = Outline::new();
+        outline.push_contour(contour);
+        scene.push_draw_path(DrawPath::new(outline, blue));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.set_deterministic(true);
+    let with_depth = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    rasterizer.set_depth_buffer(false);
+    let without_depth = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    assert!(with_depth == without_depth);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_clip_to_view_box() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+    use pathfinder_geometry::rect::RectF;
+
+    // the last column is only 0.4 px into the view box, the rect reaches far past it
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(50.4, 20.0)));
+        let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::new(40.0, 0.0), Vector2F::new(40.0, 20.0))), red));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    assert!(rasterizer.clip_to_view_box());
+    let img = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    assert_eq!(img.dimensions(), (51, 20));
+    assert_eq!(img.get_pixel(49, 10).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(50, 10).0, [255, 255, 255, 255]);
+
+    rasterizer.set_clip_to_view_box(false);
+    let img = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    assert_eq!(img.get_pixel(50, 10).0, [255, 0, 0, 255]);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_hairline_dilation() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // a 0.5pt rule rendered at 36 dpi: 0.25 px wide
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::splat(10.0)));
+        let black = scene.push_paint(&Paint::from_color(ColorU::black()));
+        let rule = RectF::new(Vector2F::new(0.0, 5.0), Vector2F::new(10.0, 0.25));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rule), black));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    let img = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    assert!(img.get_pixel(5, 5).0[0] >= 128);
+
+    rasterizer.set_render_options(RenderOptions::preserve_hairlines(0.5));
+    let img = rasterizer.rasterize(scene(), Some(ColorF::white()));
+    assert!(img.get_pixel(5, 5).0[0] < 128);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_thumbnails() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+    use pathfinder_geometry::rect::RectF;
+
+    let mut rasterizer = Rasterizer::new();
+    let mut thumbnails = rasterizer.thumbnail_renderer(64);
+    for (width, height) in [(100.0, 100.0), (200.0, 100.0), (100.0, 300.0), (612.0, 792.0), (50.0, 20.0)] {
+        let mut scene = Scene::new();
+        let page = RectF::new(Vector2F::zero(), Vector2F::new(width, height));
+        scene.set_view_box(page);
+        let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(page), red));
+
+        let img = thumbnails.render(scene, Some(ColorF::white()));
+        let (w, h) = img.dimensions();
+        assert_eq!(w.max(h), 64, "{}x{} -> {}x{}", width, height, w, h);
+        assert!(((w as f32 / h as f32) - width / height).abs() < 0.1);
+        assert_eq!(img.get_pixel(w / 2, h / 2).0, [255, 0, 0, 255]);
+    }
+    // one framebuffer for all of them
+    let (_, size, ..) = rasterizer.renderer.as_ref().unwrap();
+    assert_eq!(*size, Vector2I::splat(64));
+    assert_eq!(rasterizer.renderers_created, 1);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_delete_gl_objects() {
+    use pathfinder_geometry::rect::RectF;
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(100.0, 100.0)));
+        scene
+    }
+
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.set_fxaa(true);
+    rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    let (_, _, _, fbo, color_texture, depth_renderbuffer) = *rasterizer.renderer.as_ref().unwrap();
+
+    // a new format recreates the framebuffer and frees the old one
+    rasterizer.set_srgb(true);
+    rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+    rasterizer.make_current();
+    let (_, _, _, new_fbo, new_texture, new_renderbuffer) = *rasterizer.renderer.as_ref().unwrap();
+    unsafe {
+        // names are reused once they are free
+        assert!(fbo == new_fbo || gl::IsFramebuffer(fbo) == gl::FALSE);
+        assert!(color_texture == new_texture || gl::IsTexture(color_texture) == gl::FALSE);
+        assert!(depth_renderbuffer == new_renderbuffer || gl::IsRenderbuffer(depth_renderbuffer) == gl::FALSE);
+    }
+    rasterizer.restore_context();
+    drop(rasterizer);
+
+    // each one frees its objects and its context
+    for _ in 0..32 {
+        let mut rasterizer = Rasterizer::new();
+        rasterizer.set_fxaa(true);
+        let img = rasterizer.rasterize(scene(), ClearMode::OpaqueWhite);
+        assert_eq!(img.get_pixel(50, 50).0, [255, 255, 255, 255]);
+    }
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_from_renderer() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // the embedder's context and renderer, drawing to the default framebuffer
+    let mut context = GLContext::try_new().unwrap();
+    context.make_current();
+    let size = Vector2I::splat(16);
+    let device = GLDevice::new(GLVersion::GL3, 0);
+    let mode = RendererMode { level: RendererLevel::D3D9 };
+    let options = RendererOptions {
+        dest: DestFramebuffer::full_window(size),
+        background_color: None,
+        show_debug_ui: false,
+    };
+    let renderer = Renderer::new(device, &EmbeddedResourceLoader::new(), mode, options);
+
+    let mut rasterizer = Rasterizer::from_renderer(renderer, 0);
+    for width in [40.0, 60.0] {
+        let mut scene = Scene::new();
+        let page = RectF::new(Vector2F::zero(), Vector2F::new(width, 30.0));
+        scene.set_view_box(page);
+        let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(10.0), Vector2F::splat(10.0))), red));
+
+        let img = rasterizer.rasterize(scene, ClearMode::OpaqueWhite);
+        assert_eq!(img.dimensions(), (width as u32, 30));
+        assert_eq!(img.get_pixel(15, 15).0, [255, 0, 0, 255]);
+        assert_eq!(img.get_pixel(5, 5).0, [255, 255, 255, 255]);
+    }
+    // a new pixel format keeps the renderer too
+    rasterizer.set_pixel_format(PixelFormat::Rgb8);
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(20.0, 20.0)));
+    assert_eq!(rasterizer.rasterize_rgb(scene, ClearMode::OpaqueWhite).get_pixel(1, 1).0, [255, 255, 255]);
+    assert_eq!(rasterizer.renderers_created, 0);
+
+    assert!(rasterizer.into_renderer().is_some());
+    drop(context);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_rasterize_progressive() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(400.0, 300.0)));
+    let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+    scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(100.0), Vector2F::splat(100.0))), red));
+
+    let mut stages = vec![];
+    let mut rasterizer = Rasterizer::new();
+    rasterizer.rasterize_progressive(scene, ClearMode::OpaqueWhite, |img, done| stages.push((img, done)));
+    // one framebuffer for both stages
+    assert_eq!(rasterizer.renderer.as_ref().unwrap().1, Vector2I::new(400, 304));
+    assert_eq!(stages.len(), 2);
+    let (preview, full) = (&stages[0], &stages[1]);
+    assert!(!preview.1 && full.1);
+    assert_eq!(preview.0.dimensions(), (100, 75));
+    assert_eq!(full.0.dimensions(), (400, 300));
+    // the same content, smaller
+    assert_eq!(preview.0.get_pixel(37, 37).0, [255, 0, 0, 255]);
+    assert_eq!(full.0.get_pixel(150, 150).0, [255, 0, 0, 255]);
+    assert_eq!(preview.0.get_pixel(10, 10).0, [255, 255, 255, 255]);
+}
+
+#[test]
+#[ignore = "requires an OpenGL context"]
+fn test_new_with_loaded_gl() {
+    use pathfinder_content::outline::Outline;
+    use pathfinder_renderer::{paint::Paint, scene::DrawPath};
+
+    // the embedder created the context and loaded the function pointers
+    let mut context = GLContext::try_new().unwrap();
+    context.make_current();
+
+    let mut rasterizer = Rasterizer::new_with_loaded_gl();
+    assert!(!rasterizer.gl_info().version.is_empty());
+    let mut scene = Scene::new();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(40.0, 30.0)));
+    let red = scene.push_paint(&Paint::from_color(ColorU::new(255, 0, 0, 255)));
+    scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::splat(10.0), Vector2F::splat(10.0))), red));
+
+    let img = rasterizer.rasterize(scene, ClearMode::OpaqueWhite);
+    assert_eq!(img.dimensions(), (40, 30));
+    assert_eq!(img.get_pixel(15, 15).0, [255, 0, 0, 255]);
+    assert_eq!(img.get_pixel(5, 5).0, [255, 255, 255, 255]);
+    assert!(rasterizer.into_renderer().is_none());
+    drop(context);
 }
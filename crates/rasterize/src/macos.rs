@@ -59,7 +59,8 @@ pub struct MacOSGLContext {
 }
 
 impl MacOSGLContext {
-    pub fn new() -> Self {
+    /// Fails if CGL is missing or broken.
+    pub fn try_new() -> Result<Self, String> {
         unsafe {
             // Define pixel format attributes for OpenGL 3.2 Core Profile
             let attribs: [CGLPixelFormatAttribute; 12] = [
@@ -78,11 +79,11 @@ impl MacOSGLContext {
 
             let result = CGLChoosePixelFormat(attribs.as_ptr(), &mut pixel_format, &mut npix);
             if result != 0 {
-                panic!("Failed to choose pixel format: error code {}", result);
+                return Err(format!("Failed to choose pixel format: error code {}", result));
             }
 
             if pixel_format.0.is_null() {
-                panic!("No suitable pixel format found");
+                return Err("No suitable pixel format found".into());
             }
 
             log::info!("✓ macOS CGL pixel format created successfully");
@@ -97,21 +98,21 @@ impl MacOSGLContext {
 
             if result != 0 {
                 CGLDestroyPixelFormat(pixel_format);
-                panic!("Failed to create CGL context: error code {}", result);
+                return Err(format!("Failed to create CGL context: error code {}", result));
             }
 
             if context.0.is_null() {
                 CGLDestroyPixelFormat(pixel_format);
-                panic!("CGL context is null");
+                return Err("CGL context is null".into());
             }
 
             log::info!("✓ macOS CGL context created successfully");
 
-            MacOSGLContext {
+            Ok(MacOSGLContext {
                 pixel_format,
                 context,
                 previous_context: None,
-            }
+            })
         }
     }
 
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use image::RgbaImage;
+use pathfinder_renderer::scene::Scene;
+
+use crate::{ClearMode, Rasterizer};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RasterizeError {
+    /// The render did not finish in time. The worker and its GL context are discarded.
+    Timeout,
+    /// The worker thread died, e.g. because the renderer panicked.
+    WorkerFailed,
+    /// No OpenGL context could be created.
+    NoContext(String),
+}
+
+impl fmt::Display for RasterizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RasterizeError::Timeout => write!(f, "rasterizing timed out"),
+            RasterizeError::WorkerFailed => write!(f, "rasterizer thread failed"),
+            RasterizeError::NoContext(e) => write!(f, "no OpenGL context: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RasterizeError {}
+
+type RenderFn = Box<dyn FnMut(Scene, ClearMode) -> RgbaImage>;
+type Factory = Arc<dyn Fn() -> RenderFn + Send + Sync>;
+
+struct Worker {
+    jobs: Sender<(Scene, ClearMode)>,
+    results: Receiver<RgbaImage>,
+}
+
+impl Worker {
+    fn spawn(factory: Factory) -> Worker {
+        let (jobs, job_rx) = mpsc::channel::<(Scene, ClearMode)>();
+        let (result_tx, results) = mpsc::channel();
+        thread::Builder::new()
+            .name("rasterizer".into())
+            .spawn(move || {
+                // the GL context is created on and bound to this thread
+                let mut render = factory();
+                for (scene, background) in job_rx {
+                    if result_tx.send(render(scene, background)).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("failed to spawn the rasterizer thread");
+        Worker { jobs, results }
+    }
+}
+
+/// A `Rasterizer` on a worker thread that gives up on renders taking longer than `timeout`.
+///
+/// Some drivers hang in `glFinish` or `glReadPixels` on bad input. GL calls can't be
+/// interrupted, so after a timeout the worker is abandoned together with its context
+/// (the thread is leaked until the driver returns) and the next call starts a fresh one.
+pub struct WatchdogRasterizer {
+    timeout: Duration,
+    factory: Factory,
+    worker: Option<Worker>,
+}
+
+impl WatchdogRasterizer {
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_setup(timeout, |_| {})
+    }
+
+    /// Like `new`, `setup` configures every new `Rasterizer` (sRGB, pixel format, ...).
+    pub fn with_setup(timeout: Duration, setup: impl Fn(&mut Rasterizer) + Send + Sync + 'static) -> Self {
+        Self::with_factory(timeout, Arc::new(move || {
+            let mut rasterizer = Rasterizer::new();
+            setup(&mut rasterizer);
+            Box::new(move |scene: Scene, background: ClearMode| rasterizer.rasterize(scene, background)) as RenderFn
+        }))
+    }
+
+    fn with_factory(timeout: Duration, factory: Factory) -> Self {
+        WatchdogRasterizer { timeout, factory, worker: None }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn rasterize(&mut self, scene: Scene, background: impl Into<ClearMode>) -> Result<RgbaImage, RasterizeError> {
+        let factory = &self.factory;
+        let worker = self.worker.get_or_insert_with(|| Worker::spawn(factory.clone()));
+        if worker.jobs.send((scene, background.into())).is_err() {
+            self.worker = None;
+            return Err(RasterizeError::WorkerFailed);
+        }
+        match worker.results.recv_timeout(self.timeout) {
+            Ok(image) => Ok(image),
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("rasterizing took longer than {:?}, discarding the GL context", self.timeout);
+                self.worker = None;
+                Err(RasterizeError::Timeout)
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                self.worker = None;
+                Err(RasterizeError::WorkerFailed)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_timeout() {
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+
+    fn scene(width: f32) -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(width, 10.0)));
+        scene
+    }
+
+    // stands in for the GL renderer, anything wider than 100 hangs for a while
+    let factory: Factory = Arc::new(|| {
+        Box::new(|scene: Scene, _: ClearMode| {
+            let size = scene.view_box().size();
+            if size.x() > 100.0 {
+                thread::sleep(Duration::from_secs(2));
+            }
+            RgbaImage::new(size.x() as u32, size.y() as u32)
+        }) as RenderFn
+    });
+    let mut rasterizer = WatchdogRasterizer::with_factory(Duration::from_millis(200), factory);
+
+    assert_eq!(rasterizer.rasterize(scene(20.0), ClearMode::OpaqueWhite).unwrap().width(), 20);
+    assert_eq!(rasterizer.rasterize(scene(1000.0), ClearMode::OpaqueWhite), Err(RasterizeError::Timeout));
+    // a new worker takes over
+    assert_eq!(rasterizer.rasterize(scene(30.0), ClearMode::OpaqueWhite).unwrap().width(), 30);
+}
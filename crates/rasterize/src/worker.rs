@@ -0,0 +1,173 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use image::RgbaImage;
+use inkrender::ClearMode;
+use pathfinder_renderer::scene::Scene;
+
+use crate::{AnyRasterizer, RasterizeBackend, RasterizeError};
+
+type RenderResult = Result<RgbaImage, RasterizeError>;
+
+#[derive(Default)]
+struct State {
+    result: Option<RenderResult>,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    finished: Condvar,
+}
+
+impl Shared {
+    /// Store the result, only the first call counts.
+    fn complete(&self, result: RenderResult) {
+        let mut state = self.state.lock().unwrap();
+        if state.done {
+            return;
+        }
+        state.result = Some(result);
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.finished.notify_all();
+    }
+}
+
+/// The worker's end of a render. Dropped without a result (the worker died or is gone)
+/// it completes the render with `WorkerFailed`.
+struct Reply(Arc<Shared>);
+
+impl Drop for Reply {
+    fn drop(&mut self) {
+        self.0.complete(Err(RasterizeError::WorkerFailed));
+    }
+}
+
+struct Job {
+    scene: Scene,
+    background: ClearMode,
+    reply: Reply,
+}
+
+/// An `AnyRasterizer` on its own thread, for async UIs.
+///
+/// `render` returns a future that resolves when the worker is done, so an iced
+/// `Task::perform` or a dioxus task can await it without blocking the UI thread.
+/// Renders run one after another in the order they were requested. The GL context
+/// lives on the worker thread and is dropped with the `RasterizeWorker`.
+pub struct RasterizeWorker {
+    jobs: Sender<Job>,
+    backend: RasterizeBackend,
+}
+
+impl RasterizeWorker {
+    /// Start the worker thread. Fails like `AnyRasterizer::new`.
+    pub fn new(backend: RasterizeBackend) -> Result<Self, RasterizeError> {
+        let (jobs, job_rx) = mpsc::channel::<Job>();
+        let (init_tx, init_rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("rasterizer".into())
+            .spawn(move || {
+                // the GL context is created on and bound to this thread
+                let mut rasterizer = match AnyRasterizer::new(backend) {
+                    Ok(rasterizer) => {
+                        let _ = init_tx.send(Ok(rasterizer.backend()));
+                        rasterizer
+                    }
+                    Err(e) => {
+                        let _ = init_tx.send(Err(e));
+                        return;
+                    }
+                };
+                for Job { scene, background, reply } in job_rx {
+                    reply.0.complete(Ok(rasterizer.rasterize(scene, background)));
+                }
+            })
+            .expect("failed to spawn the rasterizer thread");
+        let backend = init_rx.recv().map_err(|_| RasterizeError::WorkerFailed)??;
+        Ok(RasterizeWorker { jobs, backend })
+    }
+
+    /// The backend of the worker's rasterizer, never `Auto`.
+    pub fn backend(&self) -> RasterizeBackend {
+        self.backend
+    }
+
+    /// Rasterize `scene` on the worker.
+    ///
+    /// The future does not borrow the worker and works with any executor.
+    pub fn render(&self, scene: Scene, background: impl Into<ClearMode>) -> RenderFuture {
+        let shared = Arc::new(Shared::default());
+        // if the worker is gone, the returned job drops its reply and fails the render
+        let _ = self.jobs.send(Job { scene, background: background.into(), reply: Reply(shared.clone()) });
+        RenderFuture { shared }
+    }
+
+    /// Like `render`, blocking until the image is ready.
+    pub fn render_blocking(&self, scene: Scene, background: impl Into<ClearMode>) -> RenderResult {
+        self.render(scene, background).wait()
+    }
+}
+
+/// A render in progress on a `RasterizeWorker`.
+pub struct RenderFuture {
+    shared: Arc<Shared>,
+}
+
+impl RenderFuture {
+    /// Block until the render is done.
+    pub fn wait(self) -> RenderResult {
+        let mut state = self.shared.state.lock().unwrap();
+        while !state.done {
+            state = self.shared.finished.wait(state).unwrap();
+        }
+        state.result.take().unwrap_or(Err(RasterizeError::WorkerFailed))
+    }
+}
+
+impl Future for RenderFuture {
+    type Output = RenderResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<RenderResult> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.done {
+            return Poll::Ready(state.result.take().unwrap_or(Err(RasterizeError::WorkerFailed)));
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+#[test]
+fn test_render_async() {
+    use pathfinder_geometry::{rect::RectF, vector::Vector2F};
+
+    fn scene(width: f32) -> Scene {
+        let mut scene = Scene::new();
+        scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(width, 10.0)));
+        scene
+    }
+
+    let worker = RasterizeWorker::new(RasterizeBackend::Cpu).unwrap();
+    assert_eq!(worker.backend(), RasterizeBackend::Cpu);
+    let (a, b) = pollster::block_on(async {
+        let a = worker.render(scene(20.0), ClearMode::OpaqueWhite).await.unwrap();
+        let b = worker.render(scene(30.0), ClearMode::OpaqueWhite).await.unwrap();
+        (a, b)
+    });
+    assert_eq!((a.width(), a.height()), (20, 10));
+    assert_eq!((b.width(), b.height()), (30, 10));
+    assert_eq!(a.get_pixel(5, 5).0, [255, 255, 255, 255]);
+
+    // the sync API shares the worker
+    assert_eq!(worker.render_blocking(scene(40.0), ClearMode::OpaqueWhite).unwrap().width(), 40);
+}
@@ -0,0 +1,63 @@
+use pdf::error::Result;
+use pdf::object::*;
+
+use crate::backend::Backend;
+use crate::forms;
+use crate::renderstate::RenderState;
+
+// annotation flags (PDF 32000-1:2008, 12.5.3)
+const HIDDEN: u32 = 1 << 1;
+const NO_VIEW: u32 = 1 << 5;
+
+/// Draw the normal appearance (`/AP /N`) of all visible annotations of `page`.
+///
+/// With `Backend::flatten_forms` form fields without one show their value.
+pub(crate) fn draw_annotations<R: Resolve, B: Backend>(
+    state: &mut RenderState<R, B>,
+    page: &Page,
+    resolve: &R,
+) {
+    let annots = match page.annotations.load(resolve) {
+        Ok(annots) => annots,
+        Err(e) => {
+            warn!("can't load annotations: {:?}", e);
+            return;
+        }
+    };
+    for annot in annots.iter() {
+        if let Err(e) = draw_annotation(state, annot, resolve) {
+            warn!("can't draw {} annotation: {:?}", annot.subtype, e);
+        }
+    }
+}
+
+fn draw_annotation<R: Resolve, B: Backend>(
+    state: &mut RenderState<R, B>,
+    annot: &Annot,
+    resolve: &R,
+) -> Result<()> {
+    if annot.annot_flags & (HIDDEN | NO_VIEW) != 0 {
+        return Ok(());
+    }
+    if let (Some(form), None, Some(rect)) = (state.flatten_forms(), &annot.appearance_streams, annot.rect) {
+        if annot.subtype.as_str() == "Widget" {
+            return forms::draw_field(state, annot, rect, &form, resolve);
+        }
+    }
+    let (ap, rect) = match (&annot.appearance_streams, annot.rect) {
+        (Some(ap), Some(rect)) => (ap, rect),
+        _ => return Ok(()),
+    };
+    let normal = resolve.get(ap.normal)?;
+    let form = match *normal {
+        AppearanceStreamEntry::Single(ref form) => form,
+        // widgets with several states (checkboxes) select one with /AS
+        AppearanceStreamEntry::Dict(ref states) => {
+            match annot.appearance_state.as_ref().and_then(|s| states.get(s)) {
+                Some(AppearanceStreamEntry::Single(form)) => form,
+                _ => return Ok(()),
+            }
+        }
+    };
+    state.draw_appearance(form, rect)
+}
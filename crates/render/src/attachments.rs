@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use pdf::error::Result;
+use pdf::object::{Object, PlainRef, Resolve, Stream};
+use pdf::primitive::{Dictionary, Primitive};
+
+/// A file embedded in the document.
+#[derive(Clone, Debug)]
+pub struct Attachment {
+    pub name: String,
+    /// `/Subtype` of the file stream, e.g. `text/plain`
+    pub mime: Option<String>,
+    pub data: Arc<[u8]>,
+}
+
+/// Read the embedded files of the document: the `/EmbeddedFiles` name tree
+/// and the files of `/FileAttachment` annotations.
+///
+/// Files referenced from both places are listed once.
+pub fn load_attachments(catalog: PlainRef, resolve: &impl Resolve) -> Result<Vec<Attachment>> {
+    let catalog = resolve.resolve(catalog)?.into_dictionary()?;
+    let mut loader = Loader { resolve, seen: HashSet::new(), attachments: vec![] };
+
+    if let Some(names) = catalog.get("Names") {
+        let names = names.clone().resolve(resolve)?.into_dictionary()?;
+        if let Some(tree) = names.get("EmbeddedFiles") {
+            loader.name_tree(tree, 0)?;
+        }
+    }
+    if let Some(pages) = catalog.get("Pages") {
+        loader.page_tree(pages, 0)?;
+    }
+    Ok(loader.attachments)
+}
+
+// guard against cycles in broken trees
+const MAX_DEPTH: usize = 32;
+
+struct Loader<'a, R> {
+    resolve: &'a R,
+    // file streams already read
+    seen: HashSet<PlainRef>,
+    attachments: Vec<Attachment>,
+}
+
+impl<'a, R: Resolve> Loader<'a, R> {
+    fn dict(&self, p: &Primitive) -> Result<Dictionary> {
+        p.clone().resolve(self.resolve)?.into_dictionary()
+    }
+
+    fn name_tree(&mut self, node: &Primitive, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Ok(());
+        }
+        let node = self.dict(node)?;
+        if let Some(Primitive::Array(names)) = node.get("Names") {
+            for pair in names.chunks_exact(2) {
+                let key = pair[0].clone().resolve(self.resolve)?.as_string()?.to_string_lossy();
+                if let Err(e) = self.file_spec(&pair[1], Some(key)) {
+                    warn!("can't read embedded file: {:?}", e);
+                }
+            }
+        }
+        if let Some(Primitive::Array(kids)) = node.get("Kids") {
+            for kid in kids {
+                self.name_tree(kid, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn page_tree(&mut self, node: &Primitive, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            return Ok(());
+        }
+        let node = self.dict(node)?;
+        if let Some(kids) = node.get("Kids") {
+            if let Primitive::Array(kids) = kids.clone().resolve(self.resolve)? {
+                for kid in &kids {
+                    self.page_tree(kid, depth + 1)?;
+                }
+            }
+        }
+        if let Some(annots) = node.get("Annots") {
+            if let Primitive::Array(annots) = annots.clone().resolve(self.resolve)? {
+                for annot in &annots {
+                    let annot = self.dict(annot)?;
+                    if annot.get("Subtype").and_then(|s| s.as_name().ok()) != Some("FileAttachment") {
+                        continue;
+                    }
+                    if let Some(fs) = annot.get("FS") {
+                        if let Err(e) = self.file_spec(fs, None) {
+                            warn!("can't read attached file: {:?}", e);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn file_spec(&mut self, spec: &Primitive, key: Option<String>) -> Result<()> {
+        let spec = self.dict(spec)?;
+        let ef = match spec.get("EF") {
+            Some(ef) => self.dict(ef)?,
+            // a reference to an external file
+            None => return Ok(()),
+        };
+        let stream_ref = match ef.get("UF").or_else(|| ef.get("F")) {
+            Some(&Primitive::Reference(r)) => r,
+            _ => return Ok(()),
+        };
+        if !self.seen.insert(stream_ref) {
+            return Ok(());
+        }
+
+        let name = match spec.get("UF").or_else(|| spec.get("F")) {
+            Some(p) => p.clone().resolve(self.resolve)?.as_string()?.to_string_lossy(),
+            None => key.unwrap_or_default(),
+        };
+        let stream = self.resolve.resolve(stream_ref)?;
+        let mime = match stream {
+            Primitive::Stream(ref s) => s.info.get("Subtype").and_then(|p| p.as_name().ok()).map(String::from),
+            _ => None,
+        };
+        let data = Stream::<()>::from_primitive(stream, self.resolve)?.data(self.resolve)?;
+        self.attachments.push(Attachment { name, mime, data });
+        Ok(())
+    }
+}
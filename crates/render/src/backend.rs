@@ -1,16 +1,22 @@
-use pathfinder_content::{fill::FillRule, outline::Outline, stroke::StrokeStyle};
+use pathfinder_content::{fill::FillRule, gradient::Gradient, outline::Outline, stroke::StrokeStyle};
 use pathfinder_geometry::{rect::RectF, transform2d::Transform2F};
 
 use super::{Fill, FontEntry, TextSpan};
-use inkfont::Glyph;
+use crate::forms::FormDefaults;
+use crate::type3::Type3Font;
+use inkfont::{Glyph, GlyphId};
 use pdf::error::PdfError;
 use pdf::font::Font as PdfFont;
 use pdf::{
     content::Op,
-    object::{ImageXObject, MaybeRef, Ref, Resolve, Resources, XObject},
+    object::{ImageXObject, MaybeRef, PlainRef, Ref, Resolve, Resources, XObject},
 };
 use std::sync::Arc;
 
+/// A soft mask drawn by `Backend::begin_mask` .. `end_mask`.
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub struct MaskId(pub usize);
+
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum BlendMode {
     Overlay,
@@ -37,6 +43,21 @@ pub trait Backend {
     );
     
     fn set_view_box(&mut self, r: RectF);
+
+    /// Fill `outline` with `gradient`, which is in device space like the outline after `transform`.
+    ///
+    /// Used for shading patterns. Backends without gradients leave the area empty.
+    fn draw_gradient(
+        &mut self,
+        outline: &Outline,
+        gradient: &Gradient,
+        fill_rule: FillRule,
+        transform: Transform2F,
+        alpha: f32,
+        mode: BlendMode,
+        clip: Option<Self::ClipPathId>,
+    ) {
+    }
     
     fn draw_image(
         &mut self,
@@ -68,6 +89,18 @@ pub trait Backend {
     ) {
         self.draw(&glyph.path, mode, FillRule::Winding, transform, clip);
     }
+
+    /// Draw glyph `gid` of a Type3 font, with the fill rules and clip paths of its procedure.
+    fn draw_type3_glyph(
+        &mut self,
+        font: &Type3Font,
+        gid: GlyphId,
+        mode: &DrawMode,
+        transform: Transform2F,
+        clip: Option<Self::ClipPathId>,
+    ) {
+        font.draw_glyph(self, gid, mode, transform, clip);
+    }
     
     fn get_font(
         &mut self,
@@ -76,6 +109,79 @@ pub trait Backend {
     ) -> Result<Option<Arc<FontEntry>>, PdfError>;
     fn add_text(&mut self, span: TextSpan, clip: Option<Self::ClipPathId>);
 
+    /// Whether content in the optional content group `ocg` is drawn.
+    fn layer_visible(&self, ocg: PlainRef) -> bool {
+        true
+    }
+
+    /// Start drawing an isolated or knockout transparency group into an offscreen surface
+    /// with a transparent backdrop. With `knockout` each element replaces what the group
+    /// drew before it instead of being composited over it.
+    ///
+    /// Returns false if the backend has no offscreen surfaces, the group is then drawn
+    /// like any other form.
+    fn begin_group(&mut self, knockout: bool) -> bool {
+        false
+    }
+    /// Finish the group started by the last `begin_group`, see `draw_group`.
+    fn end_group(&mut self) {}
+    /// Composite the group finished by the last `end_group` with `alpha` and `mode`.
+    fn draw_group(&mut self, alpha: f32, mode: BlendMode, clip: Option<Self::ClipPathId>) {}
+
+    /// Start drawing the group of a soft mask into an offscreen surface. With `luminosity`
+    /// the luminosity of what is drawn becomes the mask, its alpha otherwise. `backdrop`
+    /// is the value of the mask where the group draws nothing.
+    ///
+    /// Returns false if the backend has no offscreen surfaces, content under the mask is
+    /// then drawn without it.
+    fn begin_mask(&mut self, luminosity: bool, backdrop: f32) -> bool {
+        false
+    }
+    /// Finish the mask started by the last `begin_mask`.
+    fn end_mask(&mut self) -> Option<MaskId> {
+        None
+    }
+    /// Draw under `mask` until `end_masked`.
+    fn begin_masked(&mut self, mask: MaskId) {}
+    fn end_masked(&mut self) {}
+
+    /// Start drawing one period of a tiling pattern into an offscreen surface. `cell` is the
+    /// period in pattern space, `transform` maps pattern space to device space.
+    ///
+    /// Returns the transform to draw the content of the cell with instead of `transform`,
+    /// or `None` if the backend has no offscreen surfaces: the cell is then drawn once per
+    /// step over the filled area.
+    fn begin_tile(&mut self, cell: RectF, transform: Transform2F) -> Option<Transform2F> {
+        None
+    }
+    /// Finish the cell started by the last `begin_tile`, see `fill_tile`.
+    fn end_tile(&mut self) {}
+    /// Fill `outline` with the cell finished by the last `end_tile`, repeated in both directions.
+    fn fill_tile(
+        &mut self,
+        outline: &Outline,
+        fill_rule: FillRule,
+        transform: Transform2F,
+        alpha: f32,
+        mode: BlendMode,
+        clip: Option<Self::ClipPathId>,
+    ) {
+    }
+
+    /// Draw the values of form fields that have no appearance stream, with these defaults.
+    ///
+    /// Off by default, viewers that let the user edit the fields draw them themselves.
+    fn flatten_forms(&self) -> Option<Arc<FormDefaults>> {
+        None
+    }
+
+    /// Abort on the first operator that fails instead of skipping it.
+    ///
+    /// Useful for validating files, viewers want to show as much as possible.
+    fn strict(&self) -> bool {
+        false
+    }
+
     /// The following functions are for debugging PDF files and not relevant for rendering them.
     fn bug_text_no_font(&mut self, data: &[u8]) {}
     fn bug_text_invisible(&mut self, text: &str) {}
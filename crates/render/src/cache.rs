@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use pdf::object::*;
@@ -9,6 +10,7 @@ use pathfinder_geometry::vector::Vector2I;
 use pathfinder_content::pattern::Image;
 
 use crate::BlendMode;
+use crate::forms::FormDefaults;
 
 use super::fontentry::FontEntry;
 use super::image::load_image;
@@ -32,6 +34,10 @@ pub struct Cache {
     images: Arc<SyncCache<(Ref<XObject>, BlendMode), ImageResult>>,
     std: StandardCache,
     missing_fonts: Vec<Name>,
+    hidden_layers: HashSet<PlainRef>,
+    strict: bool,
+    offscreen: bool,
+    flatten_forms: Option<Arc<FormDefaults>>,
 }
 impl Cache {
     pub fn new() -> Cache {
@@ -43,8 +49,44 @@ impl Cache {
             #[cfg(not(target_arch = "wasm32"))]
             std: StandardCache::new(),
             missing_fonts: Vec::new(),
+            hidden_layers: HashSet::new(),
+            strict: false,
+            offscreen: true,
+            flatten_forms: None,
         }
     }
+    /// Skip content in these optional content groups, see `Layers::hidden`.
+    pub fn set_hidden_layers(&mut self, hidden: HashSet<PlainRef>) {
+        self.hidden_layers = hidden;
+    }
+    pub fn layer_visible(&self, ocg: PlainRef) -> bool {
+        !self.hidden_layers.contains(&ocg)
+    }
+    /// Fail on broken content instead of skipping the offending operators, see `Backend::strict`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+    /// Draw transparency groups into render targets. On by default.
+    ///
+    /// Turn it off for rasterizers that can't composite render targets, like the
+    /// `CpuRasterizer`: groups are then drawn in place, the opacity applies to each element.
+    pub fn set_offscreen(&mut self, offscreen: bool) {
+        self.offscreen = offscreen;
+    }
+    pub fn offscreen(&self) -> bool {
+        self.offscreen
+    }
+    /// Draw the values of form fields as page content, e.g. for printing, see `Backend::flatten_forms`.
+    /// `None` leaves fields without an appearance stream out.
+    pub fn set_flatten_forms(&mut self, form: Option<FormDefaults>) {
+        self.flatten_forms = form.map(Arc::new);
+    }
+    pub fn flatten_forms(&self) -> Option<Arc<FormDefaults>> {
+        self.flatten_forms.clone()
+    }
     pub fn get_font(
         &mut self,
         pdf_font: &MaybeRef<PdfFont>,
@@ -0,0 +1,125 @@
+use pdf::object::ColorSpace;
+use pdf::primitive::Primitive;
+
+/// The color space to draw an `/ICCBased` color space in, for a profile with `components` components.
+///
+/// Profiles are not applied: gray, RGB and CMYK profiles are treated as the device spaces,
+/// which is close for the common ones (sRGB, Adobe RGB, SWOP). Other profiles use `alternate`.
+pub(crate) fn icc_base<'a>(components: usize, alternate: Option<&'a ColorSpace>) -> Option<&'a ColorSpace> {
+    match components {
+        1 => Some(&ColorSpace::DeviceGray),
+        3 => Some(&ColorSpace::DeviceRGB),
+        4 => Some(&ColorSpace::DeviceCMYK),
+        _ => alternate,
+    }
+}
+
+/// A `/Lab` color space. pdf has no variant for it, so it arrives as `ColorSpace::Other`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LabSpace {
+    white_point: [f32; 3],
+    // a_min, a_max, b_min, b_max
+    range: [f32; 4],
+}
+
+impl LabSpace {
+    pub fn parse(cs: &ColorSpace) -> Option<LabSpace> {
+        let args = match cs {
+            ColorSpace::Other(args) => args,
+            _ => return None,
+        };
+        if args.first()?.as_name().ok()? != "Lab" {
+            return None;
+        }
+        let dict = args.get(1)?.as_dictionary().ok()?;
+        let mut white_point = [0.0; 3];
+        numbers(dict.get("WhitePoint")?, &mut white_point)?;
+        let mut range = [-100.0, 100.0, -100.0, 100.0];
+        if let Some(p) = dict.get("Range") {
+            numbers(p, &mut range)?;
+        }
+        Some(LabSpace { white_point, range })
+    }
+
+    /// sRGB of a color in this space.
+    pub fn rgb(&self, [l, a, b]: [f32; 3]) -> [f32; 3] {
+        let [a_min, a_max, b_min, b_max] = self.range;
+        lab_to_rgb([l.clamp(0.0, 100.0), a.clamp(a_min, a_max), b.clamp(b_min, b_max)], self.white_point)
+    }
+
+    /// sRGB of an 8 bit image sample, using the default `/Decode` array.
+    pub fn decode(&self, sample: &[u8]) -> [f32; 3] {
+        let [a_min, a_max, b_min, b_max] = self.range;
+        let v = |i: usize| sample[i] as f32 / 255.0;
+        self.rgb([v(0) * 100.0, a_min + v(1) * (a_max - a_min), b_min + v(2) * (b_max - b_min)])
+    }
+}
+
+fn numbers<const N: usize>(p: &Primitive, out: &mut [f32; N]) -> Option<()> {
+    let array = p.as_array().ok()?;
+    if array.len() != N {
+        return None;
+    }
+    for (o, p) in out.iter_mut().zip(array) {
+        *o = p.as_number().ok()?;
+    }
+    Some(())
+}
+
+const D65: [f32; 3] = [0.9505, 1.0, 1.089];
+
+/// Convert CIE L*a*b* relative to `white_point` (XYZ, e.g. D50 `[0.9642, 1.0, 0.8249]`) to sRGB.
+///
+/// The white point is adapted to D65 with the Bradford transform, the result is clamped to `0..=1`.
+pub fn lab_to_rgb([l, a, b]: [f32; 3], white_point: [f32; 3]) -> [f32; 3] {
+    // PDF 32000-1:2008, 8.6.5.4
+    fn g(x: f32) -> f32 {
+        if x >= 6.0 / 29.0 {
+            x * x * x
+        } else {
+            108.0 / 841.0 * (x - 4.0 / 29.0)
+        }
+    }
+    let m = (l + 16.0) / 116.0;
+    let xyz = [
+        white_point[0] * g(m + a / 500.0),
+        white_point[1] * g(m),
+        white_point[2] * g(m - b / 200.0),
+    ];
+    let xyz = adapt(xyz, white_point, D65);
+
+    const XYZ_TO_RGB: [[f32; 3]; 3] = [
+        [3.2404542, -1.5371385, -0.4985314],
+        [-0.9692660, 1.8760108, 0.0415560],
+        [0.0556434, -0.2040259, 1.0572252],
+    ];
+    mul(XYZ_TO_RGB, xyz).map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    })
+}
+
+/// Bradford chromatic adaptation of `xyz` from white point `from` to `to`.
+fn adapt(xyz: [f32; 3], from: [f32; 3], to: [f32; 3]) -> [f32; 3] {
+    const BRADFORD: [[f32; 3]; 3] = [
+        [0.8951, 0.2664, -0.1614],
+        [-0.7502, 1.7135, 0.0367],
+        [0.0389, -0.0685, 1.0296],
+    ];
+    const BRADFORD_INV: [[f32; 3]; 3] = [
+        [0.9869929, -0.1470543, 0.1599627],
+        [0.4323053, 0.5183603, 0.0492912],
+        [-0.0085287, 0.0400428, 0.9684867],
+    ];
+    let (src, dst) = (mul(BRADFORD, from), mul(BRADFORD, to));
+    let cone = mul(BRADFORD, xyz);
+    mul(BRADFORD_INV, [0, 1, 2].map(|i| cone[i] * dst[i] / src[i]))
+}
+
+fn mul(m: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
@@ -0,0 +1,159 @@
+//! Parsing content streams that may contain broken operators.
+
+use pdf::content::{parse_ops, Content, Op};
+use pdf::error::Result;
+use pdf::object::Resolve;
+
+/// The operators of a page's content streams.
+///
+/// Like `Content::operations`, but when that fails and `strict` is off the content
+/// is parsed one operator at a time and the ones that fail are left out.
+pub(crate) fn content_ops(contents: &Content, resolve: &impl Resolve, strict: bool) -> Result<Vec<Op>> {
+    match contents.operations(resolve) {
+        Ok(ops) => Ok(ops),
+        Err(e) if strict => Err(e),
+        Err(e) => {
+            warn!("can't parse the content ({:?}), skipping broken operators", e);
+            let mut data = vec![];
+            for part in &contents.parts {
+                data.extend_from_slice(&t!(part.data(resolve)));
+                // parts end between tokens
+                data.push(b'\n');
+            }
+            Ok(recover_ops(&data, resolve))
+        }
+    }
+}
+
+/// Like `parse_ops`, see `content_ops`.
+pub(crate) fn parse_ops_lenient(data: &[u8], resolve: &impl Resolve, strict: bool) -> Result<Vec<Op>> {
+    match parse_ops(data, resolve) {
+        Ok(ops) => Ok(ops),
+        Err(e) if strict => Err(e),
+        Err(e) => {
+            warn!("can't parse the content ({:?}), skipping broken operators", e);
+            Ok(recover_ops(data, resolve))
+        }
+    }
+}
+
+fn recover_ops(data: &[u8], resolve: &impl Resolve) -> Vec<Op> {
+    let mut ops = vec![];
+    for statement in statements(data) {
+        match parse_ops(statement, resolve) {
+            Ok(parsed) => ops.extend(parsed),
+            Err(e) => warn!("skipping {:?}: {:?}", String::from_utf8_lossy(statement), e),
+        }
+    }
+    ops
+}
+
+fn is_white(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(b: u8) -> bool {
+    matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+/// Split content into statements: the operands and the operator that ends them.
+///
+/// Only the token structure is looked at (strings, arrays, dictionaries, inline images),
+/// operand counts are left to `parse_ops`.
+fn statements(data: &[u8]) -> Vec<&[u8]> {
+    let mut out = vec![];
+    let mut start = 0;
+    // arrays and dictionaries, a keyword inside them is not an operator
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b if is_white(b) => i += 1,
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'(' => {
+                let mut nesting = 0;
+                while i < data.len() {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => nesting += 1,
+                        b')' => {
+                            nesting -= 1;
+                            if nesting == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => {
+                depth += 1;
+                i += 2;
+            }
+            b'>' if data.get(i + 1) == Some(&b'>') => {
+                depth = depth.saturating_sub(1);
+                i += 2;
+            }
+            b'<' => {
+                while i < data.len() && data[i] != b'>' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'[' => {
+                depth += 1;
+                i += 1;
+            }
+            b']' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b'/' => {
+                i += 1;
+                while i < data.len() && !is_white(data[i]) && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+            }
+            b if is_delimiter(b) => i += 1,
+            _ => {
+                let token_start = i;
+                while i < data.len() && !is_white(data[i]) && !is_delimiter(data[i]) {
+                    i += 1;
+                }
+                let token = &data[token_start..i];
+                let operand = token[0].is_ascii_digit()
+                    || matches!(token[0], b'+' | b'-' | b'.')
+                    || matches!(token, b"true" | b"false" | b"null");
+                if operand || depth > 0 {
+                    continue;
+                }
+                if token == b"BI" {
+                    i = inline_image_end(data, i);
+                }
+                out.push(&data[start..i]);
+                start = i;
+            }
+        }
+    }
+    out
+}
+
+/// The end of the `EI` of an inline image whose `BI` ends at `i`.
+fn inline_image_end(data: &[u8], i: usize) -> usize {
+    let keyword = |at: usize, word: &[u8]| {
+        data[at..].starts_with(word)
+            && (at == 0 || is_white(data[at - 1]))
+            && data.get(at + word.len()).map_or(true, |&b| is_white(b))
+    };
+    let Some(id) = (i..data.len()).find(|&at| keyword(at, b"ID")) else {
+        return data.len();
+    };
+    // the image data is binary, and may contain anything but "EI" between white space
+    (id + 3..data.len()).find(|&at| keyword(at, b"EI")).map_or(data.len(), |at| at + 2)
+}
@@ -1,13 +1,14 @@
 use glyphmatcher::FontDb;
 use std::borrow::Cow;
 use pdf::error::{ PdfError, Result };
-use pdf::font::Font as PdfFont;
+use pdf::font::{ Font as PdfFont, FontType };
 use pdf::object::*;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::PathBuf;
 
 use super::FontEntry;
+use super::type3::Type3Font;
 use inkfont;
 use globalcache::{ sync::SyncCache, ValueSize };
 use std::hash::{ Hash, Hasher };
@@ -121,6 +122,13 @@ pub fn load_font(
     let pdf_font = font_ref.clone();
     debug!("loading {:?}", pdf_font);
 
+    if pdf_font.subtype == FontType::Type3 {
+        debug!("loading Type3 font");
+        let font = Type3Font::load(&pdf_font, resolve)?;
+        let font = FontRc(Arc::new(font));
+        return Ok(Some(FontEntry::build(font, pdf_font, None, resolve, cache.require_unique_unicode)?));
+    }
+
     let font: FontRc = match pdf_font.embedded_data(resolve) {
         Some(Ok(data)) => {
             debug!("loading embedded font");
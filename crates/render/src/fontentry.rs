@@ -5,7 +5,7 @@ use istring::SmallString;
 use itertools::Itertools;
 use pdf::encoding::BaseEncoding;
 use pdf::error::PdfError;
-use pdf::font::{ CidToGidMap, Font as PdfFont, Widths };
+use pdf::font::{ CidToGidMap, Font as PdfFont, FontType, Widths };
 use pdf::object::{ MaybeRef, Resolve };
 use inkencoding::{ glyphname_to_unicode, Encoding };
 use std::collections::{ HashMap, HashSet };
@@ -17,6 +17,8 @@ pub struct FontEntry {
     pub widths: Option<Widths>,
     pub is_cid: bool,
     pub name: String,
+    // unicode of each glyph, from the lowest code using it
+    glyph_unicode: HashMap<GlyphId, SmallString>,
 }
 
 impl FontEntry {
@@ -317,13 +319,16 @@ impl FontEntry {
         }
 
         let widths = pdf_font.widths(resolve)?;
-        let name = pdf_font.name
-            .as_ref()
-            .ok_or_else(|| PdfError::Other {
-                msg: "font has no name".into(),
-            })?
-            .as_str()
-            .into();
+        let name = match pdf_font.name {
+            Some(ref name) => name.as_str().into(),
+            // BaseFont is optional for Type3 fonts
+            None if pdf_font.subtype == FontType::Type3 => "Type3".into(),
+            None => {
+                return Err(PdfError::Other {
+                    msg: "font has no name".into(),
+                });
+            }
+        };
 
         if require_unique_unicode {
             let mut next_code = 0xe000;
@@ -377,6 +382,15 @@ impl FontEntry {
             }
         }
 
+        let mut codes: Vec<_> = cmap.iter().collect();
+        codes.sort_unstable_by_key(|&(&code, _)| code);
+        let mut glyph_unicode = HashMap::new();
+        for (_, (gid, uni)) in codes {
+            if let Some(uni) = uni {
+                glyph_unicode.entry(*gid).or_insert_with(|| uni.clone());
+            }
+        }
+
         Ok(FontEntry {
             font,
             pdf_font,
@@ -384,8 +398,17 @@ impl FontEntry {
             is_cid,
             widths,
             name,
+            glyph_unicode,
         })
     }
+
+    /// The text a glyph stands for, e.g. for text extraction.
+    ///
+    /// Usually one character, ligatures map to several (`fi`). If several codes draw the
+    /// glyph, the text of the lowest one is used.
+    pub fn glyph_to_unicode(&self, gid: GlyphId) -> Option<SmallString> {
+        self.glyph_unicode.get(&gid).cloned()
+    }
 }
 
 impl globalcache::ValueSize for FontEntry {
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use pdf::content::{parse_ops, Op, Point};
+use pdf::error::Result;
+use pdf::object::*;
+use pdf::primitive::{Dictionary, PdfString, Primitive};
+
+use crate::backend::Backend;
+use crate::renderstate::RenderState;
+
+// fields are nested this deep at most when looking up inherited entries
+const MAX_FIELD_DEPTH: usize = 16;
+// space between the border of a field and its text (in pt)
+const PADDING: f32 = 2.0;
+
+/// The defaults of the interactive form of a document (`/AcroForm`), needed to draw the
+/// values of fields without an appearance stream, see `Cache::set_flatten_forms`.
+#[derive(Clone, Debug, Default)]
+pub struct FormDefaults {
+    /// `/DR`, has the fonts named in `/DA`
+    pub resources: Option<Arc<Resources>>,
+    /// `/DA`, for fields that have none
+    pub appearance: Option<String>,
+}
+
+impl FormDefaults {
+    /// Read `/AcroForm` from the document catalog. Empty if the document has no form.
+    pub fn load(catalog: PlainRef, resolve: &impl Resolve) -> Result<FormDefaults> {
+        let catalog = resolve.resolve(catalog)?.into_dictionary()?;
+        let form = match catalog.get("AcroForm") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => return Ok(FormDefaults::default()),
+        };
+        let resources = match form.get("DR") {
+            Some(p) => Some(Arc::new(Resources::from_primitive(p.clone(), resolve)?)),
+            None => None,
+        };
+        let appearance = match form.get("DA") {
+            Some(p) => Some(p.clone().resolve(resolve)?.as_string()?.to_string_lossy()),
+            None => None,
+        };
+        Ok(FormDefaults { resources, appearance })
+    }
+}
+
+/// Draw the value of the text or choice field of the widget `annot` as a single line
+/// of text in `rect`, with the font and color of its `/DA`.
+///
+/// For widgets without an appearance stream, the others show their value already.
+pub(crate) fn draw_field<R: Resolve, B: Backend>(
+    state: &mut RenderState<R, B>,
+    annot: &Annot,
+    rect: Rectangle,
+    form: &FormDefaults,
+    resolve: &R,
+) -> Result<()> {
+    let value = match field_entry(&annot.other, "V", resolve)? {
+        Some(Primitive::String(value)) => value,
+        // buttons have names, lists of choices arrays
+        _ => return Ok(()),
+    };
+    let appearance = match field_entry(&annot.other, "DA", resolve)? {
+        Some(p) => p.as_string()?.to_string_lossy(),
+        None => form.appearance.clone().unwrap_or_default(),
+    };
+    let ops = field_ops(&appearance, value, rect.top - rect.bottom, resolve)?;
+    state.draw_in_rect(&ops, form.resources.as_deref(), rect)
+}
+
+// one line of text, vertically centered
+fn field_ops(appearance: &str, value: PdfString, height: f32, resolve: &impl Resolve) -> Result<Vec<Op>> {
+    let mut ops = vec![Op::BeginText];
+    let mut size = 0.0;
+    for op in parse_ops(appearance.as_bytes(), resolve)? {
+        match op {
+            // size 0 fits the text to the field
+            Op::TextFont { name, size: font_size } => {
+                size = if font_size > 0.0 { font_size } else { 0.7 * height };
+                ops.push(Op::TextFont { name, size });
+            }
+            op => ops.push(op),
+        }
+    }
+    let baseline = 0.5 * (height - size) + 0.2 * size;
+    ops.push(Op::MoveTextPosition { translation: Point { x: PADDING, y: baseline } });
+    ops.push(Op::TextDraw { text: value });
+    ops.push(Op::EndText);
+    Ok(ops)
+}
+
+// `key` of the field of a widget, or inherited from its parent fields
+fn field_entry(widget: &Dictionary, key: &str, resolve: &impl Resolve) -> Result<Option<Primitive>> {
+    let mut dict = widget.clone();
+    for _ in 0..MAX_FIELD_DEPTH {
+        if let Some(p) = dict.get(key) {
+            return Ok(Some(p.clone().resolve(resolve)?));
+        }
+        dict = match dict.get("Parent") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => break,
+        };
+    }
+    Ok(None)
+}
@@ -0,0 +1,62 @@
+use pdf::error::{PdfError, Result};
+use pdf::function::Function;
+
+/// Evaluate a PDF function, e.g. the tint transform of a Separation or DeviceN color space.
+///
+/// Exponential functions (type 2) are evaluated here, with the input clamped to the domain.
+/// Sampled (type 0) and PostScript (type 4) functions are left to the `pdf` crate.
+pub fn eval_function(f: &Function, input: &[f32], out: &mut [f32]) -> Result<()> {
+    match *f {
+        Function::Interpolated(ref dims) => {
+            let &x = input.first().ok_or(PdfError::Other { msg: "function without input".into() })?;
+            if out.len() < dims.len() {
+                return Err(PdfError::Other {
+                    msg: format!("function has {} outputs, got room for {}", dims.len(), out.len()),
+                });
+            }
+            for (y, dim) in out.iter_mut().zip(dims) {
+                let (x0, x1) = dim.input_range;
+                let x = x.max(x0).min(x1);
+                let v = dim.c0 + x.powf(dim.exponent) * (dim.c1 - dim.c0);
+                let (y0, y1) = dim.output_range;
+                *y = v.max(y0).min(y1);
+            }
+            Ok(())
+        }
+        _ => f.apply(input, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf::object::{NoResolve, Object};
+    use pdf::primitive::{Dictionary, Primitive};
+
+    fn numbers(n: &[f32]) -> Primitive {
+        Primitive::Array(n.iter().map(|&f| Primitive::Number(f)).collect())
+    }
+
+    #[test]
+    fn test_exponential_tint() {
+        // a spot color with CMYK 0 0.5 1 0 at full tint
+        let mut dict = Dictionary::new();
+        dict.insert("FunctionType", Primitive::Integer(2));
+        dict.insert("Domain", numbers(&[0.0, 1.0]));
+        dict.insert("C0", numbers(&[0.0, 0.0, 0.0, 0.0]));
+        dict.insert("C1", numbers(&[0.0, 0.5, 1.0, 0.0]));
+        dict.insert("N", Primitive::Number(2.0));
+        let f = Function::from_primitive(Primitive::Dictionary(dict), &NoResolve).unwrap();
+
+        let mut out = [0.0; 4];
+        eval_function(&f, &[0.5], &mut out).unwrap();
+        let expected = [0.0, 0.125, 0.25, 0.0];
+        for (a, b) in out.iter().zip(expected.iter()) {
+            std::assert_eq!((a * 1000.).round(), (b * 1000.).round(), "{:?}", out);
+        }
+
+        // outside of the domain
+        eval_function(&f, &[2.0], &mut out).unwrap();
+        std::assert_eq!(out, [0.0, 0.5, 1.0, 0.0]);
+    }
+}
@@ -3,7 +3,7 @@ use pathfinder_renderer::{paint::PaintId, scene::ClipPath};
 use pdf::object::ColorSpace;
 
 use pathfinder_geometry::{transform2d::Transform2F, rect::RectF};
-use crate::{Fill, backend::Stroke, Backend};
+use crate::{Fill, backend::{DrawMode, MaskId, Stroke}, Backend};
 
 pub struct GraphicsState<'a, B: Backend> {
     pub transform: Transform2F,
@@ -28,12 +28,15 @@ pub struct GraphicsState<'a, B: Backend> {
     pub overprint_fill: bool,
     pub overprint_stroke: bool,
     pub overprint_mode: i32,
+
+    pub soft_mask: Option<MaskId>,
 }
 
 impl<'a, B: Backend> Clone for GraphicsState<'a, B> {
     fn clone(&self) -> Self {
         GraphicsState {
             clip_path: self.clip_path.clone(),
+            soft_mask: self.soft_mask.clone(),
             .. *self
         }
     }
@@ -67,6 +70,17 @@ impl<'a, B: Backend> GraphicsState<'a, B> {
             self.stroke_paint = None;
         }
     }
+    /// Draw with the current clip path and soft mask.
+    pub fn draw_masked(&self, backend: &mut B, mode: &DrawMode, draw: impl FnOnce(&mut B, &DrawMode, Option<B::ClipPathId>)) {
+        match self.soft_mask {
+            None => draw(backend, mode, self.clip_path_id),
+            Some(mask) => {
+                backend.begin_masked(mask);
+                draw(backend, mode, self.clip_path_id);
+                backend.end_masked();
+            }
+        }
+    }
     pub fn stroke(&self) -> Stroke {
         Stroke {
             style: self.stroke_style,
@@ -7,6 +7,8 @@ use std::path::Path;
 use std::sync::Arc;
 
 use crate::BlendMode;
+use crate::function::eval_function;
+use crate::colorspace::{icc_base, LabSpace};
 
 #[derive(Hash, PartialEq, Eq, Clone)]
 pub struct ImageData<'a> {
@@ -107,7 +109,11 @@ fn resize_alpha(data: &[u8], src_width: u32, src_height: u32, dest_width: u32, d
 }
 
 pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Resolve, mode: BlendMode) -> Result<ImageData<'static>, PdfError> {
-    let raw_data = image.image_data(resolve)?;
+    let raw_data = image_bytes(image, resolve).map_err(|e| {
+        // name the filters, an unsupported one is the usual cause
+        warn!("can't decode image data (filters: {:?}): {:?}", image.inner.filters, e);
+        e
+    })?;
 
     let pixel_count = image.width as usize * image.height as usize;
 
@@ -171,17 +177,7 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
     
     fn resolve_cs<'a>(cs: &'a ColorSpace, resources: &'a Resources) -> Option<&'a ColorSpace> {
         match cs {
-            ColorSpace::Icc(icc) => {
-                match icc.info.alternate {
-                    Some(ref b) => Some(&**b),
-                    None => match icc.info.components {
-                        1 => Some(&ColorSpace::DeviceGray),
-                        3 => Some(&ColorSpace::DeviceRGB),
-                        4 => Some(&ColorSpace::DeviceCMYK),
-                        _ => None
-                    }
-                }
-            }
+            ColorSpace::Icc(icc) => icc_base(icc.info.components as usize, icc.info.alternate.as_deref()),
             ColorSpace::Named(name) => resources.color_spaces.get(name),
             _ => Some(cs),
         }
@@ -189,7 +185,20 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
 
     let cs = image.color_space.as_ref().and_then(|cs| resolve_cs(cs, &resources));
     let alpha = alpha.iter().cloned().chain(std::iter::repeat(255));
+    let lab = cs.and_then(LabSpace::parse);
     let data_ratio = (raw_data.len() * 8) / pixel_count;
+
+    // the ranges of indexed and Lab images are not 0..1, they are applied by the lookup
+    let decoded;
+    let raw_data: &[u8] = match image.decode {
+        Some(ref decode) if data_ratio % 8 == 0 && lab.is_none() && !matches!(cs, Some(ColorSpace::Indexed(..))) => {
+            let mut data = raw_data.to_vec();
+            apply_decode(&mut data, decode, data_ratio / 8);
+            decoded = data;
+            &decoded
+        }
+        _ => &raw_data,
+    };
     // dbg!(data_ratio);
 
     debug!("CS: {cs:?}");
@@ -197,9 +206,21 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
     let data = match data_ratio {
         1 | 2 | 4 | 8 => {
             let pixel_data: Cow<[u8]> = match data_ratio {
-                1 => raw_data.iter().flat_map(|&b| (0..8).map(move |i| ex(b >> i, 1))).take(pixel_count).collect::<Vec<u8>>().into(),
-                2 => raw_data.iter().flat_map(|&b| (0..4).map(move |i| ex(b >> 2*i, 2))).take(pixel_count).collect::<Vec<u8>>().into(),
-                4 => raw_data.iter().flat_map(|&b| (0..2).map(move |i| ex(b >> 4*i, 4))).take(pixel_count).collect::<Vec<u8>>().into(),
+                1 | 2 | 4 => {
+                    let bits = data_ratio as u8;
+                    let mut samples = unpack_samples(raw_data, bits, image.width as usize, pixel_count);
+                    // indices stay as they are, other samples are scaled to 8 bit like the ones above
+                    if !matches!(cs, Some(ColorSpace::Indexed(..))) {
+                        let max = (1u32 << bits) - 1;
+                        for v in samples.iter_mut() {
+                            *v = (*v as u32 * 255 / max) as u8;
+                        }
+                        if let Some(ref decode) = image.decode {
+                            apply_decode(&mut samples, decode, 1);
+                        }
+                    }
+                    samples.into()
+                }
                 8 => Cow::Borrowed(&raw_data[..pixel_count]),
                 n => return Err(PdfError::Other { msg: format!("invalid bits per component {}", n)})
             };
@@ -241,7 +262,7 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
                         Some(ColorSpace::DeviceRGB) => {
                             for (i, rgb) in lut.iter_mut().enumerate() {
                                 let mut c = [0.; 3];
-                                func.apply(&[i as f32 / 255.], &mut c)?;
+                                eval_function(func, &[i as f32 / 255.], &mut c)?;
                                 let [r, g, b] = c;
                                 *rgb = rgb2rgb(r, g, b, mode);
                             }
@@ -249,7 +270,7 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
                         Some(ColorSpace::DeviceCMYK) => {
                             for (i, rgb) in lut.iter_mut().enumerate() {
                                 let mut c = [0.; 4];
-                                func.apply(&[i as f32 / 255.], &mut c)?;
+                                eval_function(func, &[i as f32 / 255.], &mut c)?;
                                 let [c, m, y, k] = c;
                                 *rgb = cmyk2rgb([(c * 255.) as u8, (m * 255.) as u8, (y * 255.) as u8, (k * 255.) as u8], mode);
                             }
@@ -269,6 +290,14 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
                 _ => unimplemented!("cs={:?}", cs),
             }
         }
+        24 if lab.is_some() => {
+            let lab = lab.unwrap();
+            raw_data[..pixel_count * 3].chunks_exact(3).zip(alpha).map(|(c, a)| {
+                let [r, g, b] = lab.decode(c);
+                let [r, g, b] = rgb2rgb(r, g, b, mode);
+                ColorU { r, g, b, a }
+            }).collect()
+        }
         24 => {
             if !matches!(cs, Some(ColorSpace::DeviceRGB)) {
                 info!("image has data/pixel ratio of 3, but colorspace is {:?}", cs);
@@ -297,6 +326,93 @@ pub fn load_image(image: &ImageXObject, resources: &Resources, resolve: &impl Re
     }
 }
 
+/// The samples of `image` with all filters applied.
+///
+/// JPEG is decoded here to honor `/ColorTransform`, JPEG 2000 is not supported.
+fn image_bytes(image: &ImageXObject, resolve: &impl Resolve) -> Result<Arc<[u8]>, PdfError> {
+    match image.inner.filters.last() {
+        Some(StreamFilter::DCTDecode(params)) => {
+            let (data, _) = image.raw_image_data(resolve)?;
+            decode_dct(&data, params.color_transform).map(Arc::from)
+        }
+        Some(StreamFilter::JPXDecode) => Err(PdfError::Other {
+            msg: "JPXDecode (JPEG 2000) images are not supported".into(),
+        }),
+        _ => image.image_data(resolve),
+    }
+}
+
+/// Decode a JPEG into 8 bit samples in the color space of the image.
+///
+/// `color_transform` is `/ColorTransform`: 0 keeps 3 and 4 component data as it is stored,
+/// 1 converts from YCbCr (YCCK). The default is what the file says (JFIF or Adobe marker),
+/// which is the transform for 3 and 4 component images without one.
+fn decode_dct(data: &[u8], color_transform: Option<i32>) -> Result<Vec<u8>, PdfError> {
+    use zune_core::colorspace::ColorSpace as JpegColorSpace;
+    use zune_core::options::DecoderOptions;
+    use zune_jpeg::JpegDecoder;
+
+    let error = |e| PdfError::Other { msg: format!("can't decode JPEG: {:?}", e) };
+    let mut decoder = JpegDecoder::new(data);
+    decoder.decode_headers().map_err(error)?;
+    let input = decoder.get_input_colorspace().ok_or_else(|| PdfError::Other { msg: "JPEG without color space".into() })?;
+
+    let (out, to_rgb) = match (input, color_transform) {
+        (JpegColorSpace::YCbCr, Some(0)) => (JpegColorSpace::YCbCr, false),
+        (JpegColorSpace::YCCK, Some(0)) => (JpegColorSpace::YCCK, false),
+        // the file says RGB, the PDF says YCbCr
+        (JpegColorSpace::RGB, Some(1)) => (JpegColorSpace::RGB, true),
+        (JpegColorSpace::YCbCr | JpegColorSpace::RGB, _) => (JpegColorSpace::RGB, false),
+        (JpegColorSpace::YCCK | JpegColorSpace::CMYK, _) => (JpegColorSpace::CMYK, false),
+        (other, _) => (other, false),
+    };
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(out);
+    let mut decoder = JpegDecoder::new_with_options(data, options);
+    let mut pixels = decoder.decode().map_err(error)?;
+    if to_rgb {
+        for c in pixels.chunks_exact_mut(3) {
+            let (y, cb, cr) = (c[0] as f32, c[1] as f32 - 128.0, c[2] as f32 - 128.0);
+            let rgb = [y + 1.402 * cr, y - 0.344136 * cb - 0.714136 * cr, y + 1.772 * cb];
+            for (c, v) in c.iter_mut().zip(rgb) {
+                *c = v.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    Ok(pixels)
+}
+
+/// Unpack samples of `bits` (1, 2 or 4) bits into one byte each.
+///
+/// The first sample is in the high bits, every row of `width` samples starts at a new byte.
+fn unpack_samples(data: &[u8], bits: u8, width: usize, count: usize) -> Vec<u8> {
+    let row_bytes = (width * bits as usize).div_ceil(8);
+    let mask = (1u8 << bits) - 1;
+    data.chunks(row_bytes.max(1))
+        .flat_map(|row| {
+            row.iter()
+                .flat_map(move |&b| (1..=8 / bits).map(move |i| (b >> (8 - i * bits)) & mask))
+                .take(width)
+        })
+        .take(count)
+        .collect()
+}
+
+/// Apply `/Decode` to 8 bit samples with `n` components per pixel.
+///
+/// Samples with fewer bits are scaled to 8 bit first, see `unpack_samples`.
+fn apply_decode(data: &mut [u8], decode: &[f32], n: usize) {
+    let ranges: Vec<(f32, f32)> = decode.chunks_exact(2).map(|r| (r[0], r[1])).take(n).collect();
+    if ranges.len() != n || ranges.iter().all(|&r| r == (0.0, 1.0)) {
+        return;
+    }
+    for pixel in data.chunks_exact_mut(n) {
+        for (v, &(min, max)) in pixel.iter_mut().zip(&ranges) {
+            let x = min + *v as f32 / 255.0 * (max - min);
+            *v = (x.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+}
+
 fn rgb2rgba(c: &[u8], a: u8, mode: BlendMode) -> ColorU {
     match mode {
         BlendMode::Overlay => {
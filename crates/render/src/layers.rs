@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use pdf::error::Result;
+use pdf::object::{PlainRef, Resolve};
+use pdf::primitive::{Dictionary, Primitive};
+
+/// An optional content group ("layer").
+#[derive(Clone, Debug)]
+pub struct LayerInfo {
+    pub name: String,
+    pub ocg: PlainRef,
+    pub visible: bool,
+}
+
+/// Select a layer by name or by the reference of its group.
+#[derive(Copy, Clone, Debug)]
+pub enum LayerId<'a> {
+    Name(&'a str),
+    Ref(PlainRef),
+}
+impl<'a> From<&'a str> for LayerId<'a> {
+    fn from(name: &'a str) -> Self {
+        LayerId::Name(name)
+    }
+}
+impl From<PlainRef> for LayerId<'static> {
+    fn from(r: PlainRef) -> Self {
+        LayerId::Ref(r)
+    }
+}
+
+/// The optional content groups of a document and their visibility.
+#[derive(Clone, Debug, Default)]
+pub struct Layers {
+    layers: Vec<LayerInfo>,
+}
+
+impl Layers {
+    /// Read `/OCProperties` from the document catalog.
+    ///
+    /// The initial visibility is taken from the default configuration (`/D`).
+    pub fn load(catalog: PlainRef, resolve: &impl Resolve) -> Result<Layers> {
+        let catalog = resolve.resolve(catalog)?.into_dictionary()?;
+        let props = match catalog.get("OCProperties") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => return Ok(Layers::default()),
+        };
+        let config = match props.get("D") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => Dictionary::new(),
+        };
+        let base_on = match config.get("BaseState") {
+            Some(p) => p.as_name()? != "OFF",
+            None => true,
+        };
+        let on = refs(config.get("ON"));
+        let off = refs(config.get("OFF"));
+
+        let mut layers = vec![];
+        for ocg in refs(props.get("OCGs")) {
+            let dict = resolve.resolve(ocg)?.into_dictionary()?;
+            let name = match dict.get("Name") {
+                Some(p) => p.clone().resolve(resolve)?.as_string()?.to_string_lossy(),
+                None => String::new(),
+            };
+            let visible = if on.contains(&ocg) {
+                true
+            } else if off.contains(&ocg) {
+                false
+            } else {
+                base_on
+            };
+            layers.push(LayerInfo { name, ocg, visible });
+        }
+        Ok(Layers { layers })
+    }
+
+    pub fn layers(&self) -> &[LayerInfo] {
+        &self.layers
+    }
+
+    /// Show or hide all layers matching `id`. Returns false if there is none.
+    pub fn set_visible<'a>(&mut self, id: impl Into<LayerId<'a>>, visible: bool) -> bool {
+        let id = id.into();
+        let mut found = false;
+        for layer in self.layers.iter_mut() {
+            let matches = match id {
+                LayerId::Name(name) => layer.name == name,
+                LayerId::Ref(r) => layer.ocg == r,
+            };
+            if matches {
+                layer.visible = visible;
+                found = true;
+            }
+        }
+        found
+    }
+
+    /// The groups of all hidden layers.
+    pub fn hidden(&self) -> HashSet<PlainRef> {
+        self.layers
+            .iter()
+            .filter(|layer| !layer.visible)
+            .map(|layer| layer.ocg)
+            .collect()
+    }
+}
+
+/// A single reference or an array of references.
+pub(crate) fn refs(p: Option<&Primitive>) -> Vec<PlainRef> {
+    match p {
+        Some(&Primitive::Reference(r)) => vec![r],
+        Some(Primitive::Array(arr)) => arr
+            .iter()
+            .filter_map(|p| match *p {
+                Primitive::Reference(r) => Some(r),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
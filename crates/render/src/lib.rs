@@ -26,11 +26,35 @@ pub mod tracer;
 mod image;
 mod scene;
 mod font;
+mod type3;
+mod layers;
+mod annotations;
+mod forms;
+mod function;
+mod softmask;
+mod attachments;
+mod options;
+mod colorspace;
+mod structure;
+mod words;
+mod warnings;
+mod pattern;
+mod content;
 
 pub use cache::{Cache};
 pub use fontentry::{FontEntry};
-pub use backend::{DrawMode, Backend, BlendMode, FillMode};
+pub use backend::{DrawMode, Backend, BlendMode, FillMode, MaskId};
 pub use scene::SceneBackend;
+pub use type3::Type3Font;
+pub use layers::{LayerId, LayerInfo, Layers};
+pub use forms::FormDefaults;
+pub use attachments::{load_attachments, Attachment};
+pub use options::{ClearMode, RenderOptions};
+pub use function::eval_function;
+pub use colorspace::lab_to_rgb;
+pub use structure::{MarkedContentRef, StructNode};
+pub use words::{page_lines, page_words, TextLine, Word};
+pub use warnings::page_warnings;
 pub use crate::image::{load_image, ImageData};
 use custom_debug_derive::Debug;
 
@@ -41,7 +65,11 @@ use pathfinder_geometry::{
     rect::RectF, transform2d::Transform2F,
 };
 use renderstate::RenderState;
+use std::ops::Range;
 use std::sync::Arc;
+use pdf::any::AnySync;
+use pdf::file::{Cache as PdfCache, File as PdfFile, Log};
+use pathfinder_renderer::scene::Scene;
 use itertools::Itertools;
 const SCALE: f32 = 25.4 / 72.;
 
@@ -74,47 +102,116 @@ impl From<RectF> for BBox {
 }
 
 
+fn pdf_rect(r: Rect) -> RectF {
+    let Rect { left, right, top, bottom } = r;
+    RectF::from_points(
+        Vector2F::new(left.min(right), bottom.min(top)),
+        Vector2F::new(left.max(right), bottom.max(top)),
+    ) * SCALE
+}
+
+/// The MediaBox of the page (in mm).
+pub fn page_media_box(page: &Page) -> RectF {
+    pdf_rect(page.media_box().expect("no media box"))
+}
+
+/// The CropBox of the page (in mm), clipped to the MediaBox.
+///
+/// Falls back to the MediaBox if the page (or its parents) define no CropBox.
+pub fn page_crop_box(page: &Page) -> RectF {
+    let media_box = page_media_box(page);
+    match page.crop_box() {
+        Ok(crop_box) => pdf_rect(crop_box).intersection(media_box).unwrap_or(media_box),
+        Err(_) => media_box,
+    }
+}
+
+/// The visible area of the page, which is the CropBox.
 pub fn page_bounds(page: &Page) -> RectF {
-    let Rect { left, right, top, bottom } = page.media_box().expect("no media box");
-    RectF::from_points(Vector2F::new(left, bottom), Vector2F::new(right, top)) * SCALE
+    page_crop_box(page)
 }
-pub fn render_page(backend: &mut impl Backend, resolve: &impl Resolve, page: &Page, transform: Transform2F) -> Result<Transform2F, PdfError> {
-    let bounds = page_bounds(page);
+/// `/Rotate` of the page, and the rotated page at the origin.
+fn page_rotation(page: &Page, bounds: RectF) -> (Transform2F, RectF) {
     let rotate = Transform2F::from_rotation(page.rotate as f32 * std::f32::consts::PI / 180.);
     let br = rotate * RectF::new(Vector2F::zero(), bounds.size());
     let translate = Transform2F::from_translation(Vector2F::new(
         -br.min_x().min(br.max_x()),
         -br.min_y().min(br.max_y()),
     ));
-    let view_box = transform * translate * br;
+    (translate * rotate, translate * br)
+}
+
+/// Maps PDF user space to page space: mm, y down, rotated by `/Rotate`, the page starting at the origin.
+///
+/// `render_page` draws with `transform * page_transform(page)`, map annotation and link rects
+/// with it to hit-test them against the rendered page.
+pub fn page_transform(page: &Page) -> Transform2F {
+    let bounds = page_bounds(page);
+    let (rotate, _) = page_rotation(page, bounds);
+    rotate * Transform2F::row_major(SCALE, 0.0, -bounds.min_x(), 0.0, -SCALE, bounds.max_y())
+}
+
+pub fn render_page(backend: &mut impl Backend, resolve: &impl Resolve, page: &Page, transform: Transform2F) -> Result<Transform2F, PdfError> {
+    let bounds = page_bounds(page);
+    let cropped = bounds != page_media_box(page);
+    let (_, rotated) = page_rotation(page, bounds);
+    let view_box = transform * rotated;
     backend.set_view_box(view_box);
     
-    let root_transformation = transform
-        * translate
-        * rotate
-        * Transform2F::row_major(SCALE, 0.0, -bounds.min_x(), 0.0, -SCALE, bounds.max_y());
+    let root_transformation = transform * page_transform(page);
     
     let resources = t!(page.resources());
 
-    let contents = try_opt!(page.contents.as_ref());
-    let ops = contents.operations(resolve)?;
+    if let Some(contents) = page.contents.as_ref() {
+        let ops = content::content_ops(contents, resolve, backend.strict())?;
+        let mut renderstate = RenderState::new(backend, resolve, &resources, root_transformation);
+        if cropped {
+            // hide everything outside the CropBox
+            renderstate.set_clip_rect(view_box);
+        }
+        renderstate.draw_ops(&ops)?;
+    }
+
+    // annotations don't see what the content stream left on the stack
     let mut renderstate = RenderState::new(backend, resolve, &resources, root_transformation);
-    for (i, op) in ops.iter().enumerate() {
-        debug!("op {}: {:?}", i, op);
-        renderstate.draw_op(op, i)?;
+    if cropped {
+        renderstate.set_clip_rect(view_box);
     }
+    annotations::draw_annotations(&mut renderstate, page, resolve);
 
     Ok(root_transformation)
 }
+/// Render the pages in `pages` of `file`, one `Scene` each.
+///
+/// `transform` is called with each page and gives the transform passed to `render_page`,
+/// e.g. `|_| Transform2F::from_scale(2.0)`. Pages past the end of the file are skipped.
+pub fn render_file_pages<'a, B, OC, SC, L>(
+    file: &'a PdfFile<B, OC, SC, L>,
+    cache: &'a mut Cache,
+    pages: Range<u32>,
+    mut transform: impl FnMut(&Page) -> Transform2F + 'a,
+) -> impl Iterator<Item = Result<Scene, PdfError>> + 'a
+    where
+        B: pdf::backend::Backend,
+        OC: PdfCache<Result<AnySync, Arc<PdfError>>>,
+        SC: PdfCache<Result<Arc<[u8]>, Arc<PdfError>>>,
+        L: Log
+{
+    let end = pages.end.min(file.num_pages());
+    (pages.start..end).map(move |nr| {
+        let page = file.get_page(nr)?;
+        let resolver = file.resolver();
+        let mut backend = SceneBackend::new(&mut *cache);
+        render_page(&mut backend, &resolver, &page, transform(&page))?;
+        Ok(backend.finish())
+    })
+}
 pub fn render_pattern(backend: &mut impl Backend, pattern: &Pattern, resolve: &impl Resolve) -> Result<(), PdfError> {
     match pattern {
         Pattern::Stream(dict, ops) => {
             let resources = resolve.get(dict.resources)?;
             let mut renderstate = RenderState::new(backend, resolve, &*resources, Transform2F::default());
-            for (i, op) in ops.iter().enumerate() {
-                debug!("op {}: {:?}", i, op);
-                renderstate.draw_op(op, i)?;
-            }
+            renderstate.draw_ops(ops)?;
         }
         Pattern::Dict(_) => {}
     }
@@ -140,6 +237,9 @@ pub struct TextSpan {
 
     // width in textspace units (before applying transform)
     pub width: f32,
+    // range of positions covered by the pen and the glyphs in textspace units, `rect` spans it.
+    // (0, width) unless the text moves left.
+    pub extent: (f32, f32),
     // Bounding box of the rendered outline
     pub bbox: Option<RectF>,
     pub font_size: f32,
@@ -154,8 +254,19 @@ pub struct TextSpan {
     pub transform: Transform2F,
     pub mode: TextMode,
     pub op_nr: usize,
+    // `/MCID` of the innermost marked content sequence, links the span to a `StructNode`
+    pub mcid: Option<i32>,
 }
 impl TextSpan {
+    /// The glyphs were placed right to left, `chars` are in stream order with decreasing `pos`.
+    ///
+    /// Extraction can use `rparts` to visit them in visual (left to right) order.
+    pub fn is_rtl(&self) -> bool {
+        match (self.chars.first(), self.chars.last()) {
+            (Some(first), Some(last)) => last.pos < first.pos,
+            _ => false,
+        }
+    }
     pub fn parts(&self) -> impl Iterator<Item=Part> + '_ {
         self.chars.iter().cloned()
             .chain(std::iter::once(TextChar { offset: self.text.len(), pos: self.width, width: 0.0 }))
@@ -0,0 +1,107 @@
+use pathfinder_color::ColorF;
+use pathfinder_geometry::{transform2d::Transform2F, vector::Vector2F};
+use pathfinder_renderer::options::{BuildOptions, RenderTransform};
+
+/// Quality settings for turning a `Scene` into pixels.
+///
+/// The web viewer and the offscreen rasterizer both build their `BuildOptions` from
+/// this, so the same settings give the same output everywhere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// LCD subpixel anti-aliasing. Off by default, it only looks right on screens
+    /// with a horizontal RGB layout and breaks on transparent backgrounds.
+    pub subpixel_aa: bool,
+    /// Grow every path by this amount (in pixels), can be used to embolden thin text.
+    pub dilation: Vector2F,
+    /// How far (in output units, pixels for a view transform) flattened curves may deviate
+    /// from the real ones. Larger values give fewer segments: faster, less memory, less smooth.
+    ///
+    /// 0 keeps the curves, they are then flattened by the rasterizer at full quality.
+    /// Used when building the scene, see `SceneBackend::set_render_options`.
+    pub flatness: f32,
+    /// Pixels per unit of the scene in the output, e.g. `dpi / 25.4` for a page in mm.
+    ///
+    /// Transparency groups are drawn into render targets of this resolution, so it has to
+    /// match the transform the scene is rendered with. 1 for scenes built in pixels.
+    /// Used when building the scene, see `SceneBackend::set_render_options`.
+    pub resolution: f32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            subpixel_aa: false,
+            dilation: Vector2F::zero(),
+            flatness: 0.0,
+            resolution: 1.0,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Dilate just enough that 0.5pt rules stay (at least) a pixel wide when
+    /// rendering at `scale` pixels per point, e.g. `dpi / 72`.
+    ///
+    /// Only low resolutions are affected, from 144 dpi on there is no dilation.
+    /// Everything grows by the same amount, so text gets slightly bolder too.
+    pub fn preserve_hairlines(scale: f32) -> RenderOptions {
+        // 0.5 * scale + 2 * dilation = 1
+        let dilation = (0.5 - 0.25 * scale).clamp(0.0, 0.5);
+        RenderOptions {
+            dilation: Vector2F::splat(dilation),
+            ..RenderOptions::default()
+        }
+    }
+
+    pub fn build_options(&self, transform: Transform2F) -> BuildOptions {
+        BuildOptions {
+            transform: RenderTransform::Transform2D(transform),
+            dilation: self.dilation,
+            subpixel_aa_enabled: self.subpixel_aa,
+        }
+    }
+}
+
+/// What an image starts as before the scene is drawn on it.
+///
+/// `Option<ColorF>` converts for the rasterizers: `None` is `OpaqueWhite`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClearMode {
+    OpaqueWhite,
+    /// Alpha 0 where nothing is drawn, e.g. for PNGs to put on other content.
+    Transparent,
+    Color(ColorF),
+}
+
+impl ClearMode {
+    pub fn color(self) -> ColorF {
+        match self {
+            ClearMode::OpaqueWhite => ColorF::white(),
+            ClearMode::Transparent => ColorF::transparent_black(),
+            ClearMode::Color(color) => color,
+        }
+    }
+
+    /// For `RendererOptions::background_color`, so pathfinder clears to the same color.
+    pub fn background_color(self) -> Option<ColorF> {
+        Some(self.color())
+    }
+}
+
+impl Default for ClearMode {
+    fn default() -> Self {
+        ClearMode::OpaqueWhite
+    }
+}
+
+impl From<Option<ColorF>> for ClearMode {
+    fn from(background: Option<ColorF>) -> Self {
+        background.map_or(ClearMode::OpaqueWhite, ClearMode::Color)
+    }
+}
+
+impl From<ColorF> for ClearMode {
+    fn from(color: ColorF) -> Self {
+        ClearMode::Color(color)
+    }
+}
@@ -0,0 +1,155 @@
+//! Shading patterns as gradients, see `RenderState::fill_pattern`.
+
+use pathfinder_color::ColorF;
+use pathfinder_content::gradient::{Gradient, GradientGeometry};
+use pathfinder_geometry::{line_segment::LineSegment2F, rect::RectF, transform2d::Transform2F, vector::Vector2F};
+use pdf::content::Color;
+use pdf::error::{PdfError, Result};
+use pdf::function::Function;
+use pdf::object::{ColorSpace, Object, Resolve, Resources};
+use pdf::primitive::{Dictionary, Primitive};
+
+use crate::backend::BlendMode;
+use crate::function::eval_function;
+use crate::renderstate::convert_color2;
+use crate::Fill;
+
+// color stops sampled from the function of a shading
+const GRADIENT_STOPS: usize = 32;
+// corners of the polygon a radial gradient is cut off with
+const CIRCLE_POINTS: usize = 64;
+
+/// `key` of `dict`, with references resolved.
+pub(crate) fn entry(dict: &Dictionary, key: &str, resolve: &impl Resolve) -> Result<Option<Primitive>> {
+    match dict.get(key) {
+        Some(&Primitive::Reference(r)) => Ok(Some(resolve.resolve(r)?)),
+        Some(p) => Ok(Some(p.clone())),
+        None => Ok(None),
+    }
+}
+
+fn numbers(p: &Primitive) -> Result<Vec<f32>> {
+    p.as_array()?.iter().map(|n| n.as_number()).collect()
+}
+
+/// The `/Matrix` of a pattern, identity if it has none.
+pub(crate) fn pattern_matrix(dict: &Dictionary, resolve: &impl Resolve) -> Result<Transform2F> {
+    match entry(dict, "Matrix", resolve)? {
+        Some(p) => match numbers(&p)?[..] {
+            [a, b, c, d, e, f] => Ok(Transform2F::row_major(a, c, e, b, d, f)),
+            ref m => Err(PdfError::Other { msg: format!("pattern matrix {:?} does not have 6 numbers", m) }),
+        },
+        None => Ok(Transform2F::default()),
+    }
+}
+
+/// The gradient of an axial (type 2) or radial (type 3) shading dictionary, in shading space,
+/// and its `/Extend`.
+///
+/// The colors are sampled from the function of the shading. The gradient itself is always
+/// extended beyond its ends, see `extend_polygon` to cut it off.
+pub(crate) fn shading_gradient(
+    shading: &Dictionary,
+    resources: &Resources,
+    resolve: &impl Resolve,
+    mode: BlendMode,
+) -> Result<(Gradient, [bool; 2])> {
+    let missing = |field: &str| PdfError::MissingEntry { typ: "Shading", field: field.into() };
+    let shading_type = entry(shading, "ShadingType", resolve)?.ok_or_else(|| missing("ShadingType"))?.as_integer()?;
+    let coords = numbers(&entry(shading, "Coords", resolve)?.ok_or_else(|| missing("Coords"))?)?;
+    let mut gradient = match (shading_type, &coords[..]) {
+        (2, &[x0, y0, x1, y1]) => Gradient::linear_from_points(Vector2F::new(x0, y0), Vector2F::new(x1, y1)),
+        (3, &[x0, y0, r0, x1, y1, r1]) => Gradient::radial(
+            LineSegment2F::new(Vector2F::new(x0, y0), Vector2F::new(x1, y1)),
+            Vector2F::new(r0, r1).0,
+        ),
+        (2 | 3, coords) => {
+            return Err(PdfError::Other { msg: format!("wrong /Coords {:?} for shading type {}", coords, shading_type) });
+        }
+        _ => return Err(PdfError::Other { msg: format!("shading type {} is not supported", shading_type) }),
+    };
+
+    let cs = ColorSpace::from_primitive(entry(shading, "ColorSpace", resolve)?.ok_or_else(|| missing("ColorSpace"))?, resolve)?;
+    // one function with all components, or one function per component
+    let functions = match entry(shading, "Function", resolve)?.ok_or_else(|| missing("Function"))? {
+        Primitive::Array(parts) => {
+            parts.into_iter().map(|f| Function::from_primitive(f, resolve)).collect::<Result<Vec<_>>>()?
+        }
+        f => vec![Function::from_primitive(f, resolve)?],
+    };
+    let (t0, t1) = match entry(shading, "Domain", resolve)? {
+        Some(p) => match numbers(&p)?[..] {
+            [t0, t1] => (t0, t1),
+            _ => (0.0, 1.0),
+        },
+        None => (0.0, 1.0),
+    };
+
+    for i in 0..GRADIENT_STOPS {
+        let offset = i as f32 / (GRADIENT_STOPS - 1) as f32;
+        let t = t0 + offset * (t1 - t0);
+        let mut components = vec![];
+        for f in &functions {
+            let mut out = vec![0.0; f.output_dim()];
+            eval_function(f, &[t], &mut out)?;
+            components.extend(out);
+        }
+        let color = Color::Other(components.into_iter().map(Primitive::Number).collect());
+        let (r, g, b) = match convert_color2(&mut &cs, &color, resources, mode)? {
+            Fill::Solid(r, g, b) => (r, g, b),
+            Fill::Pattern(_) => return Err(PdfError::Other { msg: "shading in a pattern color space".into() }),
+        };
+        gradient.add_color_stop(ColorF::new(r, g, b, 1.0).to_u8(), offset);
+    }
+
+    let extend = match entry(shading, "Extend", resolve)? {
+        Some(p) => match p.as_array()?[..] {
+            [ref start, ref end] => [start.as_bool()?, end.as_bool()?],
+            _ => [false, false],
+        },
+        None => [false, false],
+    };
+    Ok((gradient, extend))
+}
+
+/// The area `gradient` (in device space) paints when the ends that are not in `extend`
+/// are cut off, as a convex polygon that covers `area` where the gradient reaches.
+///
+/// `None` if nothing is cut off. Radial gradients are only cut off at the end circle.
+pub(crate) fn extend_polygon(gradient: &Gradient, extend: [bool; 2], area: RectF) -> Option<Vec<Vector2F>> {
+    let mut polygon = match gradient.geometry {
+        GradientGeometry::Linear(line) => {
+            if extend == [true, true] || line.vector().square_length() == 0.0 {
+                return None;
+            }
+            // far enough to get past `area` from anywhere on the line
+            let reach = area.size().length() + (line.from() - area.center()).length() + line.vector().length();
+            let dir = line.vector().normalize();
+            let side = Vector2F::new(-dir.y(), dir.x()) * reach;
+            let start = if extend[0] { line.from() - dir * reach } else { line.from() };
+            let end = if extend[1] { line.to() + dir * reach } else { line.to() };
+            vec![start + side, end + side, end - side, start - side]
+        }
+        GradientGeometry::Radial { line, radii, transform } => {
+            if extend[1] {
+                return None;
+            }
+            (0..CIRCLE_POINTS)
+                .map(|i| {
+                    let angle = i as f32 * std::f32::consts::TAU / CIRCLE_POINTS as f32;
+                    transform * (line.to() + Vector2F::new(angle.cos(), angle.sin()) * radii.y())
+                })
+                .collect()
+        }
+    };
+    // `Outline::clip_against_polygon` keeps the inside of clockwise polygons (y down)
+    let winding: f32 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x() * b.y() - b.x() * a.y())
+        .sum();
+    if winding < 0.0 {
+        polygon.reverse();
+    }
+    Some(polygon)
+}
@@ -5,7 +5,17 @@ use pdf::primitive::{ Primitive, Dictionary };
 use pdf::content::{ Op, Matrix, Point, Rect, Color, Rgb, Cmyk, Winding, FormXObject };
 use pdf::error::{ PdfError, Result };
 use pdf::content::TextDrawAdjusted;
-use crate::backend::{ Backend, BlendMode, FillMode };
+use pdf::content::TextMode;
+use crate::backend::{ Backend, BlendMode, FillMode, MaskId };
+use crate::layers::refs;
+use crate::function::eval_function;
+use crate::softmask::backdrop_luminosity;
+use crate::colorspace::{ icc_base, LabSpace };
+use crate::forms::FormDefaults;
+use crate::pattern::{ entry, extend_polygon, pattern_matrix, shading_gradient };
+use crate::content::parse_ops_lenient;
+use std::sync::Arc;
+use pdf::function::Function;
 
 use pathfinder_geometry::{ vector::Vector2F, rect::RectF, transform2d::Transform2F };
 use pathfinder_content::{
@@ -60,6 +70,16 @@ impl Cvt for Rgb {
         (red, green, blue)
     }
 }
+impl Cvt for Rectangle {
+    type Out = RectF;
+    fn cvt(self) -> Self::Out {
+        let Rectangle { left, bottom, right, top } = self;
+        RectF::from_points(
+            Vector2F::new(left.min(right), bottom.min(top)),
+            Vector2F::new(left.max(right), bottom.max(top))
+        )
+    }
+}
 impl Cvt for Cmyk {
     type Out = (f32, f32, f32, f32);
     fn cvt(self) -> Self::Out {
@@ -77,8 +97,15 @@ pub struct RenderState<'a, R: Resolve, B: Backend> {
     resolve: &'a R,
     resources: &'a Resources,
     backend: &'a mut B,
+    // one entry per open marked content sequence: true if it is hidden, and its `/MCID`
+    marked_content: Vec<(bool, Option<i32>)>,
+    // the default space of the content stream, patterns are placed in it
+    base_transform: Transform2F,
 }
 
+// tiling patterns with more cells in the filled area are not drawn
+const MAX_TILES: i64 = 10_000;
+
 impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
     pub fn new(
         backend: &'a mut B,
@@ -110,6 +137,7 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
             overprint_fill: false,
             overprint_stroke: false,
             overprint_mode: 0,
+            soft_mask: None,
         };
         let text_state = TextState::new();
         let stack = vec![];
@@ -125,26 +153,85 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
             resources,
             resolve,
             backend,
+            marked_content: vec![],
+            base_transform: root_transformation,
         }
     }
+    /// Restrict all drawing to `rect` (in device space).
+    pub fn set_clip_rect(&mut self, rect: RectF) {
+        let path = Outline::from_rect(rect);
+        let id = self.backend.create_clip_path(path.clone(), FillRule::Winding, None);
+        self.graphics_state.clip_path_id = Some(id);
+        self.graphics_state.clip_path = Some(ClipPath::new(path));
+        self.graphics_state.clip_path_rect = Some(rect);
+    }
     fn draw(&mut self, mode: &DrawMode, fill_rule: FillRule) {
         self.flush();
-        self.backend.draw(
-            &self.current_outline,
-            mode,
-            fill_rule,
-            self.graphics_state.transform,
-            self.graphics_state.clip_path_id
-        );
+        if self.hidden() {
+            self.current_outline.clear();
+            return;
+        }
+        // the backend only gets what a pattern draws, and the stroke
+        let stroke_only;
+        let mode = match mode {
+            DrawMode::Fill { fill } | DrawMode::FillStroke { fill, .. } if matches!(fill.color, Fill::Pattern(_)) => {
+                if let Fill::Pattern(pattern) = fill.color {
+                    if let Err(e) = self.fill_pattern(pattern, fill_rule, fill.alpha, fill.mode) {
+                        warn!("can't fill with pattern {:?}: {:?}", pattern, e);
+                    }
+                }
+                match mode {
+                    DrawMode::FillStroke { stroke, stroke_mode, .. } => {
+                        stroke_only = DrawMode::Stroke { stroke: stroke.clone(), stroke_mode: stroke_mode.clone() };
+                        &stroke_only
+                    }
+                    _ => {
+                        self.current_outline.clear();
+                        return;
+                    }
+                }
+            }
+            mode => mode,
+        };
+        let outline = &self.current_outline;
+        let transform = self.graphics_state.transform;
+        self.graphics_state.draw_masked(self.backend, mode, |backend, mode, clip| {
+            backend.draw(outline, mode, fill_rule, transform, clip);
+        });
         self.current_outline.clear();
     }
+    /// Draw all `ops`.
+    ///
+    /// An operator that fails is logged and skipped, so the rest still shows up,
+    /// unless the backend is `strict`.
+    pub fn draw_ops(&mut self, ops: &'a [Op]) -> Result<()> {
+        for (i, op) in ops.iter().enumerate() {
+            debug!("op {}: {:?}", i, op);
+            if let Err(e) = self.draw_op(op, i) {
+                if self.backend.strict() {
+                    return Err(e);
+                }
+                warn!("skipping op {} ({:?}): {:?}", i, op, e);
+            }
+        }
+        Ok(())
+    }
     #[allow(unused_variables)]
     pub fn draw_op(&mut self, op: &'a Op, op_nr: usize) -> Result<()> {
         self.backend.inspect_op(op);
         self.backend.bug_op(op_nr);
         match *op {
-            Op::BeginMarkedContent { .. } => {}
-            Op::EndMarkedContent { .. } => {}
+            Op::BeginMarkedContent { ref tag, ref properties } => {
+                let hidden = match properties {
+                    Some(p) if tag.as_str() == "OC" => !self.oc_visible(p),
+                    _ => false,
+                };
+                let mcid = properties.as_ref().and_then(|p| self.mcid_of(p));
+                self.marked_content.push((hidden, mcid));
+            }
+            Op::EndMarkedContent { .. } => {
+                self.marked_content.pop();
+            }
             Op::MarkedContentPoint { .. } => {}
             Op::Close => {
                 self.current_contour.close();
@@ -215,48 +302,7 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                 self.flush();
                 let mut path = self.current_outline.clone();
                 path.transform(&self.graphics_state.transform);
-                let clip_path_rect = to_rect(&path);
-
-                let (path, r, parent) = match
-                    (
-                        self.graphics_state.clip_path_rect,
-                        clip_path_rect,
-                        self.graphics_state.clip_path_id,
-                    )
-                {
-                    (Some(r1), Some(r2), Some(p)) => {
-                        let r = r1.intersection(r2).unwrap_or_default();
-                        (Outline::from_rect(r), Some(r), None)
-                    }
-                    (Some(r), None, Some(p)) => {
-                        path.clip_against_polygon(
-                            &[r.origin(), r.upper_right(), r.lower_right(), r.lower_left()]
-                        );
-                        (path, None, None)
-                    }
-                    (None, Some(r), Some(p)) => {
-                        let mut path = self.graphics_state.clip_path
-                            .as_ref()
-                            .unwrap()
-                            .outline()
-                            .clone();
-                        path.clip_against_polygon(
-                            &[r.origin(), r.upper_right(), r.lower_right(), r.lower_left()]
-                        );
-                        (path, None, None)
-                    }
-                    (None, Some(r), None) => { (path, Some(r), None) }
-                    (None, None, Some(p)) => (path, None, Some(p)),
-                    (None, None, None) => (path, None, None),
-                    _ => unreachable!(),
-                };
-
-                let id = self.backend.create_clip_path(path.clone(), winding.cvt(), parent);
-                self.graphics_state.clip_path_id = Some(id);
-                let mut clip = ClipPath::new(path);
-                clip.set_fill_rule(winding.cvt());
-                self.graphics_state.clip_path = Some(clip);
-                self.graphics_state.clip_path_rect = r;
+                self.clip(path, winding.cvt());
             }
 
             Op::Save => {
@@ -319,6 +365,9 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                 if let Some(m) = gs.overprint_mode {
                     self.graphics_state.overprint_mode = m;
                 }
+                if let Some(ref smask) = gs.smask {
+                    self.graphics_state.soft_mask = self.load_soft_mask(smask)?;
+                }
             }
             Op::StrokeColor { ref color } => {
                 let mode = self.blend_mode_stroke();
@@ -425,7 +474,7 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                             TextDrawAdjusted::Spacing(offset) => {
                                 // because why not PDF…
                                 let advance = text_state.advance(-0.001 * offset);
-                                span.width += advance;
+                                span.advance(advance);
                             }
                         }
                     }
@@ -438,16 +487,14 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                 let xobject = self.resolve.get(xobject_ref)?;
                 let mode = self.blend_mode_fill();
                 match *xobject {
+                    XObject::Image(_) if self.hidden() => {}
                     XObject::Image(ref im) => {
-                        self.backend.draw_image(
-                            xobject_ref,
-                            im,
-                            self.resources,
-                            self.graphics_state.transform,
-                            mode,
-                            self.graphics_state.clip_path_id,
-                            self.resolve
-                        );
+                        let (resources, resolve) = (self.resources, self.resolve);
+                        let transform = self.graphics_state.transform;
+                        let fill = DrawMode::Fill { fill: FillMode { color: Fill::black(), alpha: 1.0, mode } };
+                        self.graphics_state.draw_masked(self.backend, &fill, |backend, _, clip| {
+                            backend.draw_image(xobject_ref, im, resources, transform, mode, clip, resolve);
+                        });
                     }
                     XObject::Form(ref content) => {
                         self.draw_form(content)?;
@@ -459,16 +506,15 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                     }
                 }
             }
+            Op::InlineImage { .. } if self.hidden() => {}
             Op::InlineImage { ref image } => {
                 let mode = self.blend_mode_fill();
-                self.backend.draw_inline_image(
-                    image,
-                    &self.resources,
-                    self.graphics_state.transform,
-                    mode,
-                    self.graphics_state.clip_path_id,
-                    self.resolve
-                );
+                let (resources, resolve) = (self.resources, self.resolve);
+                let transform = self.graphics_state.transform;
+                let fill = DrawMode::Fill { fill: FillMode { color: Fill::black(), alpha: 1.0, mode } };
+                self.graphics_state.draw_masked(self.backend, &fill, |backend, _, clip| {
+                    backend.draw_inline_image(image, resources, transform, mode, clip, resolve);
+                });
             }
         }
 
@@ -489,15 +535,23 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
     ) {
         let mut span = Span::default();
         let tm = self.text_state.text_matrix;
-        let origin = tm.translation();
+
+        if self.hidden() {
+            // still advance the text position
+            let mode = std::mem::replace(&mut self.text_state.mode, TextMode::Invisible);
+            inner(&mut self.backend, &mut self.text_state, &mut self.graphics_state, &mut span);
+            self.text_state.mode = mode;
+            return;
+        }
 
         inner(&mut self.backend, &mut self.text_state, &mut self.graphics_state, &mut span);
 
         let transform =
             self.graphics_state.transform * tm * Transform2F::from_scale(Vector2F::new(1.0, -1.0));
-        let p1 = origin;
+        // the pen can move left of the origin (negative TJ adjustments, right to left text)
+        let p1 = (tm * Transform2F::from_translation(Vector2F::new(span.min_pos, 0.0))).translation();
         let p2 = (
-            tm * Transform2F::from_translation(Vector2F::new(span.width, self.text_state.font_size))
+            tm * Transform2F::from_translation(Vector2F::new(span.max_pos, self.text_state.font_size))
         ).translation();
         let clip = self.graphics_state.clip_path_id;
 
@@ -506,6 +560,7 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
             TextSpan {
                 rect: self.graphics_state.transform * RectF::from_points(p1.min(p2), p1.max(p2)),
                 width: span.width,
+                extent: (span.min_pos, span.max_pos),
                 bbox: span.bbox.rect(),
                 text: span.text,
                 chars: span.chars,
@@ -516,6 +571,7 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
                 mode: self.text_state.mode,
                 transform,
                 op_nr,
+                mcid: self.mcid(),
             },
             clip
         );
@@ -542,6 +598,51 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
             None => Err(PdfError::Other { msg: format!("color space {:?} not present", name) }),
         }
     }
+    /// Intersect the clip path with `path` (in device space).
+    fn clip(&mut self, mut path: Outline, fill_rule: FillRule) {
+        let clip_path_rect = to_rect(&path);
+
+        let (path, r, parent) = match
+            (
+                self.graphics_state.clip_path_rect,
+                clip_path_rect,
+                self.graphics_state.clip_path_id,
+            )
+        {
+            (Some(r1), Some(r2), Some(p)) => {
+                let r = r1.intersection(r2).unwrap_or_default();
+                (Outline::from_rect(r), Some(r), None)
+            }
+            (Some(r), None, Some(p)) => {
+                path.clip_against_polygon(
+                    &[r.origin(), r.upper_right(), r.lower_right(), r.lower_left()]
+                );
+                (path, None, None)
+            }
+            (None, Some(r), Some(p)) => {
+                let mut path = self.graphics_state.clip_path
+                    .as_ref()
+                    .unwrap()
+                    .outline()
+                    .clone();
+                path.clip_against_polygon(
+                    &[r.origin(), r.upper_right(), r.lower_right(), r.lower_left()]
+                );
+                (path, None, None)
+            }
+            (None, Some(r), None) => { (path, Some(r), None) }
+            (None, None, Some(p)) => (path, None, Some(p)),
+            (None, None, None) => (path, None, None),
+            _ => unreachable!(),
+        };
+
+        let id = self.backend.create_clip_path(path.clone(), fill_rule, parent);
+        self.graphics_state.clip_path_id = Some(id);
+        let mut clip = ClipPath::new(path);
+        clip.set_fill_rule(fill_rule);
+        self.graphics_state.clip_path = Some(clip);
+        self.graphics_state.clip_path_rect = r;
+    }
     fn flush(&mut self) {
         if !self.current_contour.is_empty() {
             self.current_outline.push_contour(self.current_contour.clone());
@@ -549,19 +650,54 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
         }
     }
     fn draw_form(&mut self, form: &FormXObject) -> Result<()> {
+        // isolated and knockout groups are drawn offscreen, and composited as a whole
+        let group = form.dict().group.as_ref()
+            .filter(|g| g.get("S").and_then(|s| s.as_name().ok()) == Some("Transparency"));
+        let flag = |key: &str| group.and_then(|g| g.get(key)).and_then(|p| p.as_bool().ok()).unwrap_or(false);
+        let (isolated, knockout) = (flag("I"), flag("K"));
+        if group.is_some() && (isolated || knockout) && !self.hidden() && self.backend.begin_group(knockout) {
+            let graphics_state = GraphicsState {
+                stroke_alpha: 1.0,
+                fill_alpha: 1.0,
+                stroke_color_alpha: 1.0,
+                fill_color_alpha: 1.0,
+                fill_paint: None,
+                stroke_paint: None,
+                clip_path: self.graphics_state.clip_path.clone(),
+                soft_mask: None,
+                ..self.graphics_state
+            };
+            let result = self.draw_form_with(form, graphics_state);
+            self.backend.end_group();
+
+            let mode = self.blend_mode_fill();
+            let fill = DrawMode::Fill { fill: FillMode { color: Fill::black(), alpha: self.graphics_state.fill_color_alpha, mode } };
+            self.graphics_state.draw_masked(self.backend, &fill, |backend, fill, clip| {
+                if let DrawMode::Fill { fill } = fill {
+                    backend.draw_group(fill.alpha, fill.mode, clip);
+                }
+            });
+            return result;
+        }
+
         let graphics_state = GraphicsState {
             stroke_alpha: self.graphics_state.stroke_color_alpha,
             fill_alpha: self.graphics_state.fill_color_alpha,
             clip_path_id: self.graphics_state.clip_path_id,
             clip_path: self.graphics_state.clip_path.clone(),
+            soft_mask: self.graphics_state.soft_mask.clone(),
             ..self.graphics_state
         };
+        self.draw_form_with(form, graphics_state)
+    }
+    fn draw_form_with(&mut self, form: &FormXObject, graphics_state: GraphicsState<'a, B>) -> Result<()> {
         let resources = match form.dict().resources {
             Some(ref r) => &*r,
             None => self.resources,
         };
 
         let mut inner = RenderState {
+            base_transform: graphics_state.transform,
             graphics_state: graphics_state,
             text_state: self.text_state.clone(),
             resources,
@@ -570,15 +706,343 @@ impl<'a, R: Resolve, B: Backend> RenderState<'a, R, B> {
             current_contour: Contour::new(),
             backend: self.backend,
             resolve: self.resolve,
+            marked_content: vec![(self.hidden(), self.mcid())],
         };
 
-        let ops = t!(form.operations(self.resolve));
-        for (i, op) in ops.iter().enumerate() {
-            debug!(" form op {}: {:?}", i, op);
-            inner.draw_op(op, i)?;
+        let data = t!(form.stream.data(self.resolve));
+        let ops = t!(parse_ops_lenient(&data, self.resolve, inner.backend.strict()));
+        inner.draw_ops(&ops)
+    }
+    /// Draw the appearance stream of an annotation into `rect` (in default user space).
+    ///
+    /// The /BBox transformed by the /Matrix of the form is fitted to `rect`.
+    pub fn draw_appearance(&mut self, form: &FormXObject, rect: Rectangle) -> Result<()> {
+        let dict = form.dict();
+        let matrix = dict.matrix.map(|m| m.cvt()).unwrap_or_default();
+        let bbox = matrix * dict.bbox.cvt();
+        let rect = rect.cvt();
+        if bbox.width() == 0.0 || bbox.height() == 0.0 {
+            return Ok(());
         }
+        let fit = Transform2F::from_translation(rect.origin())
+            * Transform2F::from_scale(rect.size() / bbox.size())
+            * Transform2F::from_translation(-bbox.origin());
 
-        Ok(())
+        let transform = self.graphics_state.transform;
+        self.graphics_state.transform = transform * fit * matrix;
+        let result = self.draw_form(form);
+        self.graphics_state.transform = transform;
+        result
+    }
+    /// Draw `ops` into `rect` (in default user space) and clip them to it, with `resources`
+    /// or those of the page. For content generated without a form around it.
+    pub fn draw_in_rect(&mut self, ops: &[Op], resources: Option<&Resources>, rect: Rectangle) -> Result<()> {
+        let rect = rect.cvt();
+        let transform = self.graphics_state.transform * Transform2F::from_translation(rect.origin());
+        let graphics_state = GraphicsState {
+            transform,
+            clip_path: self.graphics_state.clip_path.clone(),
+            soft_mask: self.graphics_state.soft_mask.clone(),
+            ..self.graphics_state
+        };
+
+        let mut inner = RenderState {
+            graphics_state: graphics_state,
+            text_state: TextState::new(),
+            resources: resources.unwrap_or(self.resources),
+            base_transform: transform,
+            stack: vec![],
+            current_outline: Outline::new(),
+            current_contour: Contour::new(),
+            backend: self.backend,
+            resolve: self.resolve,
+            marked_content: vec![(self.hidden(), self.mcid())],
+        };
+        inner.set_clip_rect(transform * RectF::new(Vector2F::zero(), rect.size()));
+        inner.draw_ops(ops)
+    }
+    /// Fill the current path with `pattern`.
+    ///
+    /// Tiling patterns draw their cell once and repeat it over the path (see `Backend::begin_tile`),
+    /// or draw it once per step, clipped to the path, if the backend can't. Uncolored ones
+    /// (paint type 2) are drawn with the colors of their cell. Shading patterns are drawn with
+    /// `Backend::draw_gradient`, cut off at the ends that are not extended.
+    fn fill_pattern(&mut self, pattern: Ref<Pattern>, fill_rule: FillRule, alpha: f32, mode: BlendMode) -> Result<()> {
+        let dict = match self.resolve.resolve(pattern.get_inner())? {
+            Primitive::Dictionary(dict) => dict,
+            Primitive::Stream(stream) => stream.info,
+            p => return Err(PdfError::UnexpectedPrimitive { expected: "Dictionary or Stream", found: p.get_debug_name() }),
+        };
+        let pattern_transform = self.base_transform * pattern_matrix(&dict, self.resolve)?;
+        match dict.get("PatternType").and_then(|t| t.as_integer().ok()) {
+            Some(1) => {
+                let pattern = self.resolve.get(pattern)?;
+                match *pattern {
+                    Pattern::Stream(ref tiling, ref ops) => {
+                        self.fill_tiling(tiling, ops, pattern_transform, fill_rule, alpha, mode)
+                    }
+                    Pattern::Dict(_) => Err(PdfError::Other { msg: "tiling pattern without content".into() }),
+                }
+            }
+            Some(2) => {
+                let shading = match entry(&dict, "Shading", self.resolve)? {
+                    Some(Primitive::Dictionary(shading)) => shading,
+                    Some(Primitive::Stream(_)) => {
+                        return Err(PdfError::Other { msg: "mesh shadings are not supported".into() });
+                    }
+                    _ => return Err(PdfError::MissingEntry { typ: "Pattern", field: "Shading".into() }),
+                };
+                let (mut gradient, extend) = shading_gradient(&shading, self.resources, self.resolve, mode)?;
+                gradient.apply_transform(pattern_transform);
+
+                let mut outline = self.current_outline.clone();
+                outline.transform(&self.graphics_state.transform);
+                if let Some(polygon) = extend_polygon(&gradient, extend, outline.bounds()) {
+                    outline.clip_against_polygon(&polygon);
+                }
+                let outline = &outline;
+                let transform = Transform2F::default();
+                let fill = DrawMode::Fill { fill: FillMode { color: Fill::black(), alpha, mode } };
+                self.graphics_state.draw_masked(self.backend, &fill, |backend, fill, clip| {
+                    if let DrawMode::Fill { fill } = fill {
+                        backend.draw_gradient(outline, &gradient, fill_rule, transform, fill.alpha, fill.mode, clip);
+                    }
+                });
+                Ok(())
+            }
+            t => Err(PdfError::Other { msg: format!("pattern type {:?} is not supported", t) }),
+        }
+    }
+    fn fill_tiling(
+        &mut self,
+        tiling: &PatternDict,
+        ops: &[Op],
+        pattern_transform: Transform2F,
+        fill_rule: FillRule,
+        alpha: f32,
+        mode: BlendMode,
+    ) -> Result<()> {
+        let bbox = tiling.bbox.cvt();
+        let step = Vector2F::new(tiling.x_step.abs(), tiling.y_step.abs());
+        if step.x() == 0.0 || step.y() == 0.0 || bbox.width() == 0.0 || bbox.height() == 0.0 {
+            return Ok(());
+        }
+        let resources = self.resolve.get(tiling.resources)?;
+        // the copies of the cell that reach into the period starting at the cell
+        let copies = (bbox.size() / step).ceil();
+        if copies.x() * copies.y() <= MAX_TILES as f32 {
+            if let Some(cell_transform) = self.backend.begin_tile(RectF::new(bbox.origin(), step), pattern_transform) {
+                let saved = self.graphics_state.clone();
+                self.graphics_state.clip_path_id = None;
+                self.graphics_state.clip_path = None;
+                self.graphics_state.clip_path_rect = None;
+                self.graphics_state.soft_mask = None;
+                let mut result = Ok(());
+                'cells: for row in 1 - copies.y() as i64..=0 {
+                    for col in 1 - copies.x() as i64..=0 {
+                        let offset = Vector2F::new(col as f32 * step.x(), row as f32 * step.y());
+                        let transform = cell_transform * Transform2F::from_translation(offset);
+                        // the alpha applies to the repeated cell
+                        result = self.draw_tile(ops, &resources, transform, bbox, 1.0);
+                        if result.is_err() {
+                            break 'cells;
+                        }
+                    }
+                }
+                self.graphics_state = saved;
+                self.backend.end_tile();
+
+                let outline = &self.current_outline;
+                let transform = self.graphics_state.transform;
+                let fill = DrawMode::Fill { fill: FillMode { color: Fill::black(), alpha, mode } };
+                self.graphics_state.draw_masked(self.backend, &fill, |backend, fill, clip| {
+                    if let DrawMode::Fill { fill } = fill {
+                        backend.fill_tile(outline, fill_rule, transform, fill.alpha, fill.mode, clip);
+                    }
+                });
+                return result;
+            }
+        }
+
+        let mut path = self.current_outline.clone();
+        path.transform(&self.graphics_state.transform);
+
+        // the cells that overlap the path, in pattern space
+        let mut area = path.bounds();
+        if let Some(clip) = self.graphics_state.clip_path_rect {
+            area = area.intersection(clip).unwrap_or_default();
+        }
+        let area = pattern_transform.inverse() * area;
+        let cols = ((area.min_x() - bbox.max_x()) / step.x()).ceil() as i64..=((area.max_x() - bbox.min_x()) / step.x()).floor() as i64;
+        let rows = ((area.min_y() - bbox.max_y()) / step.y()).ceil() as i64..=((area.max_y() - bbox.min_y()) / step.y()).floor() as i64;
+        let tiles = (cols.end() - cols.start() + 1).max(0) * (rows.end() - rows.start() + 1).max(0);
+        if tiles > MAX_TILES {
+            return Err(PdfError::Other { msg: format!("pattern has {} tiles in the filled area", tiles) });
+        }
+
+        let saved = self.graphics_state.clone();
+        self.clip(path, fill_rule);
+        let mut result = Ok(());
+        'rows: for row in rows {
+            for col in cols.clone() {
+                let offset = Vector2F::new(col as f32 * step.x(), row as f32 * step.y());
+                let transform = pattern_transform * Transform2F::from_translation(offset);
+                result = self.draw_tile(ops, &resources, transform, bbox, alpha);
+                if result.is_err() {
+                    break 'rows;
+                }
+            }
+        }
+        self.graphics_state = saved;
+        result
+    }
+    /// Draw one cell of a tiling pattern with `transform`, clipped to `bbox`.
+    fn draw_tile(&mut self, ops: &[Op], resources: &Resources, transform: Transform2F, bbox: RectF, alpha: f32) -> Result<()> {
+        let graphics_state = GraphicsState {
+            transform,
+            fill_color: Fill::black(),
+            fill_color_alpha: alpha,
+            fill_paint: None,
+            fill_alpha: alpha,
+            stroke_color: Fill::black(),
+            stroke_color_alpha: alpha,
+            stroke_paint: None,
+            stroke_alpha: alpha,
+            fill_color_space: &ColorSpace::DeviceRGB,
+            stroke_color_space: &ColorSpace::DeviceRGB,
+            dash_pattern: None,
+            clip_path: self.graphics_state.clip_path.clone(),
+            soft_mask: self.graphics_state.soft_mask.clone(),
+            ..self.graphics_state
+        };
+        let mut inner = RenderState {
+            graphics_state,
+            text_state: TextState::new(),
+            resources,
+            stack: vec![],
+            current_outline: Outline::new(),
+            current_contour: Contour::new(),
+            backend: self.backend,
+            resolve: self.resolve,
+            marked_content: vec![(self.hidden(), self.mcid())],
+            base_transform: transform,
+        };
+        let mut cell = Outline::from_rect(bbox);
+        cell.transform(&transform);
+        inner.clip(cell, FillRule::Winding);
+        inner.draw_ops(ops)
+    }
+    /// See `Backend::flatten_forms`.
+    pub fn flatten_forms(&self) -> Option<Arc<FormDefaults>> {
+        self.backend.flatten_forms()
+    }
+    /// Draw the mask group of the `/SMask` entry `p`, `/None` removes the mask.
+    fn load_soft_mask(&mut self, p: &Primitive) -> Result<Option<MaskId>> {
+        let dict = match *p {
+            Primitive::Name(ref name) if name.as_str() == "None" => return Ok(None),
+            Primitive::Dictionary(ref dict) => dict.clone(),
+            Primitive::Reference(r) => self.resolve.resolve(r)?.into_dictionary()?,
+            ref p => {
+                return Err(PdfError::UnexpectedPrimitive {
+                    expected: "Dictionary or /None",
+                    found: p.get_debug_name(),
+                });
+            }
+        };
+        let luminosity = dict.get("S").and_then(|s| s.as_name().ok()) != Some("Alpha");
+        let group = match dict.get("G") {
+            Some(&Primitive::Reference(r)) => self.resolve.get(Ref::<XObject>::new(r))?,
+            _ => return Err(PdfError::MissingEntry { typ: "SoftMask", field: "G".into() }),
+        };
+        let form = match *group {
+            XObject::Form(ref form) => form,
+            _ => return Err(PdfError::Other { msg: "soft mask group is not a form".into() }),
+        };
+        let backdrop = match (luminosity, dict.get("BC")) {
+            (true, Some(Primitive::Array(bc))) => {
+                let bc: Vec<f32> = bc.iter().filter_map(|c| c.as_number().ok()).collect();
+                backdrop_luminosity(&bc)
+            }
+            _ => 0.0,
+        };
+
+        if !self.backend.begin_mask(luminosity, backdrop) {
+            warn!("the backend can't draw soft masks, drawing without it");
+            return Ok(None);
+        }
+        // the group starts from the initial graphics state, with the transform of the `gs`
+        let matrix = form.dict().matrix.map(|m| m.cvt()).unwrap_or_default();
+        let graphics_state = GraphicsState {
+            transform: self.graphics_state.transform * matrix,
+            fill_color: Fill::black(),
+            fill_color_alpha: 1.0,
+            fill_paint: None,
+            fill_alpha: 1.0,
+            stroke_color: Fill::black(),
+            stroke_color_alpha: 1.0,
+            stroke_paint: None,
+            stroke_alpha: 1.0,
+            clip_path_id: None,
+            clip_path: None,
+            clip_path_rect: None,
+            fill_color_space: &ColorSpace::DeviceRGB,
+            stroke_color_space: &ColorSpace::DeviceRGB,
+            dash_pattern: None,
+            soft_mask: None,
+            ..self.graphics_state
+        };
+        let result = self.draw_form_with(form, graphics_state);
+        let mask = self.backend.end_mask();
+        result?;
+        Ok(mask)
+    }
+    /// inside hidden optional content
+    fn hidden(&self) -> bool {
+        self.marked_content.iter().any(|&(hidden, _)| hidden)
+    }
+    /// `/MCID` of the innermost marked content sequence that has one
+    fn mcid(&self) -> Option<i32> {
+        self.marked_content.iter().rev().find_map(|&(_, mcid)| mcid)
+    }
+    /// `/MCID` in the properties `p` of a `BDC`
+    fn mcid_of(&self, p: &Primitive) -> Option<i32> {
+        let dict = match *p {
+            Primitive::Name(ref name) => match self.resources.properties.get(name.as_str())? {
+                MaybeRef::Indirect(ref rc) => &**rc,
+                MaybeRef::Direct(ref dict) => &**dict,
+            },
+            Primitive::Dictionary(ref dict) => dict,
+            _ => return None,
+        };
+        dict.get("MCID")?.as_integer().ok()
+    }
+    /// Visibility of the optional content group or membership dictionary `p`.
+    fn oc_visible(&self, p: &Primitive) -> bool {
+        let (ocg, dict) = match *p {
+            Primitive::Name(ref name) => match self.resources.properties.get(name.as_str()) {
+                Some(MaybeRef::Indirect(ref rc)) => (Some(rc.get_ref().get_inner()), &**rc),
+                Some(MaybeRef::Direct(ref dict)) => (None, &**dict),
+                None => return true,
+            },
+            Primitive::Dictionary(ref dict) => (None, dict),
+            _ => return true,
+        };
+        match dict.get("Type").and_then(|t| t.as_name().ok()) {
+            Some("OCMD") => {
+                let ocgs = refs(dict.get("OCGs"));
+                if ocgs.is_empty() {
+                    return true;
+                }
+                let mut visible = ocgs.iter().map(|&r| self.backend.layer_visible(r));
+                match dict.get("P").and_then(|p| p.as_name().ok()).unwrap_or("AnyOn") {
+                    "AllOn" => visible.all(|v| v),
+                    "AnyOff" => visible.any(|v| !v),
+                    "AllOff" => visible.all(|v| !v),
+                    _ => visible.any(|v| v),
+                }
+            }
+            _ => ocg.map(|r| self.backend.layer_visible(r)).unwrap_or(true),
+        }
     }
     #[allow(dead_code)]
     fn get_properties<'b>(&'b self, p: &'b Primitive) -> Result<&'b Dictionary> {
@@ -617,7 +1081,7 @@ fn convert_color<'a>(
     }
 }
 #[allow(unused_variables)]
-fn convert_color2<'a>(
+pub(crate) fn convert_color2<'a>(
     cs: &mut &'a ColorSpace,
     color: &Color,
     resources: &Resources,
@@ -640,17 +1104,16 @@ fn convert_color2<'a>(
         Color::Other(ref args) => {
             let cs = match **cs {
                 ColorSpace::Icc(ref icc) => {
-                    match icc.info.alternate {
-                        Some(ref alt) => alt,
-                        None => {
-                            match args.len() {
-                                3 => &ColorSpace::DeviceRGB,
-                                4 => &ColorSpace::DeviceCMYK,
-                                _ => {
-                                    return Err(PdfError::Other {
-                                        msg: format!("ICC profile without alternate color space"),
-                                    });
-                                }
+                    let components = icc.info.components as usize;
+                    match icc_base(components, icc.info.alternate.as_deref()) {
+                        Some(base) if components == args.len() => base,
+                        // the profile doesn't fit the color, try the alternate
+                        _ => match icc.info.alternate {
+                            Some(ref alt) => alt,
+                            None => {
+                                return Err(PdfError::Other {
+                                    msg: format!("ICC profile with {} components for {:?}", components, args),
+                                });
                             }
                         }
                     }
@@ -701,72 +1164,12 @@ fn convert_color2<'a>(
                     let k = args[3].as_number()?;
                     Ok(cmyk2rgb((c, m, y, k), mode))
                 }
-                ColorSpace::DeviceN { ref names, ref alt, ref tint, ref attr } => {
-                    assert_eq!(args.len(), tint.input_dim());
-                    let mut input = vec![0.; args.len()];
-                    for (i, a) in input.iter_mut().zip(args.iter()) {
-                        *i = a.as_number()?;
-                    }
-                    let mut out = vec![0.0; tint.output_dim()];
-                    tint.apply(&input, &mut out)?;
-
-                    let alt = match **alt {
-                        ColorSpace::Icc(ref icc) => icc.info.alternate.as_ref().map(|b| &**b),
-                        ref a => Some(a),
-                    };
-                    match alt {
-                        Some(ColorSpace::DeviceGray) => Ok(Fill::Solid(out[0], out[0], out[0])),
-                        Some(ColorSpace::DeviceRGB) => { Ok(Fill::Solid(out[0], out[1], out[2])) }
-                        Some(ColorSpace::DeviceCMYK) => {
-                            Ok(cmyk2rgb((out[0], out[1], out[2], out[3]), mode))
-                        }
-                        _ => unimplemented!("DeviceN colorspace"),
-                    }
+                ColorSpace::DeviceN { ref alt, ref tint, .. } => {
+                    tint_transform(tint, args, alt, resources, mode)
                 }
                 ColorSpace::Separation(ref name, ref alt, ref f) => {
                     debug!("Separation(name={}, alt={:?}, f={:?}", name, alt, f);
-                    if args.len() != 1 {
-                        return Err(PdfError::Other {
-                            msg: format!("expected 1 color arguments, got {:?}", args),
-                        });
-                    }
-                    let x = args[0].as_number()?;
-                    let cs = match **alt {
-                        ColorSpace::Icc(ref info) =>
-                            &**info.alternate
-                                .as_ref()
-                                .ok_or(PdfError::Other {
-                                    msg: format!(
-                                        "no alternate color space in ICC profile {:?}",
-                                        info
-                                    ),
-                                })?,
-                        _ => alt,
-                    };
-                    match cs {
-                        &ColorSpace::DeviceCMYK => {
-                            let mut cmyk = [0.0; 4];
-                            f.apply(&[x], &mut cmyk)?;
-                            let [c, m, y, k] = cmyk;
-                            //debug!("c={c}, m={m}, y={y}, k={k}");
-                            Ok(cmyk2rgb((c, m, y, k), mode))
-                        }
-                        &ColorSpace::DeviceRGB => {
-                            let mut rgb = [0.0, 0.0, 0.0];
-                            f.apply(&[x], &mut rgb)?;
-                            let [r, g, b] = rgb;
-                            //debug!("r={r}, g={g}, b={b}");
-                            Ok(Fill::Solid(r, g, b))
-                        }
-                        &ColorSpace::DeviceGray => {
-                            let mut gray = [0.0];
-                            f.apply(&[x], &mut gray)?;
-                            let [gray] = gray;
-                            //debug!("gray={gray}");
-                            Ok(Fill::Solid(gray, gray, gray))
-                        }
-                        c => unimplemented!("Separation(alt={:?})", c),
-                    }
+                    tint_transform(f, args, alt, resources, mode)
                 }
                 ColorSpace::Indexed(ref cs, hival, ref lut) => {
                     if args.len() != 1 {
@@ -797,13 +1200,50 @@ fn convert_color2<'a>(
                         unimplemented!("Pattern {} not found", name)
                     }
                 }
-                ColorSpace::Other(ref p) => unimplemented!("Other Color space {:?}", p),
+                ColorSpace::Other(ref p) => {
+                    let lab = match LabSpace::parse(cs) {
+                        Some(lab) => lab,
+                        None => unimplemented!("Other Color space {:?}", p),
+                    };
+                    if args.len() != 3 {
+                        return Err(PdfError::Other {
+                            msg: format!("expected 3 color arguments, got {:?}", args),
+                        });
+                    }
+                    let [r, g, b] = lab.rgb([args[0].as_number()?, args[1].as_number()?, args[2].as_number()?]);
+                    Ok(Fill::Solid(r, g, b))
+                }
                 ColorSpace::Named(ref p) => unimplemented!("nested Named {:?}", p),
             }
         }
     }
 }
 
+/// Map the tint values `args` through `f` into the alternate color space and convert from there.
+fn tint_transform(
+    f: &Function,
+    args: &[Primitive],
+    alt: &ColorSpace,
+    resources: &Resources,
+    mode: BlendMode
+) -> Result<Fill> {
+    if args.len() != f.input_dim() {
+        return Err(PdfError::Other {
+            msg: format!("expected {} color arguments, got {:?}", f.input_dim(), args),
+        });
+    }
+    let mut input = Vec::with_capacity(args.len());
+    for a in args {
+        input.push(a.as_number()?);
+    }
+    let mut out = vec![0.0; f.output_dim()];
+    eval_function(f, &input, &mut out)?;
+
+    let color = Color::Other(out.into_iter().map(Primitive::Number).collect());
+    let mut alt = alt;
+    convert_color2(&mut alt, &color, resources, mode)
+}
+
 fn gray2rgb(g: f32) -> Fill {
     Fill::Solid(g, g, g)
 }
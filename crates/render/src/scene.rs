@@ -2,19 +2,23 @@ use pathfinder_color::{ ColorF, ColorU };
 use pathfinder_content::{
     fill::FillRule,
     stroke::OutlineStrokeToFill,
-    outline::Outline,
-    pattern::Pattern,
+    outline::{ Contour, ContourIterFlags, Outline },
+    segment::Segment,
+    pattern::{ Image, Pattern, PatternSource },
+    render_target::RenderTargetId,
     dash::OutlineDash,
+    gradient::Gradient,
 };
 use pathfinder_renderer::{
-    scene::{ DrawPath, ClipPath, ClipPathId, Scene },
-    paint::{ PaintId, Paint },
+    scene::{ DrawPath, ClipPath, ClipPathId, RenderTarget, Scene },
+    paint::{ Paint, PaintContents },
 };
-use pathfinder_geometry::{ vector::Vector2F, rect::RectF, transform2d::Transform2F };
-use pdf::object::{ Ref, XObject, ImageXObject, Resolve, Resources, MaybeRef };
-use crate::backend;
+use pathfinder_geometry::{ vector::{ Vector2F, Vector2I }, rect::RectF, transform2d::Transform2F };
+use pdf::object::{ Ref, XObject, ImageXObject, Resolve, Resources, MaybeRef, PlainRef };
+use crate::backend::{ self, MaskId };
+use crate::softmask::luminosity;
 
-use super::{ FontEntry, TextSpan, DrawMode, Backend, Fill, Cache };
+use super::{ FontEntry, TextSpan, DrawMode, Backend, Fill, Cache, RenderOptions, FormDefaults };
 use pdf::font::Font as PdfFont;
 use pdf::error::PdfError;
 use std::sync::Arc;
@@ -22,25 +26,210 @@ use std::sync::Arc;
 pub struct SceneBackend<'a> {
     scene: Scene,
     cache: &'a mut Cache,
+    text: Option<Vec<TextSpan>>,
+    // union of everything drawn, without the page background
+    content_bounds: Option<RectF>,
+    // see `RenderOptions::flatness`
+    flatness: f32,
+    // see `RenderOptions::resolution`
+    resolution: f32,
+    // open transparency groups and whether they knock out
+    groups: Vec<(RenderTargetId, bool)>,
+    // the last group that was ended, see `draw_group`
+    last_group: Option<RenderTargetId>,
+    // the render targets of the soft masks, by `MaskId`
+    masks: Vec<RenderTargetId>,
+    // the soft mask being drawn, and whether it takes the luminosity
+    drawing_mask: Option<(RenderTargetId, bool)>,
+    // what was drawn under a soft mask so far, it is composited before anything else is drawn
+    masked: Option<(MaskId, RenderTargetId)>,
+    // between `begin_masked` and `end_masked`
+    in_masked: bool,
+    // the cell of the last tiling pattern: its target, size and the pattern transform
+    tile: Option<(RenderTargetId, Vector2I, Transform2F)>,
+    // between `begin_tile` and `end_tile`
+    in_tile: bool,
 }
 
+// curves are split at most this often, 2^10 lines per curve
+const MAX_FLATTEN_DEPTH: u32 = 10;
+// largest side (in pixels) of the cell of a tiling pattern
+const MAX_TILE_SIZE: i32 = 4096;
+
 impl<'a> SceneBackend<'a> {
     pub fn new(cache: &'a mut Cache) -> Self {
         let scene = Scene::new();
         SceneBackend {
             scene,
             cache,
+            text: None,
+            content_bounds: None,
+            flatness: 0.0,
+            resolution: 1.0,
+            groups: Vec::new(),
+            last_group: None,
+            masks: Vec::new(),
+            drawing_mask: None,
+            masked: None,
+            in_masked: false,
+            tile: None,
+            in_tile: false,
+        }
+    }
+    /// Build the scene with `options`. Only the flatness and the resolution are used,
+    /// the rest applies when the scene is rendered (see `RenderOptions::build_options`).
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.flatness = options.flatness;
+        self.resolution = options.resolution;
+    }
+    /// Also keep the text spans of the page (see `finish_with_text`).
+    pub fn collect_text(&mut self) {
+        self.text.get_or_insert_with(Vec::new);
+    }
+    /// Bounding box of the paths, glyphs and images drawn so far, within the view box.
+    ///
+    /// Clip paths are not taken into account, so it can be larger than what is visible.
+    /// `None` for a blank page.
+    pub fn content_bounds(&self) -> Option<RectF> {
+        self.content_bounds
+    }
+    fn add_content(&mut self, rect: RectF) {
+        if let Some(rect) = rect.intersection(self.scene.view_box()) {
+            self.content_bounds = Some(match self.content_bounds {
+                Some(bounds) => bounds.union_rect(rect),
+                None => rect,
+            });
         }
     }
-    pub fn finish(self) -> Scene {
+    pub fn finish(mut self) -> Scene {
+        self.flush_masked();
         self.scene
     }
-    fn paint(&mut self, fill: Fill, alpha: f32) -> PaintId {
-        let paint = match fill {
+    pub fn finish_with_text(mut self) -> (Scene, Vec<TextSpan>) {
+        self.flush_masked();
+        (self.scene, self.text.unwrap_or_default())
+    }
+    /// Replace the curves of `outline` by lines, see `RenderOptions::flatness`.
+    fn flatten(&self, outline: Outline) -> Outline {
+        if self.flatness <= 0.0 {
+            return outline;
+        }
+        let mut flat = Outline::new();
+        for contour in outline.contours() {
+            let mut lines = Contour::new();
+            for (i, segment) in contour.iter(ContourIterFlags::IGNORE_CLOSE_SEGMENT).enumerate() {
+                if i == 0 {
+                    lines.push_endpoint(segment.baseline.from());
+                }
+                flatten_segment(&mut lines, segment, self.flatness, 0);
+            }
+            if contour.is_closed() {
+                lines.close();
+            }
+            flat.push_contour(lines);
+        }
+        flat
+    }
+    /// Elements of a knockout group replace what is below them in the group.
+    fn blend_mode(&self, mode: backend::BlendMode) -> pathfinder_content::effects::BlendMode {
+        match self.groups.last() {
+            Some(&(_, true)) => pathfinder_content::effects::BlendMode::Copy,
+            _ => blend_mode(mode),
+        }
+    }
+    fn paint(&self, fill: Fill, alpha: f32) -> Paint {
+        match fill {
             Fill::Solid(r, g, b) => Paint::from_color(ColorF::new(r, g, b, alpha).to_u8()),
             Fill::Pattern(_) => { Paint::black() }
+        }
+    }
+    /// Add `outline` (in device space) filled with `paint`.
+    ///
+    /// In the group of a luminosity mask the mask is built up in the alpha channel: what the
+    /// path covers is taken away and its luminosity added, which is compositing the luminosity.
+    fn push_path(
+        &mut self,
+        outline: Outline,
+        paint: &Paint,
+        fill_rule: FillRule,
+        mode: backend::BlendMode,
+        clip: Option<ClipPathId>
+    ) {
+        self.flush_masked();
+        let outline = self.flatten(outline);
+        let push = |scene: &mut Scene, paint: &Paint, mode| {
+            let paint_id = scene.push_paint(paint);
+            let mut draw_path = DrawPath::new(outline.clone(), paint_id);
+            draw_path.set_clip_path(clip);
+            draw_path.set_fill_rule(fill_rule);
+            draw_path.set_blend_mode(mode);
+            scene.push_draw_path(draw_path);
         };
-        self.scene.push_paint(&paint)
+        match self.drawing_mask {
+            Some((_, true)) => {
+                push(&mut self.scene, paint, pathfinder_content::effects::BlendMode::DestOut);
+                push(&mut self.scene, &luminosity_paint(paint), pathfinder_content::effects::BlendMode::Lighter);
+            }
+            Some((_, false)) => push(&mut self.scene, paint, pathfinder_content::effects::BlendMode::SrcOver),
+            None => {
+                // the cell of a pattern is not where it ends up
+                if !self.in_tile {
+                    self.add_content(outline.bounds());
+                }
+                let mode = self.blend_mode(mode);
+                push(&mut self.scene, paint, mode);
+            }
+        }
+    }
+    /// Composite what was drawn under a soft mask, unless more of it is being drawn.
+    fn flush_masked(&mut self) {
+        if self.in_masked {
+            return;
+        }
+        if let Some((mask, content)) = self.masked.take() {
+            // keep the content where the mask is
+            let mask = self.masks[mask.0];
+            self.draw_target(mask, 1.0, pathfinder_content::effects::BlendMode::DestIn, None);
+            self.scene.pop_render_target();
+            let mode = self.blend_mode(backend::BlendMode::Overlay);
+            self.draw_target(content, 1.0, mode, None);
+        }
+    }
+    /// The size in pixels of a render target covering the view box.
+    fn target_size(&self) -> Vector2I {
+        (self.scene.view_box().size() * self.resolution).ceil().to_i32()
+    }
+    /// Start drawing into a new render target covering the view box, `None` if it would be empty.
+    fn push_target(&mut self, name: &str) -> Option<RenderTargetId> {
+        let size = self.target_size();
+        if size.x() <= 0 || size.y() <= 0 {
+            return None;
+        }
+        Some(self.scene.push_render_target(RenderTarget::new(size, name.into())))
+    }
+    /// Cover the view box with the pixels of render target `id`.
+    ///
+    /// The content of a target is drawn with the transform of the scene, so a pixel of the
+    /// target is a pixel of the output and the pattern has to undo that transform.
+    fn draw_target(
+        &mut self,
+        id: RenderTargetId,
+        alpha: f32,
+        mode: pathfinder_content::effects::BlendMode,
+        clip: Option<ClipPathId>
+    ) {
+        let view_box = self.scene.view_box();
+        let mut pattern = Pattern::from_render_target(id, self.target_size());
+        pattern.apply_transform(
+            Transform2F::from_translation(view_box.origin()) * Transform2F::from_scale(1.0 / self.resolution)
+        );
+        let mut paint = Paint::from_pattern(pattern);
+        paint.set_base_color(ColorF::new(1.0, 1.0, 1.0, alpha).to_u8());
+        let paint_id = self.scene.push_paint(&paint);
+        let mut draw_path = DrawPath::new(Outline::from_rect(view_box), paint_id);
+        draw_path.set_clip_path(clip);
+        draw_path.set_blend_mode(mode);
+        self.scene.push_draw_path(draw_path);
     }
 }
 
@@ -77,11 +266,7 @@ impl<'a> Backend for SceneBackend<'a> {
                 let paint = self.paint(fill.color, fill.alpha);
                 let mut transformed_outline = outline.clone();
                 transformed_outline.transform(&transform);
-                let mut draw_path = DrawPath::new(transformed_outline, paint);
-                draw_path.set_clip_path(clip);
-                draw_path.set_fill_rule(fill_rule);
-                draw_path.set_blend_mode(blend_mode(fill.mode));
-                self.scene.push_draw_path(draw_path);
+                self.push_path(transformed_outline, &paint, fill_rule, fill.mode, clip);
             }
             _ => {}
         }
@@ -104,16 +289,27 @@ impl<'a> Backend for SceneBackend<'a> {
                 };
                 let mut transformed_contour = contour;
                 transformed_contour.transform(&transform);
-                let mut draw_path = DrawPath::new(transformed_contour, paint);
-                draw_path.set_clip_path(clip);
-                draw_path.set_fill_rule(fill_rule);
-
-                draw_path.set_blend_mode(blend_mode(stroke.mode));
-                self.scene.push_draw_path(draw_path);
+                self.push_path(transformed_contour, &paint, fill_rule, stroke.mode, clip);
             }
             _ => {}
         }
     }
+    fn draw_gradient(
+        &mut self,
+        outline: &Outline,
+        gradient: &Gradient,
+        fill_rule: FillRule,
+        transform: Transform2F,
+        alpha: f32,
+        mode: backend::BlendMode,
+        clip: Option<ClipPathId>
+    ) {
+        let mut paint = Paint::from_gradient(gradient.clone());
+        paint.set_base_color(ColorF::new(1.0, 1.0, 1.0, alpha).to_u8());
+        let mut transformed_outline = outline.clone();
+        transformed_outline.transform(&transform);
+        self.push_path(transformed_outline, &paint, fill_rule, mode, clip);
+    }
     fn draw_image(
         &mut self,
         xobject_ref: Ref<XObject>,
@@ -138,12 +334,7 @@ impl<'a> Backend for SceneBackend<'a> {
             let mut pattern = Pattern::from_image(image.clone());
             pattern.apply_transform(im_tr);
             let paint = Paint::from_pattern(pattern);
-            let paint_id = self.scene.push_paint(&paint);
-            let mut draw_path = DrawPath::new(outline, paint_id);
-            draw_path.set_clip_path(clip);
-            draw_path.set_blend_mode(blend_mode(mode));
-
-            self.scene.push_draw_path(draw_path);
+            self.push_path(outline, &paint, FillRule::Winding, mode, clip);
         }
     }
     fn draw_inline_image(
@@ -163,7 +354,207 @@ impl<'a> Backend for SceneBackend<'a> {
     ) -> Result<Option<Arc<FontEntry>>, PdfError> {
         self.cache.get_font(font_ref, resolve)
     }
-    fn add_text(&mut self, span: TextSpan, clip: Option<Self::ClipPathId>) {}
+    fn add_text(&mut self, span: TextSpan, clip: Option<Self::ClipPathId>) {
+        // text in a mask group or pattern cell is not on the page
+        if self.drawing_mask.is_some() || self.in_tile {
+            return;
+        }
+        if let Some(ref mut text) = self.text {
+            text.push(span);
+        }
+    }
+    /// The group is drawn into a render target covering the view box, at the resolution of
+    /// the `RenderOptions`. Not supported with `Cache::set_offscreen(false)`, nor in the
+    /// group of a soft mask: the luminosity of a render target can't be taken.
+    fn begin_group(&mut self, knockout: bool) -> bool {
+        if !self.cache.offscreen() || self.drawing_mask.is_some() || self.in_tile {
+            return false;
+        }
+        self.flush_masked();
+        match self.push_target("transparency group") {
+            Some(id) => {
+                self.groups.push((id, knockout));
+                true
+            }
+            None => false,
+        }
+    }
+    fn end_group(&mut self) {
+        self.flush_masked();
+        if let Some((id, _)) = self.groups.pop() {
+            self.scene.pop_render_target();
+            self.last_group = Some(id);
+        }
+    }
+    fn draw_group(&mut self, alpha: f32, mode: backend::BlendMode, clip: Option<ClipPathId>) {
+        let id = match self.last_group {
+            Some(id) => id,
+            None => return,
+        };
+        self.flush_masked();
+        let mode = self.blend_mode(mode);
+        self.draw_target(id, alpha, mode, clip);
+    }
+    /// The mask is drawn into a render target like a group, its value ends up in the alpha
+    /// channel. Content under it is collected in another target, that is cut down to the
+    /// mask and composited once something is drawn without the mask.
+    ///
+    /// Content under the mask is blended with the other content under it, not with what is
+    /// below. Masks in the group of a mask are not supported.
+    fn begin_mask(&mut self, luminosity: bool, backdrop: f32) -> bool {
+        if !self.cache.offscreen() || self.drawing_mask.is_some() || self.in_tile {
+            return false;
+        }
+        self.flush_masked();
+        let id = match self.push_target("soft mask") {
+            Some(id) => id,
+            None => return false,
+        };
+        self.drawing_mask = Some((id, luminosity));
+        if backdrop > 0.0 {
+            let paint = self.scene.push_paint(&Paint::from_color(ColorF::new(0.0, 0.0, 0.0, backdrop).to_u8()));
+            self.scene.push_draw_path(DrawPath::new(Outline::from_rect(self.scene.view_box()), paint));
+        }
+        true
+    }
+    fn end_mask(&mut self) -> Option<MaskId> {
+        let (id, _) = self.drawing_mask.take()?;
+        self.scene.pop_render_target();
+        self.masks.push(id);
+        Some(MaskId(self.masks.len() - 1))
+    }
+    fn begin_masked(&mut self, mask: MaskId) {
+        if self.drawing_mask.is_some() || mask.0 >= self.masks.len() {
+            return;
+        }
+        if self.masked.map(|(current, _)| current) != Some(mask) {
+            self.flush_masked();
+            match self.push_target("soft mask content") {
+                Some(id) => self.masked = Some((mask, id)),
+                None => return,
+            }
+        }
+        self.in_masked = true;
+    }
+    fn end_masked(&mut self) {
+        self.in_masked = false;
+    }
+    /// The cell is drawn into a render target with about as many pixels as it covers in the
+    /// output, and repeated by the pattern of the paint. Not in the group of a mask (it
+    /// would have no luminosity) nor in another cell.
+    fn begin_tile(&mut self, cell: RectF, transform: Transform2F) -> Option<Transform2F> {
+        if !self.cache.offscreen() || self.drawing_mask.is_some() || self.in_tile {
+            return None;
+        }
+        // pixels per unit of pattern space, along its axes
+        let axis = |v: Vector2F| (transform * v - transform * Vector2F::zero()).length() * self.resolution;
+        let pixels = cell.size() * Vector2F::new(axis(Vector2F::new(1.0, 0.0)), axis(Vector2F::new(0.0, 1.0)));
+        if !(pixels.x() > 0.0 && pixels.y() > 0.0) {
+            return None;
+        }
+        let size = pixels.round().to_i32().max(Vector2I::splat(1)).min(Vector2I::splat(MAX_TILE_SIZE));
+        self.flush_masked();
+        let id = self.scene.push_render_target(RenderTarget::new(size, "tiling pattern".into()));
+        // the cell fills the target exactly, so the copies line up
+        let to_target = Transform2F::from_scale(size.to_f32() / cell.size())
+            * Transform2F::from_translation(-cell.origin());
+        let pattern_transform = transform * to_target.inverse();
+        self.tile = Some((id, size, pattern_transform));
+        self.in_tile = true;
+        // the content of a target is drawn with the transform of the scene
+        let view_box = self.scene.view_box();
+        Some(
+            Transform2F::from_translation(view_box.origin())
+                * Transform2F::from_scale(1.0 / self.resolution)
+                * to_target
+        )
+    }
+    fn end_tile(&mut self) {
+        if self.in_tile {
+            self.scene.pop_render_target();
+            self.in_tile = false;
+        }
+    }
+    fn fill_tile(
+        &mut self,
+        outline: &Outline,
+        fill_rule: FillRule,
+        transform: Transform2F,
+        alpha: f32,
+        mode: backend::BlendMode,
+        clip: Option<ClipPathId>
+    ) {
+        let (id, size, pattern_transform) = match self.tile {
+            Some(tile) if !self.in_tile => tile,
+            _ => return,
+        };
+        let mut pattern = Pattern::from_render_target(id, size);
+        pattern.apply_transform(pattern_transform);
+        pattern.set_repeat_x(true);
+        pattern.set_repeat_y(true);
+        let mut paint = Paint::from_pattern(pattern);
+        paint.set_base_color(ColorF::new(1.0, 1.0, 1.0, alpha).to_u8());
+        let mut transformed_outline = outline.clone();
+        transformed_outline.transform(&transform);
+        self.push_path(transformed_outline, &paint, fill_rule, mode, clip);
+    }
+    fn layer_visible(&self, ocg: PlainRef) -> bool {
+        self.cache.layer_visible(ocg)
+    }
+    fn strict(&self) -> bool {
+        self.cache.strict()
+    }
+    fn flatten_forms(&self) -> Option<Arc<FormDefaults>> {
+        self.cache.flatten_forms()
+    }
+}
+
+fn flatten_segment(lines: &mut Contour, segment: Segment, tolerance: f32, depth: u32) {
+    if segment.is_line() {
+        lines.push_endpoint(segment.baseline.to());
+        return;
+    }
+    let segment = segment.to_cubic();
+    if depth >= MAX_FLATTEN_DEPTH || segment.as_cubic_segment().is_flat(tolerance) {
+        lines.push_endpoint(segment.baseline.to());
+        return;
+    }
+    let (first, second) = segment.split(0.5);
+    flatten_segment(lines, first, tolerance, depth + 1);
+    flatten_segment(lines, second, tolerance, depth + 1);
+}
+
+/// `paint` in black, with its luminosity as the alpha. For the DestOut + Lighter pair in
+/// `push_path`, the alpha of `paint` itself is in the base color.
+fn luminosity_paint(paint: &Paint) -> Paint {
+    let to_alpha = |c: ColorU| {
+        let c = c.to_f32();
+        ColorF::new(0.0, 0.0, 0.0, luminosity(c.r(), c.g(), c.b()) * c.a()).to_u8()
+    };
+    let base = paint.base_color();
+    let mut lum = match paint.overlay().map(|overlay| overlay.contents()) {
+        None => return Paint::from_color(to_alpha(base)),
+        Some(PaintContents::Gradient(gradient)) => {
+            let mut gradient = gradient.clone();
+            for stop in gradient.stops_mut() {
+                stop.color = to_alpha(stop.color);
+            }
+            Paint::from_gradient(gradient)
+        }
+        Some(PaintContents::Pattern(pattern)) => match pattern.source() {
+            PatternSource::Image(image) => {
+                let pixels: Vec<ColorU> = image.pixels().iter().map(|&c| to_alpha(c)).collect();
+                let mut image_pattern = Pattern::from_image(Image::new(image.size(), Arc::new(pixels)));
+                image_pattern.apply_transform(pattern.transform());
+                image_pattern.set_smoothing_enabled(pattern.smoothing_enabled());
+                Paint::from_pattern(image_pattern)
+            }
+            // groups are drawn in place in mask groups
+            PatternSource::RenderTarget { .. } => return Paint::from_color(ColorU::transparent_black()),
+        },
+    };
+    lum.set_base_color(base);
+    lum
 }
 
 fn blend_mode(mode: backend::BlendMode) -> pathfinder_content::effects::BlendMode {
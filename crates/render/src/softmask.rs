@@ -0,0 +1,16 @@
+//! Soft masks (`/SMask` of an ExtGState), see `Backend::begin_mask`.
+
+/// Luminosity of a gray, RGB or CMYK backdrop (`/BC`).
+pub(crate) fn backdrop_luminosity(components: &[f32]) -> f32 {
+    match *components {
+        [g] => g,
+        [r, g, b] => luminosity(r, g, b),
+        [c, m, y, k] => luminosity((1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)),
+        _ => 0.0,
+    }
+}
+
+// PDF 32000-1:2008, 11.3.5.3
+pub(crate) fn luminosity(r: f32, g: f32, b: f32) -> f32 {
+    0.3 * r + 0.59 * g + 0.11 * b
+}
@@ -0,0 +1,160 @@
+use std::collections::HashSet;
+
+use pdf::error::Result;
+use pdf::object::{PlainRef, Resolve};
+use pdf::primitive::{Dictionary, Primitive};
+
+use crate::TextSpan;
+
+// deeper trees are cut off, they are broken or hostile
+const MAX_DEPTH: usize = 64;
+
+/// A marked content sequence (`BDC` with `/MCID`) belonging to a structure element.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MarkedContentRef {
+    /// The page whose content stream holds the sequence.
+    pub page: Option<PlainRef>,
+    pub mcid: i32,
+}
+
+/// An element of the logical structure of a tagged PDF.
+#[derive(Clone, Debug, Default)]
+pub struct StructNode {
+    /// Structure type, mapped to a standard type through the `/RoleMap`, e.g. "H1", "P" or "L".
+    /// "StructTreeRoot" for the root.
+    pub kind: String,
+    pub title: Option<String>,
+    pub alt: Option<String>,
+    pub actual_text: Option<String>,
+    /// The content of this element, in order. The content of children is in the children.
+    pub content: Vec<MarkedContentRef>,
+    pub children: Vec<StructNode>,
+}
+
+impl StructNode {
+    /// Read `/StructTreeRoot` from the document catalog, `None` if the document is not tagged.
+    ///
+    /// Object references (`/OBJR`, e.g. links) are skipped.
+    pub fn load(catalog: PlainRef, resolve: &impl Resolve) -> Result<Option<StructNode>> {
+        let catalog = resolve.resolve(catalog)?.into_dictionary()?;
+        let root = match catalog.get("StructTreeRoot") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => return Ok(None),
+        };
+        let role_map = match root.get("RoleMap") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => Dictionary::new(),
+        };
+        let mut loader = Loader { resolve, role_map, visited: HashSet::new() };
+        let mut node = StructNode { kind: "StructTreeRoot".into(), ..StructNode::default() };
+        if let Some(kids) = root.get("K") {
+            loader.kids(&mut node, kids, None, 0)?;
+        }
+        Ok(Some(node))
+    }
+
+    /// The text spans of this element (not of its children) on `page`.
+    ///
+    /// `spans` are the text of `page`, e.g. from `SceneBackend::finish_with_text`.
+    pub fn spans<'a>(&'a self, page: PlainRef, spans: &'a [TextSpan]) -> impl Iterator<Item = &'a TextSpan> + 'a {
+        spans.iter().filter(move |span| {
+            span.mcid.map_or(false, |mcid| self.content.iter().any(|c| c.page == Some(page) && c.mcid == mcid))
+        })
+    }
+
+    /// This element and all its descendants, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &StructNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+}
+
+struct Loader<'a, R> {
+    resolve: &'a R,
+    role_map: Dictionary,
+    visited: HashSet<PlainRef>,
+}
+
+impl<'a, R: Resolve> Loader<'a, R> {
+    /// Add the kids `/K` of a structure element to `node`. `page` is the `/Pg` in effect.
+    fn kids(&mut self, node: &mut StructNode, kids: &Primitive, page: Option<PlainRef>, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            warn!("structure tree deeper than {}", MAX_DEPTH);
+            return Ok(());
+        }
+        let kid = match *kids {
+            Primitive::Array(ref arr) => {
+                for kid in arr {
+                    self.kids(node, kid, page, depth)?;
+                }
+                return Ok(());
+            }
+            Primitive::Integer(mcid) => {
+                node.content.push(MarkedContentRef { page, mcid });
+                return Ok(());
+            }
+            Primitive::Reference(r) => {
+                if !self.visited.insert(r) {
+                    warn!("structure element {:?} appears twice", r);
+                    return Ok(());
+                }
+                self.resolve.resolve(r)?.into_dictionary()?
+            }
+            Primitive::Dictionary(ref dict) => dict.clone(),
+            _ => return Ok(()),
+        };
+        let page = match kid.get("Pg") {
+            Some(&Primitive::Reference(r)) => Some(r),
+            _ => page,
+        };
+        match kid.get("Type").and_then(|t| t.as_name().ok()) {
+            Some("MCR") => {
+                if let Some(mcid) = kid.get("MCID").and_then(|p| p.as_integer().ok()) {
+                    node.content.push(MarkedContentRef { page, mcid });
+                }
+            }
+            Some("OBJR") => {}
+            _ => {
+                let kind = match kid.get("S").and_then(|s| s.as_name().ok()) {
+                    Some(kind) => self.standard_kind(kind),
+                    None => return Ok(()),
+                };
+                let mut child = StructNode {
+                    kind,
+                    title: self.text(&kid, "T")?,
+                    alt: self.text(&kid, "Alt")?,
+                    actual_text: self.text(&kid, "ActualText")?,
+                    ..StructNode::default()
+                };
+                if let Some(kids) = kid.get("K") {
+                    self.kids(&mut child, kids, page, depth + 1)?;
+                }
+                node.children.push(child);
+            }
+        }
+        Ok(())
+    }
+
+    fn standard_kind(&self, kind: &str) -> String {
+        let mut kind = kind;
+        // role maps can chain, and loop
+        for _ in 0..8 {
+            match self.role_map.get(kind).and_then(|p| p.as_name().ok()) {
+                Some(mapped) if mapped != kind => kind = mapped,
+                _ => break,
+            }
+        }
+        kind.into()
+    }
+
+    fn text(&self, dict: &Dictionary, key: &str) -> Result<Option<String>> {
+        match dict.get(key) {
+            Some(p) => Ok(Some(p.clone().resolve(self.resolve)?.as_string()?.to_string_lossy())),
+            None => Ok(None),
+        }
+    }
+}
@@ -9,6 +9,7 @@ use super::{
     Backend,
     DrawMode,
     TextChar,
+    Type3Font,
 };
 use itertools::Either;
 use pdf::content::TextMode;
@@ -135,13 +136,12 @@ impl TextState {
             ) * e.font.font_matrix();
 
         for (cid, t) in glyphs {
-            let (gid, unicode, is_space) = match t {
-                Some((gid, unicode)) => {
-                    let is_space = !e.is_cid && unicode.as_deref() == Some(" ");
-                    (gid, unicode, is_space)
-                }
-                None => (GlyphId(0), None, cid == 0x20),
+            let (gid, unicode) = match t {
+                Some((gid, unicode)) => (gid, unicode),
+                None => (GlyphId(0), None),
             };
+            // word spacing applies to the single byte code 32, whatever glyph it maps to
+            let is_space = !e.is_cid && cid == 0x20;
             //debug!("cid {} -> gid {:?} {:?}", cid, gid, unicode);
 
             let glyph = e.font.glyph(gid);
@@ -151,38 +151,32 @@ impl TextState {
                 .or_else(|| glyph.as_ref().map(|g| tr.m11() * g.metrics.advance))
                 .unwrap_or(0.0);
 
-            if is_space {
-                let advance = (self.char_space + self.word_space) * self.horiz_scale + width;
-                self.text_matrix =
-                    self.text_matrix * Transform2F::from_translation(Vector2F::new(advance, 0.0));
-
-                let offset = span.text.len();
-                span.text.push(' ');
-                span.chars.push(TextChar {
-                    offset,
-                    pos: span.width,
-                    width,
-                });
-                span.width += advance;
-                continue;
-            }
             if let Some(glyph) = glyph {
                 let transform = gs.transform * self.text_matrix * tr;
                 if !glyph.path.contours().is_empty() {
                     span.bbox.add(gs.transform * transform * glyph.path.bounds());
                     if let Some(ref draw_mode) = draw_mode {
-                        backend.draw_glyph(&glyph, draw_mode, transform, gs.clip_path_id);
+                        gs.draw_masked(backend, draw_mode, |backend, mode, clip| {
+                            match e.font.downcast_ref::<Type3Font>() {
+                                Some(type3) => backend.draw_type3_glyph(type3, gid, mode, transform, clip),
+                                None => backend.draw_glyph(&glyph, mode, transform, clip),
+                            }
+                        });
                     }
                 }
-            } else {
+            } else if !is_space {
                 debug!("no glyph for gid {:?}", gid);
             }
-            let advance = self.char_space * self.horiz_scale + width;
+
+            // tx = ((w0 - Tj/1000) * Tfs + Tc + Tw) * Th, Tj is handled in `advance`
+            let spacing = if is_space { self.char_space + self.word_space } else { self.char_space };
+            let advance = spacing * self.horiz_scale + width;
             self.text_matrix =
                 self.text_matrix * Transform2F::from_translation(Vector2F::new(advance, 0.0));
 
             let offset = span.text.len();
-            if let Some(s) = unicode {
+            let text = if is_space { Some(" ".into()) } else { unicode };
+            if let Some(s) = text {
                 span.text.push_str(&*s);
                 span.chars.push(TextChar {
                     offset,
@@ -190,7 +184,8 @@ impl TextState {
                     width,
                 });
             }
-            span.width += advance;
+            span.include(span.width + width);
+            span.advance(advance);
         }
     }
     pub fn advance(&mut self, delta: f32) -> f32 {
@@ -208,4 +203,18 @@ pub struct Span {
     pub chars: Vec<TextChar>,
     pub width: f32,
     pub bbox: BBox,
+    // range of pen positions and glyph ends, relative to the start of the span
+    pub min_pos: f32,
+    pub max_pos: f32,
+}
+impl Span {
+    /// Move the pen by `delta`, which is negative for negative TJ adjustments and right to left text.
+    pub fn advance(&mut self, delta: f32) {
+        self.width += delta;
+        self.include(self.width);
+    }
+    fn include(&mut self, pos: f32) {
+        self.min_pos = self.min_pos.min(pos);
+        self.max_pos = self.max_pos.max(pos);
+    }
 }
@@ -1,9 +1,9 @@
 use crate::backend::Stroke;
 use crate::font::{load_font, StandardCache};
 use crate::{
-    backend::{BlendMode, FillMode}, Backend, DrawMode, FontEntry, TextSpan,
+    backend::{BlendMode, FillMode}, Backend, DrawMode, FontEntry, TextSpan, Type3Font,
 };
-use inkfont::Glyph;
+use inkfont::{Glyph, GlyphId};
 use globalcache::sync::SyncCache;
 use pathfinder_content::{fill::FillRule, outline::Outline};
 use pathfinder_geometry::{rect::RectF, transform2d::Transform2F, vector::Vector2F};
@@ -187,6 +187,15 @@ impl<'a> Backend for Tracer<'a> {
         clip: Option<ClipPathId>,
     ) {
     }
+    fn draw_type3_glyph(
+        &mut self,
+        _font: &Type3Font,
+        _gid: GlyphId,
+        _mode: &DrawMode,
+        _transform: Transform2F,
+        _clip: Option<ClipPathId>,
+    ) {
+    }
     fn get_font(
         &mut self,
         font_ref: &MaybeRef<PdfFont>,
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use inkfont::{Font, Glyph, GlyphId, HMetrics, Info, Name};
+use pathfinder_content::{
+    fill::FillRule,
+    outline::Outline,
+    stroke::OutlineStrokeToFill,
+    dash::OutlineDash,
+};
+use pathfinder_geometry::{rect::RectF, transform2d::Transform2F, vector::Vector2F};
+use pdf::content::Op;
+use pdf::error::{PdfError, Result};
+use pdf::font::Font as PdfFont;
+use pdf::object::*;
+use pdf::primitive::{Dictionary, Primitive};
+
+use crate::backend::{Backend, BlendMode, DrawMode};
+use crate::content::parse_ops_lenient;
+use crate::renderstate::RenderState;
+use crate::{FontEntry, TextSpan};
+
+/// A Type3 font, where every glyph is defined by a content stream (CharProc).
+///
+/// The glyph procedures are interpreted once when the font is loaded and the resulting
+/// paths (in glyph space) are kept, so they can be drawn like any other glyph.
+pub struct Type3Font {
+    glyphs: IndexMap<String, Glyph>, // name -> glyph
+    procs: Vec<GlyphProc>,           // glyph id -> what its procedure paints
+    codepoints: HashMap<u32, u32>,   // codepoint -> glyph id
+    font_matrix: Transform2F,
+    bbox: Option<RectF>,
+    name: Name,
+    info: Info,
+}
+
+impl Font for Type3Font {
+    fn num_glyphs(&self) -> u32 {
+        self.glyphs.len() as u32
+    }
+    fn font_matrix(&self) -> Transform2F {
+        self.font_matrix
+    }
+    fn glyph(&self, gid: GlyphId) -> Option<Glyph> {
+        self.glyphs
+            .get_index(gid.0 as usize)
+            .map(|(_, glyph)| glyph.clone())
+    }
+    fn is_empty_glyph(&self, gid: GlyphId) -> bool {
+        self.glyphs
+            .get_index(gid.0 as usize)
+            .map(|(_, glyph)| glyph.path.contours().len() == 0)
+            .unwrap_or(true)
+    }
+    fn gid_for_codepoint(&self, codepoint: u32) -> Option<GlyphId> {
+        let &index = self.codepoints.get(&codepoint)?;
+        Some(GlyphId(index))
+    }
+    fn gid_for_name(&self, name: &str) -> Option<GlyphId> {
+        self.glyphs
+            .get_full(name)
+            .map(|(id, _, _)| GlyphId(id as u32))
+    }
+    fn bbox(&self) -> Option<RectF> {
+        self.bbox
+    }
+    fn name(&self) -> &Name {
+        &self.name
+    }
+    fn info(&self) -> &Info {
+        &self.info
+    }
+}
+
+impl Type3Font {
+    pub fn load(pdf_font: &PdfFont, resolve: &impl Resolve) -> Result<Type3Font> {
+        let dict = match pdf_font.data {
+            pdf::font::FontData::Other(ref dict) => dict,
+            _ => return Err(PdfError::Other { msg: "Type3 font without font dictionary".into() }),
+        };
+
+        let font_matrix = match dict.get("FontMatrix") {
+            Some(p) => matrix(&p.clone().resolve(resolve)?)?,
+            None => Transform2F::from_scale(0.001),
+        };
+        let bbox = match dict.get("FontBBox") {
+            Some(p) => rect(&p.clone().resolve(resolve)?).ok(),
+            None => None,
+        };
+        let resources = match dict.get("Resources") {
+            Some(p) => Resources::from_primitive(p.clone(), resolve)?,
+            None => Resources::default(),
+        };
+
+        let first_char = match dict.get("FirstChar") {
+            Some(p) => p.clone().resolve(resolve)?.as_integer()?,
+            None => 0,
+        };
+        let widths: Vec<f32> = match dict.get("Widths") {
+            Some(p) => p.clone().resolve(resolve)?
+                .as_array()?
+                .iter()
+                .map(|w| w.as_number())
+                .collect::<Result<_>>()?,
+            None => vec![],
+        };
+        let width_of = |name: &str| -> f32 {
+            let encoding = match pdf_font.encoding() {
+                Some(e) => e,
+                None => return 0.0,
+            };
+            encoding.differences.iter()
+                .find(|&(_, n)| n.as_str() == name)
+                .and_then(|(&cp, _)| widths.get((cp as i32 - first_char) as usize))
+                .cloned()
+                .unwrap_or(0.0)
+        };
+
+        let char_procs = match dict.get("CharProcs") {
+            Some(p) => p.clone().resolve(resolve)?.into_dictionary()?,
+            None => Dictionary::new(),
+        };
+
+        let mut glyphs = IndexMap::with_capacity(char_procs.len());
+        let mut procs = Vec::with_capacity(char_procs.len());
+        for (name, proc) in char_procs.iter() {
+            let stream = t!(Stream::<()>::from_primitive(proc.clone(), resolve));
+            let data = t!(stream.data(resolve));
+            // some writers' d0/d1 are not understood, the paths still are
+            let ops = t!(parse_ops_lenient(&data, resolve, false));
+            let glyph_proc = glyph_proc(&ops, &resources, resolve)?;
+            let advance = width_of(name.as_str());
+
+            glyphs.insert(name.as_str().into(), Glyph {
+                metrics: HMetrics { lsb: 0.0, advance },
+                path: glyph_proc.outline(),
+            });
+            procs.push(glyph_proc);
+        }
+
+        let mut codepoints = HashMap::new();
+        if let Some(encoding) = pdf_font.encoding() {
+            for (&cp, name) in encoding.differences.iter() {
+                if let Some(index) = glyphs.get_index_of(name.as_str()) {
+                    codepoints.insert(cp, index as u32);
+                }
+            }
+        }
+
+        Ok(Type3Font {
+            glyphs,
+            procs,
+            codepoints,
+            font_matrix,
+            bbox,
+            name: Name::default(),
+            info: Info { weight: None },
+        })
+    }
+}
+
+impl Type3Font {
+    /// Draw glyph `gid` like `Backend::draw_glyph`, each path with the fill rule and
+    /// clip path its procedure used.
+    pub fn draw_glyph<B: Backend + ?Sized>(
+        &self,
+        backend: &mut B,
+        gid: GlyphId,
+        mode: &DrawMode,
+        transform: Transform2F,
+        clip: Option<B::ClipPathId>,
+    ) {
+        let Some(glyph) = self.procs.get(gid.0 as usize) else {
+            return;
+        };
+        // a clip path comes after the one it is inside of
+        let mut clips = Vec::with_capacity(glyph.clips.len());
+        for c in glyph.clips.iter() {
+            let mut outline = c.outline.clone();
+            outline.transform(&transform);
+            let parent = c.clip.map(|i| clips[i]).or(clip);
+            clips.push(backend.create_clip_path(outline, c.fill_rule, parent));
+        }
+        for p in glyph.paths.iter() {
+            let clip = p.clip.map(|i| clips[i]).or(clip);
+            backend.draw(&p.outline, mode, p.fill_rule, transform, clip);
+        }
+    }
+}
+
+/// An outline in glyph space, and the index of the clip path it is inside of.
+#[derive(Clone)]
+struct GlyphPath {
+    outline: Outline,
+    fill_rule: FillRule,
+    clip: Option<usize>,
+}
+
+/// What a glyph procedure paints, and the clip paths it sets.
+#[derive(Clone, Default)]
+pub(crate) struct GlyphProc {
+    paths: Vec<GlyphPath>,
+    clips: Vec<GlyphPath>,
+}
+
+impl GlyphProc {
+    /// All painted paths in one outline, for the bounds and to test for an empty glyph.
+    pub(crate) fn outline(&self) -> Outline {
+        let mut outline = Outline::new();
+        for p in self.paths.iter() {
+            for contour in p.outline.contours() {
+                outline.push_contour(contour.clone());
+            }
+        }
+        outline
+    }
+}
+
+/// Interpret a glyph procedure and collect everything it paints.
+///
+/// The paths are in glyph space; the font matrix is applied when the glyph is drawn.
+pub(crate) fn glyph_proc(ops: &[Op], resources: &Resources, resolve: &impl Resolve) -> Result<GlyphProc> {
+    let mut collector = GlyphCollector { glyph: GlyphProc::default() };
+    let mut renderstate = RenderState::new(&mut collector, resolve, resources, Transform2F::default());
+    for (i, op) in ops.iter().enumerate() {
+        debug!("glyph op {}: {:?}", i, op);
+        renderstate.draw_op(op, i)?;
+    }
+    Ok(collector.glyph)
+}
+
+fn matrix(p: &Primitive) -> Result<Transform2F> {
+    let m = p.as_array()?;
+    if m.len() != 6 {
+        return Err(PdfError::Other { msg: format!("expected 6 matrix entries, got {}", m.len()) });
+    }
+    let [a, b, c, d, e, f] = [0, 1, 2, 3, 4, 5].map(|i| m[i].as_number().unwrap_or(0.0));
+    Ok(Transform2F::row_major(a, c, e, b, d, f))
+}
+
+fn rect(p: &Primitive) -> Result<RectF> {
+    let r = Rect::from_primitive(p.clone(), &NoResolve)?;
+    Ok(RectF::from_points(
+        Vector2F::new(r.left.min(r.right), r.bottom.min(r.top)),
+        Vector2F::new(r.left.max(r.right), r.bottom.max(r.top)),
+    ))
+}
+
+/// A backend that keeps the painted paths and clip paths of a glyph procedure.
+struct GlyphCollector {
+    glyph: GlyphProc,
+}
+
+impl Backend for GlyphCollector {
+    type ClipPathId = usize;
+
+    fn create_clip_path(&mut self, path: Outline, fill_rule: FillRule, parent: Option<usize>) -> usize {
+        self.glyph.clips.push(GlyphPath { outline: path, fill_rule, clip: parent });
+        self.glyph.clips.len() - 1
+    }
+
+    fn draw(
+        &mut self,
+        outline: &Outline,
+        mode: &DrawMode,
+        fill_rule: FillRule,
+        transform: Transform2F,
+        clip: Option<usize>,
+    ) {
+        let mut path = match mode {
+            DrawMode::Fill { .. } | DrawMode::FillStroke { .. } => outline.clone(),
+            DrawMode::Stroke { stroke_mode, .. } => {
+                let dashed;
+                let outline = match stroke_mode.dash_pattern {
+                    Some((ref pat, phase)) => {
+                        dashed = OutlineDash::new(outline, &*pat, phase).into_outline();
+                        &dashed
+                    }
+                    None => outline,
+                };
+                let mut stroke = OutlineStrokeToFill::new(outline, stroke_mode.style);
+                stroke.offset();
+                stroke.into_outline()
+            }
+        };
+        path.transform(&transform);
+        // strokes are filled with the nonzero rule
+        let fill_rule = match mode {
+            DrawMode::Stroke { .. } => FillRule::Winding,
+            _ => fill_rule,
+        };
+        self.glyph.paths.push(GlyphPath { outline: path, fill_rule, clip });
+    }
+
+    fn set_view_box(&mut self, _r: RectF) {}
+
+    fn draw_image(
+        &mut self,
+        _xref: Ref<XObject>,
+        _im: &ImageXObject,
+        _resources: &Resources,
+        _transform: Transform2F,
+        _mode: BlendMode,
+        _clip: Option<usize>,
+        _resolve: &impl Resolve,
+    ) {
+        debug!("image in Type3 glyph ignored");
+    }
+
+    fn draw_inline_image(
+        &mut self,
+        _im: &Arc<ImageXObject>,
+        _resources: &Resources,
+        _transform: Transform2F,
+        _mode: BlendMode,
+        _clip: Option<usize>,
+        _resolve: &impl Resolve,
+    ) {
+        debug!("inline image in Type3 glyph ignored");
+    }
+
+    fn get_font(
+        &mut self,
+        _font_ref: &MaybeRef<PdfFont>,
+        _resolve: &impl Resolve,
+    ) -> Result<Option<Arc<FontEntry>>> {
+        Ok(None)
+    }
+
+    fn add_text(&mut self, _span: TextSpan, _clip: Option<usize>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf::content::Winding;
+
+    #[test]
+    fn test_square_glyph() {
+        let ops = vec![
+            Op::Rect { rect: pdf::content::Rect { x: 0.0, y: 0.0, width: 750.0, height: 750.0 } },
+            Op::Fill { winding: Winding::NonZero },
+        ];
+        let resources = Resources::default();
+        let glyph = glyph_proc(&ops, &resources, &NoResolve).unwrap();
+        let outline = glyph.outline();
+
+        // the crate-level `assert_eq!` returns a PdfError, use the std one here
+        std::assert_eq!(outline.contours().len(), 1);
+        let bounds = outline.bounds();
+        std::assert_eq!(bounds.origin(), Vector2F::new(0.0, 0.0));
+        std::assert_eq!(bounds.size(), Vector2F::new(750.0, 750.0));
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use pdf::content::Op;
+use pdf::enc::StreamFilter;
+use pdf::object::*;
+use pdf::primitive::Name;
+
+// form XObjects nested deeper are not looked into
+const MAX_FORM_DEPTH: usize = 16;
+
+/// Problems with the resources the content of `page` uses, found without rendering it.
+///
+/// Reports fonts and XObjects that are missing from the resources or can't be loaded,
+/// embedded fonts that don't parse and images with filters the renderer does not support.
+/// Empty if nothing is wrong. Fonts that are not embedded are replaced by a standard font
+/// when rendering and are not reported.
+pub fn page_warnings(page: &Page, resolve: &impl Resolve) -> Vec<String> {
+    let mut check = Check { resolve, checked: HashSet::new(), warnings: vec![] };
+    let resources = match page.resources() {
+        Ok(resources) => resources,
+        Err(e) => return vec![format!("can't load the page resources: {}", e)],
+    };
+    if let Some(ref contents) = page.contents {
+        match contents.operations(resolve) {
+            Ok(ops) => check.ops(&ops, resources, 0),
+            Err(e) => check.warnings.push(format!("can't parse the page content: {}", e)),
+        }
+    }
+    check.warnings
+}
+
+struct Check<'a, R> {
+    resolve: &'a R,
+    // (resources, name) pairs looked at already
+    checked: HashSet<(usize, Name)>,
+    warnings: Vec<String>,
+}
+
+impl<'a, R: Resolve> Check<'a, R> {
+    fn ops(&mut self, ops: &[Op], resources: &Resources, depth: usize) {
+        for op in ops {
+            match *op {
+                Op::TextFont { ref name, .. } => self.font(name, resources),
+                Op::XObject { ref name } => self.xobject(name, resources, depth),
+                _ => {}
+            }
+        }
+    }
+
+    fn first_use(&mut self, name: &Name, resources: &Resources) -> bool {
+        self.checked.insert((resources as *const Resources as usize, name.clone()))
+    }
+
+    fn font(&mut self, name: &Name, resources: &Resources) {
+        if !self.first_use(name, resources) {
+            return;
+        }
+        let font = match resources.fonts.get(name) {
+            Some(font) => font,
+            None => {
+                self.warnings.push(format!("font /{} is not in the resources", name));
+                return;
+            }
+        };
+        let base_font = font.name.as_ref().map(|n| n.as_str()).unwrap_or("unnamed");
+        match font.embedded_data(self.resolve) {
+            Some(Ok(data)) => {
+                if let Err(e) = inkfont::parse(&data) {
+                    self.warnings.push(format!("font /{} ({}) can't be parsed: {:?}", name, base_font, e));
+                }
+            }
+            Some(Err(e)) => {
+                self.warnings.push(format!("font /{} ({}) can't be read: {}", name, base_font, e));
+            }
+            None => {}
+        }
+    }
+
+    fn xobject(&mut self, name: &Name, resources: &Resources, depth: usize) {
+        if !self.first_use(name, resources) {
+            return;
+        }
+        let xobject = match resources.xobjects.get(name) {
+            Some(&xobject_ref) => match self.resolve.get(xobject_ref) {
+                Ok(xobject) => xobject,
+                Err(e) => {
+                    self.warnings.push(format!("XObject /{} can't be loaded: {}", name, e));
+                    return;
+                }
+            },
+            None => {
+                self.warnings.push(format!("XObject /{} is not in the resources", name));
+                return;
+            }
+        };
+        match *xobject {
+            XObject::Image(ref image) => {
+                // see `image_bytes`
+                if let Some(StreamFilter::JPXDecode) = image.inner.filters.last() {
+                    self.warnings.push(format!("image /{} is JPEG 2000 (JPXDecode), which is not supported", name));
+                }
+            }
+            XObject::Form(ref form) if depth < MAX_FORM_DEPTH => {
+                let form_resources = match form.dict().resources {
+                    Some(ref r) => &**r,
+                    None => resources,
+                };
+                match form.operations(self.resolve) {
+                    Ok(ops) => self.ops(&ops, form_resources, depth + 1),
+                    Err(e) => self.warnings.push(format!("form /{} can't be parsed: {}", name, e)),
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+
+use crate::TextSpan;
+
+// a gap wider than this (in em) starts a new word, even without a space
+const WORD_GAP: f32 = 0.15;
+
+/// A word of the page text, see `page_words`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Word {
+    pub text: String,
+    /// From the start of the first glyph to the end of the last, one em high.
+    pub rect: RectF,
+}
+
+/// Words that share a baseline, see `page_lines`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLine {
+    /// The words, separated by a space.
+    pub text: String,
+    pub rect: RectF,
+    pub words: Vec<Word>,
+}
+
+/// Split the text spans of a page into words.
+///
+/// Words end at whitespace and at gaps wider than 0.15 em, and continue across spans
+/// that follow each other without a gap (kerned `TJ` arrays, font changes). Spans are
+/// read in stream order, text is assumed to be horizontal.
+pub fn page_words(spans: &[TextSpan]) -> Vec<Word> {
+    let mut words = vec![];
+    let mut current: Option<Word> = None;
+    for span in spans {
+        let (min, max) = span.extent;
+        if max <= min {
+            continue;
+        }
+        let scale = span.rect.width() / (max - min);
+        let x = |pos: f32| span.rect.min_x() + (pos - min) * scale;
+        let gap = WORD_GAP * span.rect.height();
+        for part in span.parts() {
+            if part.text.chars().all(char::is_whitespace) {
+                words.extend(current.take());
+                continue;
+            }
+            let (x0, x1) = (x(part.pos), x(part.pos + part.width));
+            let rect = RectF::from_points(
+                Vector2F::new(x0.min(x1), span.rect.min_y()),
+                Vector2F::new(x0.max(x1), span.rect.max_y()),
+            );
+            match current {
+                Some(ref mut word) if same_line(word.rect, rect) && (rect.min_x() - word.rect.max_x()).abs() < gap => {
+                    word.text.push_str(part.text);
+                    word.rect = word.rect.union_rect(rect);
+                }
+                _ => {
+                    words.extend(current.take());
+                    current = Some(Word { text: part.text.into(), rect });
+                }
+            }
+        }
+    }
+    words.extend(current);
+    words
+}
+
+/// Group `words` into lines: a line continues while the words share the baseline and
+/// go on to the right.
+pub fn page_lines(words: &[Word]) -> Vec<TextLine> {
+    let mut lines: Vec<TextLine> = vec![];
+    for word in words {
+        let continues = lines.last().map_or(false, |line| {
+            let last = line.words.last().unwrap().rect;
+            same_line(last, word.rect) && word.rect.min_x() >= last.min_x()
+        });
+        if continues {
+            let line = lines.last_mut().unwrap();
+            line.text.push(' ');
+            line.text.push_str(&word.text);
+            line.rect = line.rect.union_rect(word.rect);
+            line.words.push(word.clone());
+        } else {
+            lines.push(TextLine { text: word.text.clone(), rect: word.rect, words: vec![word.clone()] });
+        }
+    }
+    lines
+}
+
+fn same_line(a: RectF, b: RectF) -> bool {
+    (a.center().y() - b.center().y()).abs() < 0.25 * a.height().max(b.height())
+}
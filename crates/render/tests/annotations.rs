@@ -0,0 +1,67 @@
+mod common;
+
+use inkrender::tracer::{ClipPathId, DrawItem};
+use inkrender::{render_page, Cache, Layers, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use common::{open_fixture, trace};
+
+/// Bounds and clip of the paths drawn on page 0 of fixture `name`.
+fn traced_paths(name: &str) -> Vec<(RectF, Option<ClipPathId>)> {
+    trace(&open_fixture(name), 0)
+        .into_iter()
+        .filter_map(|item| match item {
+            DrawItem::Vector(path) => Some((path.transform * path.outline.bounds(), path.clip)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn assert_widget_rect(rect: RectF) {
+    let pt = 25.4 / 72.;
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-2;
+    assert!(close(rect.min_x(), 100.0 * pt), "{:?}", rect);
+    assert!(close(rect.max_x(), 300.0 * pt), "{:?}", rect);
+    assert!(close(rect.min_y(), (792.0 - 740.0) * pt), "{:?}", rect);
+    assert!(close(rect.max_y(), (792.0 - 700.0) * pt), "{:?}", rect);
+}
+
+#[test]
+fn test_annotations_after_unbalanced_content() {
+    // the content stream scales, clips and opens a layer without restoring any of it
+    let paths = traced_paths("annotations_unbalanced.pdf");
+    assert_eq!(paths.len(), 1);
+    let (rect, clip) = paths[0];
+    assert_widget_rect(rect);
+    assert!(clip.is_none());
+
+    // hiding the layer the content left open doesn't hide the widget
+    let file = open_fixture("annotations_unbalanced.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+    let mut layers = Layers::load(file.trailer.root.get_ref().get_inner(), &resolver).unwrap();
+    assert!(layers.set_visible("Layer", false));
+
+    let mut cache = Cache::new();
+    cache.set_hidden_layers(layers.hidden());
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    assert_widget_rect(backend.content_bounds().expect("the widget is not drawn"));
+}
+
+#[test]
+fn test_annotations_without_contents() {
+    let paths = traced_paths("annotations_no_contents.pdf");
+    assert_eq!(paths.len(), 1);
+    assert_widget_rect(paths[0].0);
+}
+
+#[test]
+fn test_widget_appearance() {
+    // the hidden square is skipped
+    let paths = traced_paths("annotations.pdf");
+    assert_eq!(paths.len(), 1);
+
+    // the 100 x 20 appearance fills the widget rect [100 700 300 740] (in pt, page space is in mm)
+    assert_widget_rect(paths[0].0);
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use inkrender::load_attachments;
+use common::open_fixture;
+
+#[test]
+fn test_embedded_file() {
+    let file = open_fixture("attachment.pdf");
+    let resolver = file.resolver();
+
+    // listed in /EmbeddedFiles and attached to an annotation, reported once
+    let attachments = load_attachments(file.trailer.root.get_ref().get_inner(), &resolver).unwrap();
+    assert_eq!(attachments.len(), 1);
+    let attachment = &attachments[0];
+    assert_eq!(attachment.name, "notes.txt");
+    assert_eq!(attachment.mime.as_deref(), Some("text/plain"));
+    assert_eq!(attachment.data.len(), 19);
+    assert_eq!(&attachment.data[..5], b"Hello");
+}
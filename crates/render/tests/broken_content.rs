@@ -0,0 +1,46 @@
+mod common;
+
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_geometry::transform2d::Transform2F;
+use common::open_fixture;
+
+#[test]
+fn test_skip_broken_op() {
+    let file = open_fixture("broken_content.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    // `/Missing Do` sits between a rect at x = 10..60 and one at x = 100..150 (in pt)
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let bounds = backend.finish().bounds();
+    let scale = 25.4 / 72.0;
+    assert!((bounds.min_x() - 10.0 * scale).abs() < 0.1, "{:?}", bounds);
+    assert!((bounds.max_x() - 150.0 * scale).abs() < 0.1, "{:?}", bounds);
+
+    cache.set_strict(true);
+    let mut backend = SceneBackend::new(&mut cache);
+    assert!(render_page(&mut backend, &resolver, &page, Transform2F::default()).is_err());
+}
+
+#[test]
+fn test_skip_malformed_op() {
+    let file = open_fixture("malformed_op.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    // `20 20 re` is missing two operands and `bogus` is no operator, both sit between
+    // a rect at x = 10..60 and one at x = 100..150 (in pt)
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let bounds = backend.finish().bounds();
+    let scale = 25.4 / 72.0;
+    assert!((bounds.min_x() - 10.0 * scale).abs() < 0.1, "{:?}", bounds);
+    assert!((bounds.max_x() - 150.0 * scale).abs() < 0.1, "{:?}", bounds);
+
+    cache.set_strict(true);
+    let mut backend = SceneBackend::new(&mut cache);
+    assert!(render_page(&mut backend, &resolver, &page, Transform2F::default()).is_err());
+}
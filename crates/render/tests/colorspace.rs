@@ -0,0 +1,24 @@
+use inkrender::lab_to_rgb;
+
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+const D65: [f32; 3] = [0.9505, 1.0, 1.089];
+
+fn assert_close(rgb: [f32; 3], expected: [f32; 3]) {
+    for (c, e) in rgb.iter().zip(expected) {
+        assert!((c - e).abs() < 0.01, "{:?} != {:?}", rgb, expected);
+    }
+}
+
+#[test]
+fn test_lab_to_rgb() {
+    // sRGB red
+    assert_close(lab_to_rgb([53.24, 80.09, 67.20], D65), [1.0, 0.0, 0.0]);
+    // neutral colors stay neutral under any white point
+    assert_close(lab_to_rgb([50.0, 0.0, 0.0], D50), [0.4663, 0.4663, 0.4663]);
+    assert_close(lab_to_rgb([100.0, 0.0, 0.0], D50), [1.0, 1.0, 1.0]);
+    assert_close(lab_to_rgb([0.0, 0.0, 0.0], D50), [0.0, 0.0, 0.0]);
+    // out of gamut values are clamped
+    for c in lab_to_rgb([50.0, 127.0, -127.0], D50) {
+        assert!((0.0..=1.0).contains(&c));
+    }
+}
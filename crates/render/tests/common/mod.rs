@@ -0,0 +1,49 @@
+//! Shared by the integration tests, each one only uses some of it.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use inkrender::render_page;
+use inkrender::tracer::{DrawItem, TraceCache, Tracer};
+use inkrender::TextSpan;
+use pathfinder_geometry::transform2d::Transform2F;
+use pdf::any::AnySync;
+use pdf::error::PdfError;
+use pdf::file::{File as PdfFile, FileOptions, NoLog, SyncCache};
+use pdf::object::PlainRef;
+
+pub type CachedFile = PdfFile<
+    Vec<u8>,
+    Arc<SyncCache<PlainRef, Result<AnySync, Arc<PdfError>>>>,
+    Arc<SyncCache<PlainRef, Result<Arc<[u8]>, Arc<PdfError>>>>,
+    NoLog,
+>;
+
+/// Open `tests/fixtures/<name>`.
+pub fn open_fixture(name: &str) -> CachedFile {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    FileOptions::cached().open(&path).unwrap_or_else(|e| panic!("can't open {}: {}", path, e))
+}
+
+/// Everything drawn on page `nr`, in page space.
+pub fn trace(file: &CachedFile, nr: u32) -> Vec<DrawItem> {
+    let resolver = file.resolver();
+    let page = file.get_page(nr).unwrap();
+
+    let cache = TraceCache::new();
+    let mut clip_paths = vec![];
+    let mut tracer = Tracer::new(&cache, &mut clip_paths);
+    render_page(&mut tracer, &resolver, &page, Transform2F::default()).unwrap();
+    tracer.finish()
+}
+
+/// The text spans drawn on page `nr`.
+pub fn trace_text(file: &CachedFile, nr: u32) -> Vec<TextSpan> {
+    trace(file, nr)
+        .into_iter()
+        .filter_map(|item| match item {
+            DrawItem::Text(span, _) => Some(span),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use common::open_fixture;
+
+#[test]
+fn test_content_bounds() {
+    let file = open_fixture("content_bounds.pdf");
+    let resolver = file.resolver();
+    let mut cache = Cache::new();
+
+    let mut bounds = |nr: u32| {
+        let page = file.get_page(nr).unwrap();
+        let mut backend = SceneBackend::new(&mut cache);
+        render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+        backend.content_bounds()
+    };
+
+    // 200 x 200 pt: a rect at [50 100 90 120] and a 4 pt wide line from (120, 30) to (160, 30)
+    let bounds_1 = bounds(0).unwrap();
+    // y down: the rect spans 80..100, the line 168..172
+    let expected = RectF::from_points(Vector2F::new(50.0, 80.0), Vector2F::new(160.0, 172.0));
+    assert!(
+        (bounds_1.origin() - expected.origin()).length() < 0.1 && (bounds_1.size() - expected.size()).length() < 0.1,
+        "{:?} != {:?}", bounds_1, expected
+    );
+
+    // the white page background does not count
+    assert_eq!(bounds(1), None);
+}
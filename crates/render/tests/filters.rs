@@ -0,0 +1,33 @@
+//! Older PDFs compress streams with LZW or run length encoding.
+//! Both are decoded by the `pdf` crate; make sure they keep working.
+
+use pdf::enc::{decode, LZWFlateParams, StreamFilter};
+
+#[test]
+fn test_lzw_decode() {
+    // the example from the PDF reference, section 7.4.4.2
+    let data = [0x80, 0x0B, 0x60, 0x50, 0x22, 0x0C, 0x0C, 0x85, 0x01];
+    let filter = StreamFilter::LZWDecode(LZWFlateParams::default());
+    assert_eq!(decode(&data, &filter).unwrap(), b"-----A---B");
+}
+
+#[test]
+fn test_lzw_decode_early_change() {
+    // long enough that the code width grows past 9 bits
+    let expected: Vec<u8> = (0..600u32).map(|i| ((i * i * 7 + i) % 251) as u8).collect();
+    let data = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/lzw_early_change_0.bin")).unwrap();
+
+    let filter = StreamFilter::LZWDecode(LZWFlateParams { early_change: 0, ..Default::default() });
+    assert_eq!(decode(&data, &filter).unwrap(), expected);
+
+    // the code width changes one code too early with the default
+    let filter = StreamFilter::LZWDecode(LZWFlateParams::default());
+    assert_ne!(decode(&data, &filter).ok(), Some(expected));
+}
+
+#[test]
+fn test_run_length_decode() {
+    // 3 literal bytes, 'x' repeated 3 times, EOD
+    let data = [0x02, b'a', b'b', b'c', 0xFE, b'x', 0x80];
+    assert_eq!(decode(&data, &StreamFilter::RunLengthDecode).unwrap(), b"abcxxx");
+}
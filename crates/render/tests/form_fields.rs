@@ -0,0 +1,42 @@
+mod common;
+
+use inkrender::{render_page, Cache, FormDefaults, SceneBackend};
+use pathfinder_geometry::transform2d::Transform2F;
+use common::open_fixture;
+
+const SCALE: f32 = 25.4 / 72.0;
+
+#[test]
+fn test_flatten_forms() {
+    let file = open_fixture("form_fields.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let render = |cache: &mut Cache| {
+        let mut backend = SceneBackend::new(cache);
+        backend.collect_text();
+        render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+        backend.finish_with_text().1
+    };
+
+    // the fields have no appearance streams, only their values
+    let mut cache = Cache::new();
+    assert!(render(&mut cache).is_empty());
+
+    let form = FormDefaults::load(file.trailer.root.get_ref().get_inner(), &resolver).unwrap();
+    assert_eq!(form.appearance.as_deref(), Some("/Cour 0 Tf 0 g"));
+    cache.set_flatten_forms(Some(form));
+    let spans = render(&mut cache);
+    let texts: Vec<_> = spans.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, ["Hello", "Paris"]);
+
+    // inside the widget rects [100 700 300 720] and [100 600 300 640] (in pt)
+    let hello = spans[0].rect;
+    assert!((hello.min_x() - 102.0 * SCALE).abs() < 0.1, "{:?}", hello);
+    assert!(hello.min_y() >= (792.0 - 720.0) * SCALE && hello.max_y() <= (792.0 - 700.0) * SCALE, "{:?}", hello);
+    assert_eq!(spans[0].font_size, 12.0);
+    // the inherited value in the default appearance, size 0 fits the field
+    let paris = spans[1].rect;
+    assert!(paris.min_y() >= (792.0 - 640.0) * SCALE && paris.max_y() <= (792.0 - 600.0) * SCALE, "{:?}", paris);
+    assert!(spans[1].font_size > 12.0);
+}
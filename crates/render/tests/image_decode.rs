@@ -0,0 +1,31 @@
+mod common;
+
+use inkrender::{load_image, BlendMode};
+use pdf::object::{Resolve, XObject};
+use common::open_fixture;
+
+// 8x2 1 bit gray images with the rows 11110000 and 00001111,
+// Im1 as it is, Im2 inverted with /Decode [1 0]
+#[test]
+fn test_decode_1bit() {
+    let file = open_fixture("bilevel.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+    let resources = page.resources().unwrap();
+
+    let gray = |name: &str| -> Vec<u8> {
+        let (_, &xobject_ref) = resources.xobjects.iter().find(|(n, _)| n.as_str() == name).unwrap();
+        let xobject = resolver.get(xobject_ref).unwrap();
+        let image = match *xobject {
+            XObject::Image(ref image) => image,
+            _ => panic!("{} is not an image", name),
+        };
+        let data = load_image(image, &resources, &resolver, BlendMode::Overlay).unwrap();
+        assert_eq!((data.width(), data.height()), (8, 2));
+        data.data().iter().map(|c| c.r).collect()
+    };
+
+    let (w, b) = (255, 0);
+    assert_eq!(gray("Im1"), [w, w, w, w, b, b, b, b, b, b, b, b, w, w, w, w]);
+    assert_eq!(gray("Im2"), [b, b, b, b, w, w, w, w, w, w, w, w, b, b, b, b]);
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use inkrender::{load_image, render_page, BlendMode, Cache, SceneBackend};
+use pathfinder_color::ColorU;
+use pathfinder_geometry::transform2d::Transform2F;
+use pdf::object::{Resolve, XObject};
+use common::open_fixture;
+
+// 8x8 single color JPEGs:
+// Im1 gray 100, Im2 the same with /Decode [1 0],
+// Im3 YCbCr (JFIF) of rgb(200, 50, 50), Im4 rgb(200, 50, 50) stored as is with /ColorTransform 0
+#[test]
+fn test_dct_decode() {
+    let file = open_fixture("jpeg.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+    let resources = page.resources().unwrap();
+
+    let pixel = |name: &str| -> ColorU {
+        let (_, &xobject_ref) = resources.xobjects.iter().find(|(n, _)| n.as_str() == name).unwrap();
+        let xobject = resolver.get(xobject_ref).unwrap();
+        let image = match *xobject {
+            XObject::Image(ref image) => image,
+            _ => panic!("{} is not an image", name),
+        };
+        let data = load_image(image, &resources, &resolver, BlendMode::Overlay).unwrap();
+        assert_eq!((data.width(), data.height()), (8, 8));
+        data.data()[0]
+    };
+    let close = |c: ColorU, [r, g, b]: [u8; 3]| {
+        assert!(c.r.abs_diff(r) <= 2 && c.g.abs_diff(g) <= 2 && c.b.abs_diff(b) <= 2, "{:?} != {:?}", c, [r, g, b]);
+    };
+
+    assert_eq!(pixel("Im1"), ColorU::new(100, 100, 100, 255));
+    assert_eq!(pixel("Im2"), ColorU::new(155, 155, 155, 255));
+    close(pixel("Im3"), [200, 50, 50]);
+    assert_eq!(pixel("Im4"), ColorU::new(200, 50, 50, 255));
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+}
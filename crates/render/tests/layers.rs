@@ -0,0 +1,36 @@
+mod common;
+
+use inkrender::{render_page, Cache, Layers, SceneBackend};
+use pathfinder_geometry::transform2d::Transform2F;
+use common::open_fixture;
+
+#[test]
+fn test_hide_layer() {
+    let file = open_fixture("layers.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut layers = Layers::load(file.trailer.root.get_ref().get_inner(), &resolver).unwrap();
+    let names: Vec<_> = layers.layers().iter().map(|l| (l.name.as_str(), l.visible)).collect();
+    assert_eq!(names, [("Layer 1", true), ("Layer 2", true)]);
+
+    let mut cache = Cache::new();
+    let mut render = |cache: &mut Cache| {
+        let mut backend = SceneBackend::new(cache);
+        render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+        backend.finish().bounds()
+    };
+
+    // layer 1 covers x = 10..60, layer 2 x = 100..150 (in pt)
+    let all = render(&mut cache);
+
+    assert!(layers.set_visible("Layer 2", false));
+    cache.set_hidden_layers(layers.hidden());
+    let hidden = render(&mut cache);
+
+    assert_eq!(hidden.min_x(), all.min_x());
+    assert!(hidden.max_x() < all.max_x());
+    assert!(hidden.width() < 0.5 * all.width());
+
+    assert!(!layers.set_visible("Layer 3", false));
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use inkrender::{page_bounds, page_crop_box, page_media_box};
+use pathfinder_geometry::vector::Vector2F;
+use common::open_fixture;
+
+const SCALE: f32 = 25.4 / 72.;
+
+#[test]
+fn test_crop_box() {
+    let file = open_fixture("cropbox.pdf");
+    let page = file.get_page(0).unwrap();
+
+    let media_box = page_media_box(&page);
+    assert_eq!(media_box.origin(), Vector2F::new(0.0, 0.0));
+    assert_eq!(media_box.size(), Vector2F::new(612.0, 792.0) * SCALE);
+
+    let crop_box = page_crop_box(&page);
+    assert_eq!(crop_box.origin(), Vector2F::new(36.0, 36.0) * SCALE);
+    assert_eq!(crop_box.size(), Vector2F::new(540.0, 720.0) * SCALE);
+
+    assert_eq!(page_bounds(&page), crop_box);
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use inkrender::page_warnings;
+use common::open_fixture;
+
+#[test]
+fn test_page_warnings() {
+    let file = open_fixture("page_warnings.pdf");
+    let resolver = file.resolver();
+
+    // /F1 embeds garbage, /F2 is not defined and /Im1 is JPEG 2000
+    let page = file.get_page(0).unwrap();
+    let warnings = page_warnings(&page, &resolver);
+    assert_eq!(warnings.len(), 3, "{:?}", warnings);
+    assert!(warnings[0].starts_with("font /F1 (Broken) can't be parsed"), "{:?}", warnings);
+    assert_eq!(warnings[1], "font /F2 is not in the resources");
+    assert!(warnings[2].contains("/Im1") && warnings[2].contains("JPXDecode"), "{:?}", warnings);
+
+    // a standard font that is not embedded is fine
+    let page = file.get_page(1).unwrap();
+    assert!(page_warnings(&page, &resolver).is_empty());
+}
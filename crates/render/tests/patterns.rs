@@ -0,0 +1,91 @@
+mod common;
+
+use inkrender::tracer::DrawItem;
+use inkrender::{render_page, Cache, Fill, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::{Vector2F, Vector2I};
+use pathfinder_renderer::scene::DrawPathId;
+use common::{open_fixture, trace};
+
+const SCALE: f32 = 25.4 / 72.0;
+
+#[test]
+fn test_tiling_pattern() {
+    let items = trace(&open_fixture("tiling_pattern.pdf"), 0);
+
+    // [0 0 100 100] (in pt, on a 200 pt page) is filled with a red 10 pt square every 20 pt
+    let fills: Vec<(RectF, Fill)> = items
+        .iter()
+        .filter_map(|item| match item {
+            DrawItem::Vector(path) => Some((path.transform * path.outline.bounds(), path.fill.as_ref()?.color)),
+            _ => None,
+        })
+        .collect();
+    assert!(fills.iter().all(|&(_, color)| color == Fill::Solid(1.0, 0.0, 0.0)), "{:?}", fills);
+
+    let area = RectF::from_points(Vector2F::new(0.0, 100.0) * SCALE, Vector2F::new(100.0, 200.0) * SCALE);
+    let inside: Vec<RectF> = fills
+        .iter()
+        .map(|&(rect, _)| rect)
+        .filter(|rect| {
+            rect.min_x() > area.min_x() - 0.01 && rect.max_x() < area.max_x() + 0.01
+                && rect.min_y() > area.min_y() - 0.01 && rect.max_y() < area.max_y() + 0.01
+        })
+        .collect();
+    assert_eq!(inside.len(), 25, "{:?}", fills);
+    for rect in &inside {
+        assert!((rect.width() - 10.0 * SCALE).abs() < 0.01, "{:?}", rect);
+        assert!((rect.height() - 10.0 * SCALE).abs() < 0.01, "{:?}", rect);
+        // one cell per step
+        let cell = rect.origin() / (20.0 * SCALE);
+        assert!((cell.x() - cell.x().round()).abs() < 1e-3, "{:?}", rect);
+        assert!(((cell.y() + 0.5) - (cell.y() + 0.5).round()).abs() < 1e-3, "{:?}", rect);
+    }
+}
+
+#[test]
+fn test_tiling_pattern_offscreen() {
+    let file = open_fixture("tiling_pattern.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    // in pt, a pixel per unit of the pattern
+    render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+    let bounds = backend.content_bounds().unwrap();
+    let scene = backend.finish();
+
+    // the background, the red square in the cell and the area filled with the cell
+    assert_eq!(scene.draw_path_count(), 3);
+    let cell = scene.get_paint(scene.get_draw_path(DrawPathId(1)).paint());
+    assert_eq!(cell.base_color().r, 255);
+    let fill = scene.get_draw_path(DrawPathId(2));
+    let pattern = scene.get_paint(fill.paint()).pattern().unwrap();
+    assert_eq!(pattern.size(), Vector2I::splat(20));
+    assert!(pattern.repeat_x() && pattern.repeat_y());
+    assert!((fill.outline().bounds().size() - Vector2F::splat(100.0)).length() < 0.01, "{:?}", fill.outline().bounds());
+
+    // the cell is drawn elsewhere, only the filled area counts
+    assert!((bounds.size() - Vector2F::splat(100.0)).length() < 0.01, "{:?}", bounds);
+}
+
+#[test]
+fn test_shading_extend() {
+    let file = open_fixture("shading_extend.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+    let scene = backend.finish();
+
+    // the page is filled with an axial shading from x = 50 to 150 that is not extended
+    assert_eq!(scene.draw_path_count(), 2);
+    let bounds = scene.get_draw_path(DrawPathId(1)).outline().bounds();
+    assert!((bounds.min_x() - 50.0).abs() < 0.01, "{:?}", bounds);
+    assert!((bounds.max_x() - 150.0).abs() < 0.01, "{:?}", bounds);
+    assert!((bounds.height() - 200.0).abs() < 0.01, "{:?}", bounds);
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use inkrender::{render_file_pages, Cache};
+use pathfinder_geometry::transform2d::Transform2F;
+use common::open_fixture;
+
+#[test]
+fn test_render_file_pages() {
+    let file = open_fixture("two_pages.pdf");
+    let mut cache = Cache::new();
+
+    // 200x200 and 300x200 pt, at 2 px per mm
+    let scenes: Vec<_> = render_file_pages(&file, &mut cache, 0..10, |_| Transform2F::from_scale(2.0))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(scenes.len(), 2);
+
+    let mm = 2.0 * 25.4 / 72.0;
+    for (scene, width) in scenes.iter().zip([200.0, 300.0]) {
+        assert!(scene.bounds().width() > 0.0, "{:?}", scene.bounds());
+        assert!((scene.view_box().width() - width * mm).abs() < 0.1);
+    }
+}
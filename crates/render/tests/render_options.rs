@@ -0,0 +1,73 @@
+mod common;
+
+use inkrender::RenderOptions;
+use pathfinder_geometry::{transform2d::Transform2F, vector::Vector2F};
+use pathfinder_renderer::options::RenderTransform;
+
+#[test]
+fn test_build_options() {
+    let transform = Transform2F::from_scale(2.0);
+
+    let options = RenderOptions::default().build_options(transform);
+    assert!(!options.subpixel_aa_enabled);
+    assert_eq!(options.dilation, Vector2F::zero());
+
+    let custom = RenderOptions { subpixel_aa: true, dilation: Vector2F::splat(0.5), ..RenderOptions::default() };
+    let options = custom.build_options(transform);
+    assert!(options.subpixel_aa_enabled);
+    assert_eq!(options.dilation, Vector2F::splat(0.5));
+    match options.transform {
+        RenderTransform::Transform2D(t) => assert_eq!(t, transform),
+        _ => panic!("expected a 2D transform"),
+    }
+}
+
+#[test]
+fn test_flatness() {
+    use inkrender::{render_page, Cache, SceneBackend};
+    use pathfinder_renderer::scene::DrawPathId;
+    use crate::common::open_fixture;
+
+    let file = open_fixture("curves.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+    let mut cache = Cache::new();
+
+    // 25 circles of four curves each, at 4 px per pt. Returns the number of points and paths.
+    let mut render = |flatness: f32| {
+        let mut backend = SceneBackend::new(&mut cache);
+        backend.set_render_options(RenderOptions { flatness, ..RenderOptions::default() });
+        render_page(&mut backend, &resolver, &page, Transform2F::from_scale(4.0 * 72.0 / 25.4)).unwrap();
+        let bounds = backend.content_bounds().unwrap();
+        let scene = backend.finish();
+        let points: usize = (0..scene.draw_path_count())
+            .map(|i| scene.get_draw_path(DrawPathId(i)).outline().contours().iter().map(|c| c.len() as usize).sum::<usize>())
+            .sum();
+        (points, scene.draw_path_count(), bounds)
+    };
+
+    let (fine, paths, bounds) = render(0.05);
+    let (coarse, coarse_paths, coarse_bounds) = render(2.0);
+    assert!(coarse < fine, "{} points with a tolerance of 2 px, {} with 0.05 px", coarse, fine);
+    // the same circles, just less round
+    assert_eq!(coarse_paths, paths);
+    assert!((coarse_bounds.origin() - bounds.origin()).length() < 2.0);
+    assert!((coarse_bounds.size() - bounds.size()).length() < 4.0);
+
+    // by default the curves are kept: 1 + 4 * 3 points per circle, plus the page background
+    let (curves, ..) = render(0.0);
+    assert_eq!(curves, 25 * 13 + 4);
+}
+
+#[test]
+fn test_clear_mode() {
+    use inkrender::ClearMode;
+    use pathfinder_color::ColorF;
+
+    assert_eq!(ClearMode::from(None), ClearMode::OpaqueWhite);
+    assert_eq!(ClearMode::OpaqueWhite.color(), ColorF::white());
+    assert_eq!(ClearMode::Transparent.color().a(), 0.0);
+    let red = ColorF::new(1.0, 0.0, 0.0, 1.0);
+    assert_eq!(ClearMode::from(Some(red)), ClearMode::Color(red));
+    assert_eq!(ClearMode::Color(red).background_color(), Some(red));
+}
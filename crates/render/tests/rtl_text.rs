@@ -0,0 +1,31 @@
+mod common;
+
+use common::{open_fixture, trace_text};
+
+#[test]
+fn test_negative_tj_advance() {
+    let file = open_fixture("rtl_text.pdf");
+    let spans = trace_text(&file, 0);
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.text, "ABC");
+
+    // 10 Tf, all glyphs 600 units wide: each glyph advances by 6,
+    // 1200 in TJ moves back by 12, so every glyph lands 6 left of the previous one
+    let expected = [0.0, -6.0, -12.0];
+    let pos: Vec<f32> = span.chars.iter().map(|c| c.pos).collect();
+    assert_eq!(pos.len(), expected.len());
+    for (&p, &e) in pos.iter().zip(expected.iter()) {
+        assert!((p - e).abs() < 1e-3, "{:?} != {:?}", pos, expected);
+    }
+    assert!(span.is_rtl());
+    assert!((span.width + 6.0).abs() < 1e-3);
+
+    // the rect covers C (at -12) up to the end of A (at 6)
+    let (min, max) = span.extent;
+    assert!((min + 12.0).abs() < 1e-3 && (max - 6.0).abs() < 1e-3, "{:?}", span.extent);
+    assert!((span.rect.width() - 18.0).abs() < 1e-3, "{:?}", span.rect);
+
+    let visual: String = span.rparts().map(|p| p.text).collect();
+    assert_eq!(visual, "CBA");
+}
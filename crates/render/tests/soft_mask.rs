@@ -0,0 +1,81 @@
+mod common;
+
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_content::effects::BlendMode;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_renderer::paint::{Paint, PaintContents};
+use pathfinder_renderer::scene::{DrawPathId, Scene};
+use common::open_fixture;
+
+fn render(name: &str) -> Scene {
+    let file = open_fixture(name);
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    backend.finish()
+}
+
+/// (paint, blend mode) of the paths drawn after the page background
+fn paths(scene: &Scene) -> Vec<(&Paint, BlendMode)> {
+    (1..scene.draw_path_count())
+        .map(|i| {
+            let path = scene.get_draw_path(DrawPathId(i));
+            (scene.get_paint(path.paint()), path.blend_mode())
+        })
+        .collect()
+}
+
+fn alpha(paint: &Paint) -> f32 {
+    paint.base_color().a as f32 / 255.0
+}
+
+#[test]
+fn test_luminosity_mask() {
+    let scene = render("soft_mask.pdf");
+    let paths = paths(&scene);
+
+    // the mask has stripes of gray 1, 0.75, 0.5 and 0 from left to right, each taking
+    // away what is below it and adding its luminosity as alpha
+    let (mask, rest) = paths.split_at(8);
+    for (pair, expected) in mask.chunks(2).zip([1.0, 0.75, 0.5, 0.0]) {
+        assert_eq!(pair[0].1, BlendMode::DestOut);
+        assert_eq!(alpha(pair[0].0), 1.0);
+        assert_eq!(pair[1].1, BlendMode::Lighter);
+        assert!((alpha(pair[1].0) - expected).abs() < 0.01, "{:?}", alpha(pair[1].0));
+    }
+
+    // the red page is drawn offscreen, cut down to the mask and composited
+    assert_eq!(rest.len(), 3);
+    assert_eq!(rest[0].0.base_color().r, 255);
+    assert!(rest[0].0.pattern().is_none());
+    assert_eq!(rest[1].1, BlendMode::DestIn);
+    assert!(rest[1].0.pattern().is_some());
+    assert!(rest[2].0.pattern().is_some());
+}
+
+#[test]
+fn test_luminosity_mask_shading() {
+    let scene = render("soft_mask_shading.pdf");
+    let paths = paths(&scene);
+
+    // the mask group is an axial shading from white on the left to black on the right,
+    // its luminosity is drawn as a gradient of alpha
+    assert_eq!(paths[0].1, BlendMode::DestOut);
+    assert_eq!(paths[1].1, BlendMode::Lighter);
+    let gradient = match paths[1].0.overlay().map(|overlay| overlay.contents()) {
+        Some(PaintContents::Gradient(gradient)) => gradient,
+        _ => panic!("the mask is not a gradient"),
+    };
+    let stops = gradient.stops();
+    assert!(stops.iter().all(|stop| stop.color.r == 0 && stop.color.g == 0 && stop.color.b == 0));
+    assert!(stops.first().unwrap().color.a > 250, "{:?}", stops);
+    assert!(stops.last().unwrap().color.a < 5, "{:?}", stops);
+    assert!(stops.windows(2).all(|w| w[0].color.a >= w[1].color.a), "{:?}", stops);
+
+    // the rect under the mask
+    assert_eq!(paths.len(), 5);
+    assert_eq!(paths[3].1, BlendMode::DestIn);
+}
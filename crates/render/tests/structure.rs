@@ -0,0 +1,42 @@
+mod common;
+
+use inkrender::{render_page, Cache, SceneBackend, StructNode};
+use pathfinder_geometry::transform2d::Transform2F;
+use pdf::object::PlainRef;
+use common::open_fixture;
+
+#[test]
+fn test_structure_tree() {
+    let file = open_fixture("tagged.pdf");
+    let resolver = file.resolver();
+
+    let root = StructNode::load(file.trailer.root.get_ref().get_inner(), &resolver).unwrap().unwrap();
+    assert_eq!(root.kind, "StructTreeRoot");
+    let kinds = |node: &StructNode| node.children.iter().map(|c| c.kind.clone()).collect::<Vec<_>>();
+    assert_eq!(kinds(&root), ["Document"]);
+    let document = &root.children[0];
+    // /Heading1 is role mapped to H1
+    assert_eq!(kinds(document), ["H1", "P", "L"]);
+    let all: Vec<_> = root.iter().map(|n| n.kind.as_str()).collect();
+    assert_eq!(all, ["StructTreeRoot", "Document", "H1", "P", "L", "LI", "LBody"]);
+
+    // the page is object 3, the list body inherits it from the list
+    let page_ref = PlainRef { id: 3, gen: 0 };
+    let body = root.iter().find(|n| n.kind == "LBody").unwrap();
+    assert_eq!(body.alt.as_deref(), Some("first item"));
+    assert_eq!(body.content[0].page, Some(page_ref));
+
+    let page = file.get_page(0).unwrap();
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    backend.collect_text();
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let (_, spans) = backend.finish_with_text();
+    let text = |node: &StructNode| node.spans(page_ref, &spans).map(|s| s.text.as_str()).collect::<String>();
+
+    assert_eq!(text(&document.children[0]), "Title");
+    assert_eq!(text(&document.children[1]), "Some text.");
+    assert_eq!(text(body), "Item");
+    // untagged content is in no element
+    assert!(spans.iter().any(|s| s.text == "Header" && s.mcid.is_none()));
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use common::{open_fixture, trace_text};
+
+#[test]
+fn test_tj_spacing() {
+    let file = open_fixture("text_spacing.pdf");
+    let spans = trace_text(&file, 0);
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span.text, "ABC D");
+
+    // 10 Tf 2 Tc 3 Tw 50 Tz, all glyphs 600 units wide:
+    // glyph advance = (0.6 * 10 + 2) * 0.5 = 4
+    // space advance = (0.6 * 10 + 2 + 3) * 0.5 = 5.5
+    // -500 in TJ = 0.5 * 10 * 0.5 = 2.5
+    let expected = [0.0, 4.0, 10.5, 14.5, 20.0];
+    let pos: Vec<f32> = span.chars.iter().map(|c| c.pos).collect();
+    assert_eq!(pos.len(), expected.len());
+    for (&p, &e) in pos.iter().zip(expected.iter()) {
+        assert!((p - e).abs() < 1e-3, "{:?} != {:?}", pos, expected);
+    }
+    assert!((span.width - 24.0).abs() < 1e-3);
+    for c in &span.chars {
+        assert!((c.width - 3.0).abs() < 1e-3);
+    }
+}
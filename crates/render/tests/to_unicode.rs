@@ -0,0 +1,16 @@
+mod common;
+
+use common::{open_fixture, trace_text};
+
+#[test]
+fn test_glyph_to_unicode() {
+    let file = open_fixture("to_unicode.pdf");
+    let span = trace_text(&file, 0).into_iter().next().unwrap();
+    // the ToUnicode CMap maps f to the fi ligature
+    assert_eq!(span.text, "Afi");
+
+    let font = span.font.as_ref().unwrap();
+    let gid = |code: u16| font.cmap[&code].0;
+    assert_eq!(font.glyph_to_unicode(gid(0x41)).as_deref(), Some("A"));
+    assert_eq!(font.glyph_to_unicode(gid(0x66)).as_deref(), Some("fi"));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use inkrender::{render_page, Cache, RenderOptions, SceneBackend};
+use pathfinder_content::effects::BlendMode;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_renderer::scene::{DrawPathId, Scene};
+use common::open_fixture;
+
+/// (opacity, is the group, blend mode) of the paths drawn after the page background
+fn paths(scene: &Scene) -> Vec<(f32, bool, BlendMode)> {
+    (1..scene.draw_path_count())
+        .map(|i| {
+            let path = scene.get_draw_path(DrawPathId(i));
+            let paint = scene.get_paint(path.paint());
+            let opacity = (paint.base_color().a as f32 / 25.5).round() / 10.0;
+            (opacity, paint.pattern().is_some(), path.blend_mode())
+        })
+        .collect()
+}
+
+#[test]
+fn test_transparency_groups() {
+    let file = open_fixture("transparency_group.pdf");
+    let resolver = file.resolver();
+    let mut cache = Cache::new();
+
+    let mut render = |nr: u32| {
+        let page = file.get_page(nr).unwrap();
+        let mut backend = SceneBackend::new(&mut cache);
+        render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+        backend.finish()
+    };
+
+    // two overlapping opaque squares in a form, drawn with /ca 0.5
+    // what the scene uses for normal compositing
+    let normal = BlendMode::Overlay;
+
+    // isolated: the squares are drawn opaque offscreen, and the group at half opacity,
+    // so the overlap is as light as the rest
+    let isolated = paths(&render(0));
+    assert_eq!(isolated, vec![(1.0, false, normal), (1.0, false, normal), (0.5, true, normal)]);
+
+    // a plain form: each square at half opacity, the overlap is darker
+    let plain = paths(&render(1));
+    assert_eq!(plain, vec![(0.5, false, normal), (0.5, false, normal)]);
+
+    // knockout: the second square replaces the first one where they overlap
+    let knockout = paths(&render(2));
+    assert_eq!(knockout, vec![(1.0, false, BlendMode::Copy), (1.0, false, BlendMode::Copy), (0.5, true, normal)]);
+}
+
+#[test]
+fn test_transparency_group_targets() {
+    let file = open_fixture("transparency_group.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    // built for twice the size: the target has a pixel per output pixel
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    backend.set_render_options(RenderOptions { resolution: 2.0, ..RenderOptions::default() });
+    render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+    let scene = backend.finish();
+    let view_box = scene.view_box();
+    let group = scene.get_draw_path(DrawPathId(scene.draw_path_count() - 1));
+    let pattern = scene.get_paint(group.paint()).pattern().unwrap();
+    assert_eq!(pattern.size(), (view_box.size() * 2.0).ceil().to_i32());
+    assert_eq!(pattern.transform(), Transform2F::from_translation(view_box.origin()) * Transform2F::from_scale(0.5));
+
+    // without render targets the group is drawn in place, each square at its opacity
+    cache.set_offscreen(false);
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::from_scale(72.0 / 25.4)).unwrap();
+    let normal = BlendMode::Overlay;
+    assert_eq!(paths(&backend.finish()), vec![(0.5, false, normal), (0.5, false, normal)]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_content::fill::FillRule;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_renderer::scene::DrawPathId;
+use common::open_fixture;
+
+#[test]
+fn test_type3_glyphs() {
+    let file = open_fixture("type3.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let scene = backend.finish();
+
+    // the page background and one path per glyph
+    assert_eq!(scene.draw_path_count(), 3);
+    let frame = scene.get_draw_path(DrawPathId(1));
+    let clipped = scene.get_draw_path(DrawPathId(2));
+
+    // /a is a 750 x 750 square with a hole at 100 pt, the font matrix scales it to 75 pt
+    let pt = 25.4 / 72.;
+    let expected = RectF::new(Vector2F::new(100.0, 200.0 - 175.0), Vector2F::splat(75.0)) * pt;
+    let bounds = frame.outline().bounds();
+    assert!((bounds.origin() - expected.origin()).length() < 0.01, "{:?}", bounds);
+    assert!((bounds.size() - expected.size()).length() < 0.01, "{:?}", bounds);
+    assert_eq!(frame.outline().contours().len(), 2);
+    // the hole is only there with the even-odd rule of its f*
+    assert_eq!(frame.fill_rule(), FillRule::EvenOdd);
+    assert!(frame.clip_path().is_none());
+
+    // /b fills the square inside a clip path, one glyph advance further
+    let bounds = clipped.outline().bounds();
+    assert!((bounds.origin() - (expected.origin() + Vector2F::new(100.0 * pt, 0.0))).length() < 0.01, "{:?}", bounds);
+    assert_eq!(clipped.fill_rule(), FillRule::Winding);
+    assert!(clipped.clip_path().is_some());
+}
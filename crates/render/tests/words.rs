@@ -0,0 +1,42 @@
+mod common;
+
+use inkrender::{page_lines, page_words, render_page, Cache, SceneBackend};
+use pathfinder_geometry::transform2d::Transform2F;
+use common::open_fixture;
+
+const SCALE: f32 = 25.4 / 72.0;
+
+#[test]
+fn test_page_words() {
+    let file = open_fixture("words.pdf");
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    backend.collect_text();
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let (_, spans) = backend.finish_with_text();
+
+    // "The" and "quick" are 3 pt apart without a space, "bro" and "wn" are kerned by 0.2 pt
+    let words = page_words(&spans);
+    let texts: Vec<_> = words.iter().map(|w| w.text.as_str()).collect();
+    assert_eq!(texts, ["The", "quick", "brown", "fox", "jumps", "over"]);
+
+    // 10 pt Courier: every glyph is 6 pt wide, the line starts at x = 100 pt
+    let the = words[0].rect;
+    assert!((the.min_x() - 100.0 * SCALE).abs() < 0.1, "{:?}", the);
+    assert!((the.width() - 18.0 * SCALE).abs() < 0.1, "{:?}", the);
+    let quick = words[1].rect;
+    assert!((quick.min_x() - 121.0 * SCALE).abs() < 0.1, "{:?}", quick);
+    assert!((quick.width() - 30.0 * SCALE).abs() < 0.1, "{:?}", quick);
+    // "jumps" is 20 pt below
+    assert!((words[4].rect.min_y() - words[0].rect.min_y() - 20.0 * SCALE).abs() < 0.1);
+
+    let lines = page_lines(&words);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].text, "The quick brown fox");
+    assert_eq!(lines[1].text, "jumps over");
+    assert_eq!(lines[0].words.len(), 4);
+    assert!(lines[0].rect.contains_rect(quick));
+}
@@ -1,15 +1,71 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
+use pathfinder_color::ColorF;
 use pathfinder_geometry::rect::RectF;
 use pathfinder_geometry::transform2d::Transform2F;
 use pathfinder_geometry::vector::{Vector2F, Vector2I};
 
+use pathfinder_renderer::scene::Scene;
+
 use crate::config::{Config, Icon};
+use crate::links::{link_at, tooltip_at, Link, LinkTarget, Tooltip};
+use crate::selection::{runs_text, Selection, SelectionMode, TextRun};
+use crate::types::ViewError;
 
 pub trait ViewBackend {
     fn resize(&mut self, size: Vector2F);
     fn get_scroll_factors(&self) -> (Vector2F, Vector2F);
     fn set_icon(&mut self, icon: Icon);
+    /// Open an external link, e.g. in the browser.
+    fn open_uri(&mut self, uri: &str);
+    /// Show `tooltip` at the cursor, or hide the tooltip with `None`.
+    fn set_tooltip(&mut self, tooltip: Option<String>);
+    /// Show only the page, without window decorations and toolbars, see `Context::set_presentation_mode`.
+    fn set_fullscreen(&mut self, fullscreen: bool);
+    /// Put `text` on the system clipboard, see `Context::copy_selection`.
+    fn set_clipboard(&mut self, text: &str);
+}
+
+/// The part of the view that external UI (toolbars, page counters) shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewState {
+    pub page_nr: usize,
+    pub num_pages: usize,
+    pub scale: f32,
+    pub view_center: Vector2F,
+}
+
+/// Unit of a wheel delta, see `Context::scroll`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollUnit {
+    Pixels,
+    Lines,
+}
+
+/// How the zoom is chosen when the first page is loaded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialFit {
+    /// The page width fills the window.
+    Width,
+    /// The whole page is visible.
+    Page,
+    /// A fixed scale, e.g. `DEFAULT_SCALE`.
+    Scale(f32),
+}
+
+// what a cached scene was built for
+#[derive(Clone, Copy, PartialEq)]
+struct SceneKey {
+    view: ViewState,
+    window_size: Vector2F,
+    scale_factor: f32,
+}
+
+impl Default for InitialFit {
+    fn default() -> Self {
+        InitialFit::Width
+    }
 }
 
 pub struct Context<B: ViewBackend> {
@@ -19,18 +75,53 @@ pub struct Context<B: ViewBackend> {
     pub num_pages: usize,
     pub scale: f32, // device independent
     pub view_center: Vector2F,
-    pub window_size: Vector2F, // in pixels
-    pub scale_factor: f32, // device dependent
+    pub window_size: Vector2F, // in logical (CSS) pixels
+    pub scale_factor: f32, // device pixels per logical pixel
     pub config: Rc<Config>,
     pub bounds: Option<RectF>,
     pub close: bool,
     pub update_interval: Option<f32>,
     pub pixel_scroll_factor: Vector2F,
     pub line_scroll_factor: Vector2F,
+    pub text_runs: Vec<TextRun>,
+    pub selection: Option<Selection>,
+    pub links: Vec<Link>,
+    pub tooltips: Vec<Tooltip>,
+    // drawn area of the current page, see `fit_content`
+    pub content_bounds: Option<RectF>,
     pub backend: B,
+    view_observers: Vec<Box<dyn FnMut(&ViewState)>>,
+    // last state the observers have seen
+    view_state: Option<ViewState>,
+    initial_fit: InitialFit,
+    // "natural" scrolling, the content follows the fingers
+    scroll_inverted: bool,
+    // zoom and position of visited pages, if enabled
+    page_views: Option<HashMap<usize, (f32, Vector2F)>>,
+    // the initial fit has been applied
+    fitted: bool,
+    // the last scene, see `cached_scene`
+    scene_cache: Option<(SceneKey, Scene)>,
+    // what the backend shows, see `hover`
+    tooltip: Option<String>,
+    // zoom and position before presentation mode
+    presentation: Option<(f32, Vector2F)>,
+    // space between pages in multi-page layouts, in logical pixels
+    page_gap: f32,
+    // behind and between the pages
+    canvas_color: ColorF,
+    // throttle for `redraw_delay`, in seconds
+    min_frame_interval: Option<f32>,
+    // time of the last frame, see `mark_drawn_at`
+    last_frame: Option<f64>,
 }
 
 pub const DEFAULT_SCALE: f32 = 96.0 / 25.4;
+/// zoom limits for interactive zooming, relative to `DEFAULT_SCALE`
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 16.0;
+/// space between pages in logical pixels, see `Context::set_page_gap`
+pub const DEFAULT_PAGE_GAP: f32 = 16.0;
 
 impl<B: ViewBackend> Context<B> {
     pub fn new(config: Rc<Config>, backend: B) -> Self {
@@ -49,22 +140,197 @@ impl<B: ViewBackend> Context<B> {
             update_interval: None,
             pixel_scroll_factor,
             line_scroll_factor,
+            text_runs: Vec::new(),
+            selection: None,
+            links: Vec::new(),
+            tooltips: Vec::new(),
+            content_bounds: None,
             backend,
+            view_observers: Vec::new(),
+            view_state: None,
+            initial_fit: InitialFit::default(),
+            scroll_inverted: false,
+            page_views: None,
+            fitted: false,
+            scene_cache: None,
+            tooltip: None,
+            presentation: None,
+            page_gap: DEFAULT_PAGE_GAP,
+            canvas_color: ColorF::new(0.95, 0.95, 0.95, 1.0),
+            min_frame_interval: None,
+            last_frame: None,
         }
     }
 
+    /// Ask for a new frame. Requests until the next frame are merged into one, and the
+    /// frame reuses the last scene if the view did not change (see `cached_scene`).
     pub fn request_redraw(&mut self) {
         self.redraw_requested = true;
+        self.notify_view_change();
+    }
+
+    /// A frame was requested and has not been drawn yet, see `mark_drawn`.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw_requested
+    }
+
+    /// The requested frame was drawn, `needs_redraw` is false until the next request.
+    pub fn mark_drawn(&mut self) {
+        self.redraw_requested = false;
+    }
+
+    /// Like `mark_drawn`, and start the minimum frame interval at `time` (in seconds,
+    /// from any fixed point like the start of the app).
+    pub fn mark_drawn_at(&mut self, time: f64) {
+        self.mark_drawn();
+        self.last_frame = Some(time);
+    }
+
+    /// Draw at most one frame per `interval` seconds, e.g. `Some(1.0 / 60.0)`,
+    /// so a burst of zoom events does not draw a frame for each. `None` by default.
+    pub fn set_min_frame_interval(&mut self, interval: Option<f32>) {
+        self.min_frame_interval = interval;
+    }
+
+    pub fn min_frame_interval(&self) -> Option<f32> {
+        self.min_frame_interval
+    }
+
+    /// Seconds at `time` until the requested frame should be drawn, 0 if now,
+    /// `None` if no frame is needed. The interval starts with `mark_drawn_at`.
+    pub fn redraw_delay(&self, time: f64) -> Option<f32> {
+        if !self.needs_redraw() {
+            return None;
+        }
+        match (self.min_frame_interval, self.last_frame) {
+            (Some(interval), Some(last)) => Some((last + interval as f64 - time).max(0.0) as f32),
+            _ => Some(0.0),
+        }
+    }
+
+    /// Drop the cached scene and ask for a new frame.
+    ///
+    /// For changes of what is drawn that `ViewState` does not cover, e.g. a new document.
+    pub fn invalidate_scene(&mut self) {
+        self.scene_cache = None;
+        self.request_redraw();
+    }
+
+    /// The scene built by `build`, or the last one if the view has not changed since.
+    ///
+    /// Changes of the `ViewState`, window size, scale factor and selection are noticed,
+    /// anything else needs `invalidate_scene`.
+    pub fn cached_scene(&mut self, build: impl FnOnce(&mut Self) -> Scene) -> Scene {
+        self.try_cached_scene(|ctx| Ok(build(ctx))).unwrap()
+    }
+
+    /// Like `cached_scene`, errors are not cached.
+    pub fn try_cached_scene(&mut self, build: impl FnOnce(&mut Self) -> Result<Scene, ViewError>) -> Result<Scene, ViewError> {
+        let key = self.scene_key();
+        if let Some((cached, ref scene)) = self.scene_cache {
+            if cached == key {
+                return Ok(scene.clone());
+            }
+        }
+        let scene = build(self)?;
+        // the key from before: if `build` moved the view, the next frame builds again
+        self.scene_cache = Some((key, scene.clone()));
+        Ok(scene)
+    }
+
+    fn scene_key(&self) -> SceneKey {
+        SceneKey {
+            view: self.view_state(),
+            window_size: self.window_size,
+            scale_factor: self.scale_factor,
+        }
+    }
+
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            page_nr: self.page_nr,
+            num_pages: self.num_pages,
+            scale: self.scale,
+            view_center: self.view_center,
+        }
+    }
+
+    /// Call `observer` whenever the page, zoom or position changes,
+    /// no matter if by the app or by user input handled here.
+    pub fn on_view_change(&mut self, observer: Box<dyn FnMut(&ViewState)>) {
+        self.view_state = Some(self.view_state());
+        self.view_observers.push(observer);
+    }
+
+    fn notify_view_change(&mut self) {
+        let state = self.view_state();
+        if self.view_state == Some(state) {
+            return;
+        }
+        self.view_state = Some(state);
+        for observer in self.view_observers.iter_mut() {
+            observer(&state);
+        }
+    }
+
+    /// Remember the zoom and position of each page and restore them when the page is
+    /// visited again. By default the current view is kept when changing pages.
+    pub fn set_per_page_view_memory(&mut self, enabled: bool) {
+        match (enabled, &self.page_views) {
+            (true, None) => self.page_views = Some(HashMap::new()),
+            (false, _) => self.page_views = None,
+            _ => {}
+        }
     }
 
     pub fn goto_page(&mut self, page: usize) {
         let page = page.min(self.num_pages - 1);
         if page != self.page_nr {
+            if let Some(ref mut views) = self.page_views {
+                views.insert(self.page_nr, (self.scale, self.view_center));
+                if let Some(&(scale, center)) = views.get(&page) {
+                    self.scale = scale;
+                    self.view_center = center;
+                }
+            }
             self.page_nr = page;
+            self.clear_page_state();
             self.request_redraw();
         }
     }
 
+    // text, links and tooltips of the current page
+    fn clear_page_state(&mut self) {
+        self.text_runs.clear();
+        self.selection = None;
+        self.links.clear();
+        self.tooltips.clear();
+        self.show_tooltip(None);
+        self.content_bounds = None;
+    }
+
+    /// A document with `num_pages` pages replaces the current one.
+    ///
+    /// Goes to the first page and forgets the views of visited pages, the initial fit
+    /// applies again once the bounds of the new page are set.
+    pub fn load_document(&mut self, num_pages: usize) {
+        self.num_pages = num_pages.max(1);
+        self.page_nr = 0;
+        self.clear_page_state();
+        if let Some(ref mut views) = self.page_views {
+            views.clear();
+        }
+        self.bounds = None;
+        self.fitted = false;
+        self.invalidate_scene();
+    }
+
+    /// The current document is closed: drop what was read from its page.
+    pub fn unload_document(&mut self) {
+        self.clear_page_state();
+        self.invalidate_scene();
+    }
+
     pub fn next_page(&mut self) {
         self.goto_page(self.page_nr.saturating_add(1));
     }
@@ -91,6 +357,154 @@ impl<B: ViewBackend> Context<B> {
         }
     }
 
+    /// Zoom to `scale` (clamped to the zoom limits), keeping the point under
+    /// `center` (in logical window pixels) in place.
+    pub fn zoom_at(&mut self, scale: f32, center: Vector2F) {
+        let scale = scale.clamp(MIN_ZOOM * DEFAULT_SCALE, MAX_ZOOM * DEFAULT_SCALE);
+        if scale == self.scale {
+            return;
+        }
+        let offset = center - self.window_size * 0.5;
+        let point = self.view_center + offset * (1.0 / self.scale);
+        self.scale = scale;
+        self.view_center = point - offset * (1.0 / scale);
+        self.check_bounds();
+        self.request_redraw();
+    }
+
+    /// Zoom and scroll so that `page_rect` (in page space) fills the window,
+    /// e.g. for "zoom to selection" or to show a search hit.
+    pub fn zoom_to_rect(&mut self, page_rect: RectF) {
+        self.zoom_to_rect_with_margin(page_rect, 0.0);
+    }
+
+    /// Like `zoom_to_rect`, keeping `margin` logical pixels free on each side of the rect.
+    ///
+    /// The scale is clamped to the zoom limits and the view to the page bounds,
+    /// so the rect can end up smaller than the window or off center.
+    pub fn zoom_to_rect_with_margin(&mut self, page_rect: RectF, margin: f32) {
+        let avail = self.window_size - Vector2F::splat(2.0 * margin);
+        if avail.x() <= 0.0 || avail.y() <= 0.0 || page_rect.width() <= 0.0 || page_rect.height() <= 0.0 {
+            return;
+        }
+        let scale = (avail.x() / page_rect.width()).min(avail.y() / page_rect.height());
+        self.scale = scale.clamp(MIN_ZOOM * DEFAULT_SCALE, MAX_ZOOM * DEFAULT_SCALE);
+        self.view_center = page_rect.center();
+        self.check_bounds();
+        self.request_redraw();
+    }
+
+    /// Set the bounding box of the drawn content of the current page (in page space),
+    /// e.g. `SceneBackend::content_bounds` of a page rendered with `Transform2F::default()`.
+    pub fn set_content_bounds(&mut self, bounds: Option<RectF>) {
+        self.content_bounds = bounds;
+    }
+
+    /// Zoom to the drawn content of the page, leaving out empty margins.
+    ///
+    /// Fits the whole page if the content bounds are unknown or have no area.
+    pub fn fit_content(&mut self) {
+        match self.content_bounds {
+            Some(rect) if rect.width() > 0.0 && rect.height() > 0.0 => self.zoom_to_rect(rect),
+            _ => {
+                if let Some(scale) = self.fit_scale(InitialFit::Page) {
+                    self.scale = scale;
+                    self.check_bounds();
+                    self.request_redraw();
+                }
+            }
+        }
+    }
+
+    /// Show one page at a time, fit into a fullscreen window without chrome (see
+    /// `ViewBackend::set_fullscreen`), and advance with the keys of `presentation_key`.
+    ///
+    /// Leaving it restores the zoom and position from before.
+    pub fn set_presentation_mode(&mut self, enabled: bool) {
+        if enabled == self.presentation.is_some() {
+            return;
+        }
+        if enabled {
+            self.presentation = Some((self.scale, self.view_center));
+            self.backend.set_fullscreen(true);
+            self.fit_presentation();
+        } else {
+            let (scale, center) = self.presentation.take().unwrap();
+            self.backend.set_fullscreen(false);
+            self.scale = scale;
+            self.view_center = center;
+        }
+        self.check_bounds();
+        self.request_redraw();
+    }
+
+    pub fn presentation_mode(&self) -> bool {
+        self.presentation.is_some()
+    }
+
+    /// Handle `key` (a `KeyboardEvent.key` or GPUI key name) in presentation mode:
+    /// space, right, down and page down go to the next page, left, up, page up and
+    /// backspace to the previous one, escape leaves presentation mode.
+    ///
+    /// Returns whether the key was used, always false outside of presentation mode.
+    pub fn presentation_key(&mut self, key: &str) -> bool {
+        if self.presentation.is_none() {
+            return false;
+        }
+        match key {
+            " " | "space" | "ArrowRight" | "right" | "ArrowDown" | "down" | "PageDown" | "pagedown" => self.next_page(),
+            "ArrowLeft" | "left" | "ArrowUp" | "up" | "PageUp" | "pageup" | "Backspace" | "backspace" => self.prev_page(),
+            "Escape" | "escape" => self.set_presentation_mode(false),
+            _ => return false,
+        }
+        true
+    }
+
+    // the whole page, centered
+    fn fit_presentation(&mut self) {
+        if let (Some(scale), Some(bounds)) = (self.fit_scale(InitialFit::Page), self.bounds) {
+            self.scale = scale;
+            self.view_center = bounds.center();
+        }
+    }
+
+    /// Choose how the scale is picked once both the page bounds and the window size are known.
+    ///
+    /// Only affects the first page that is loaded, later zoom changes are left alone.
+    pub fn set_initial_fit(&mut self, fit: InitialFit) {
+        self.initial_fit = fit;
+    }
+
+    /// The scale that fits the page width (or the whole page) into the window.
+    pub fn fit_scale(&self, fit: InitialFit) -> Option<f32> {
+        let bounds = self.bounds?;
+        if self.window_size.x() <= 0.0 || self.window_size.y() <= 0.0 || bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            return None;
+        }
+        let sx = self.window_size.x() / bounds.width();
+        let sy = self.window_size.y() / bounds.height();
+        let scale = match fit {
+            InitialFit::Width => sx,
+            InitialFit::Page => sx.min(sy),
+            InitialFit::Scale(scale) => scale,
+        };
+        Some(scale.clamp(MIN_ZOOM * DEFAULT_SCALE, MAX_ZOOM * DEFAULT_SCALE))
+    }
+
+    fn apply_initial_fit(&mut self) {
+        if self.fitted {
+            return;
+        }
+        if let Some(scale) = self.fit_scale(self.initial_fit) {
+            self.fitted = true;
+            self.scale = scale;
+            if let Some(bounds) = self.bounds {
+                // start at the top of the page
+                self.view_center = Vector2F::new(bounds.center().x(), bounds.origin_y());
+            }
+        }
+    }
+
     pub fn close(&mut self) {
         self.close = true;
     }
@@ -99,6 +513,41 @@ impl<B: ViewBackend> Context<B> {
         self.move_to(self.view_center + delta);
     }
 
+    /// Logical pixels per unit of wheel delta. Defaults to `ViewBackend::get_scroll_factors`.
+    pub fn set_scroll_factors(&mut self, pixel: Vector2F, line: Vector2F) {
+        self.pixel_scroll_factor = pixel;
+        self.line_scroll_factor = line;
+    }
+
+    /// Move the content in the direction of the wheel delta instead of the view.
+    pub fn set_scroll_inverted(&mut self, inverted: bool) {
+        self.scroll_inverted = inverted;
+    }
+
+    pub fn scroll_inverted(&self) -> bool {
+        self.scroll_inverted
+    }
+
+    /// Apply a wheel delta. By default positive y moves the view down the page.
+    pub fn scroll(&mut self, delta: Vector2F, unit: ScrollUnit) {
+        let factor = match unit {
+            ScrollUnit::Pixels => self.pixel_scroll_factor,
+            ScrollUnit::Lines => self.line_scroll_factor,
+        };
+        let delta = delta * factor * (1.0 / self.scale);
+        self.move_by(if self.scroll_inverted { -delta } else { delta });
+    }
+
+    /// Scroll by a delta in pixels, as trackpads and precise wheels report it.
+    pub fn scroll_pixels(&mut self, delta: Vector2F) {
+        self.scroll(delta, ScrollUnit::Pixels);
+    }
+
+    /// Scroll by a delta in lines, as classic mouse wheels report it.
+    pub fn scroll_lines(&mut self, delta: Vector2F) {
+        self.scroll(delta, ScrollUnit::Lines);
+    }
+
     pub fn check_bounds(&mut self) {
         if let Some(bounds) = self.bounds {
             let mut point = self.view_center;
@@ -135,33 +584,113 @@ impl<B: ViewBackend> Context<B> {
 
     pub fn set_bounds(&mut self, bounds: RectF) {
         self.bounds = Some(bounds);
+        self.apply_initial_fit();
+        if self.presentation.is_some() {
+            self.fit_presentation();
+        }
         self.check_bounds();
+        self.notify_view_change();
     }
 
+    /// Set the device pixel ratio. The logical zoom and position are unchanged,
+    /// but the page is rendered at the new native resolution.
     pub fn set_scale_factor(&mut self, factor: f32) {
-        self.scale_factor = factor;
-        self.check_bounds();
-        self.request_redraw();
+        if factor != self.scale_factor {
+            self.scale_factor = factor;
+            self.check_bounds();
+            self.request_redraw();
+        }
     }
 
     pub fn window_size(&self) -> Vector2F {
         self.window_size
     }
 
+    /// The size of the window in device pixels, i.e. the size of the rendered scene.
+    pub fn pixel_size(&self) -> Vector2F {
+        self.window_size * self.scale_factor
+    }
+
     pub fn set_window_size(&mut self, size: Vector2F) {
         self.window_size = size;
         self.backend.resize(size);
 
+        self.apply_initial_fit();
         self.check_bounds();
         self.request_redraw();
     }
 
+    /// Transform from page space into device pixels.
     pub fn view_transform(&self) -> Transform2F {
-        Transform2F::from_translation(self.window_size * 0.5)
+        Transform2F::from_scale(self.scale_factor)
+            * Transform2F::from_translation(self.window_size * 0.5)
             * Transform2F::from_scale(self.scale)
             * Transform2F::from_translation(-self.view_center)
     }
 
+    /// Space between pages in continuous and two-page layouts, in logical pixels.
+    ///
+    /// It stays the same when zooming. `DEFAULT_PAGE_GAP` by default.
+    pub fn set_page_gap(&mut self, gap: f32) {
+        self.page_gap = gap.max(0.0);
+        self.invalidate_scene();
+    }
+
+    pub fn page_gap(&self) -> f32 {
+        self.page_gap
+    }
+
+    /// The color around the pages, which the backend clears the window with. Light gray by default.
+    pub fn set_canvas_color(&mut self, color: ColorF) {
+        self.canvas_color = color;
+        self.request_redraw();
+    }
+
+    pub fn canvas_color(&self) -> ColorF {
+        self.canvas_color
+    }
+
+    /// Top of each page in page space when the pages with `heights` are stacked for
+    /// continuous scrolling, the first one at 0 and `page_gap` apart.
+    pub fn page_offsets(&self, heights: &[f32]) -> Vec<f32> {
+        let gap = self.page_gap / self.scale;
+        let mut y = 0.0;
+        heights
+            .iter()
+            .map(|&height| {
+                let top = y;
+                y += height + gap;
+                top
+            })
+            .collect()
+    }
+
+    /// `view_transform` for a page that starts at `offset`, see `page_offsets`.
+    pub fn page_view_transform(&self, offset: f32) -> Transform2F {
+        self.view_transform() * Transform2F::from_translation(Vector2F::new(0.0, offset))
+    }
+
+    /// Map a position in the window (in logical pixels) into page space.
+    pub fn window_to_page(&self, pos: Vector2F) -> Vector2F {
+        self.view_transform().inverse() * (pos * self.scale_factor)
+    }
+
+    /// The part of the page (in page space) that is inside the window.
+    ///
+    /// Empty if the page is scrolled out of view. Without bounds this is the whole window.
+    pub fn visible_page_rect(&self) -> RectF {
+        let window = self.view_transform().inverse() * self.view_box();
+        match self.bounds {
+            Some(bounds) => window.intersection(bounds).unwrap_or_default(),
+            None => window,
+        }
+    }
+
+    /// The scene view box, covering the window in device pixels.
+    pub fn view_box(&self) -> RectF {
+        RectF::new(Vector2F::zero(), self.pixel_size())
+    }
+
     pub fn set_view_box(&mut self, view_box: RectF) {
         self.window_size = view_box.size();
         self.check_bounds();
@@ -172,6 +701,7 @@ impl<B: ViewBackend> Context<B> {
     pub fn set_scale(&mut self, scale: f32) {
         self.scale = scale;
         self.check_bounds();
+        self.notify_view_change();
     }
 
     fn sanity_check(&mut self) {
@@ -181,9 +711,489 @@ impl<B: ViewBackend> Context<B> {
         self.window_size *= s;
     }
 
+    /// Set the text runs of the current page, used for hit testing the selection.
+    pub fn set_text_runs(&mut self, runs: Vec<TextRun>) {
+        self.text_runs = runs;
+    }
+
+    /// Start a text selection at `pos` (in page space).
+    pub fn begin_selection(&mut self, pos: Vector2F) {
+        self.begin_selection_with_mode(pos, SelectionMode::Text);
+    }
+
+    pub fn begin_selection_with_mode(&mut self, pos: Vector2F, mode: SelectionMode) {
+        self.selection = Some(Selection::new(mode, pos));
+        self.invalidate_scene();
+    }
+
+    /// Move the end of the current selection to `pos` (in page space).
+    pub fn update_selection(&mut self, pos: Vector2F) {
+        if let Some(ref mut selection) = self.selection {
+            selection.update(pos);
+            self.invalidate_scene();
+        }
+    }
+
+    pub fn clear_selection(&mut self) {
+        if self.selection.take().is_some() {
+            self.invalidate_scene();
+        }
+    }
+
+    /// Select all text of the current page (Ctrl/Cmd+A).
+    pub fn select_all(&mut self) {
+        self.begin_selection_with_mode(Vector2F::zero(), SelectionMode::All);
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        let text = self.selection.as_ref()?.text(&self.text_runs);
+        if text.is_empty() { None } else { Some(text) }
+    }
+
+    /// Copy the selected text to the clipboard (Ctrl/Cmd+C).
+    ///
+    /// Returns false if nothing is selected.
+    pub fn copy_selection(&mut self) -> bool {
+        match self.selected_text() {
+            Some(text) => {
+                self.backend.set_clipboard(&text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copy all text of the current page to the clipboard, without changing the selection.
+    ///
+    /// For the text of the whole document, pass `search::document_text` to `copy_text`.
+    pub fn copy_all_text(&mut self) {
+        let text = runs_text(&self.text_runs);
+        self.copy_text(&text);
+    }
+
+    pub fn copy_text(&mut self, text: &str) {
+        self.backend.set_clipboard(text);
+    }
+
+    /// Highlight quads of the current selection (in page space).
+    pub fn selection_quads(&self) -> Vec<RectF> {
+        match self.selection {
+            Some(ref selection) => selection.quads(&self.text_runs),
+            None => vec![],
+        }
+    }
+
+    /// Set the links of the current page (in page space).
+    pub fn set_links(&mut self, links: Vec<Link>) {
+        self.links = links;
+    }
+
+    /// The target of the link at `pos` (in page space).
+    pub fn link_at(&self, pos: Vector2F) -> Option<&LinkTarget> {
+        link_at(&self.links, pos).map(|link| &link.target)
+    }
+
+    /// Set the annotation tooltips of the current page (in page space), see `page_space_tooltips`.
+    pub fn set_tooltips(&mut self, tooltips: Vec<Tooltip>) {
+        self.tooltips = tooltips;
+    }
+
+    /// What to show when hovering `pos` (in page space): the target of a link,
+    /// or the text of an annotation.
+    pub fn tooltip_at(&self, pos: Vector2F) -> Option<String> {
+        match self.link_at(pos) {
+            Some(target) => Some(target.to_string()),
+            None => tooltip_at(&self.tooltips, pos).map(String::from),
+        }
+    }
+
+    /// The cursor is at `pos` (in page space): show its tooltip in the backend.
+    ///
+    /// The backend is only called when the tooltip changes.
+    pub fn hover(&mut self, pos: Vector2F) {
+        let tooltip = self.tooltip_at(pos);
+        self.show_tooltip(tooltip);
+    }
+
+    fn show_tooltip(&mut self, tooltip: Option<String>) {
+        if tooltip != self.tooltip {
+            self.tooltip = tooltip.clone();
+            self.backend.set_tooltip(tooltip);
+        }
+    }
+
     pub fn send(&mut self, _data: Vec<u8>) {}
 
     pub fn set_icon(&mut self, icon: Icon) {
         self.backend.set_icon(icon);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{test_context, NoBackend};
+
+    #[test]
+    fn test_scale_factor() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+        let page = RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0));
+
+        let scale = ctx.scale;
+        let page_1x = ctx.view_transform() * page;
+        assert_eq!(ctx.view_box().size(), Vector2F::new(800.0, 600.0));
+
+        ctx.mark_drawn();
+        ctx.set_scale_factor(2.0);
+        assert!(ctx.needs_redraw());
+
+        // twice the pixels, same logical zoom
+        assert_eq!(ctx.scale, scale);
+        assert_eq!(ctx.window_size(), Vector2F::new(800.0, 600.0));
+        assert_eq!(ctx.view_box().size(), Vector2F::new(1600.0, 1200.0));
+        let page_2x = ctx.view_transform() * page;
+        assert_eq!(page_2x.size(), page_1x.size() * 2.0);
+        assert_eq!(page_2x.origin(), page_1x.origin() * 2.0);
+    }
+
+    #[test]
+    fn test_zoom_to_rect() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+
+        // same aspect ratio as the window
+        let rect = RectF::new(Vector2F::new(50.0, 100.0), Vector2F::new(40.0, 30.0));
+        ctx.zoom_to_rect(rect);
+        let tr = ctx.view_transform();
+        assert!((tr * rect.origin()).length() < 1e-2, "{:?}", tr * rect.origin());
+        assert!((tr * rect.lower_right() - Vector2F::new(800.0, 600.0)).length() < 1e-2);
+
+        // the margin is kept on the limiting side, the other one is centered
+        ctx.zoom_to_rect_with_margin(rect, 20.0);
+        let r = ctx.view_transform() * rect;
+        assert!((r.min_y() - 20.0).abs() < 1e-2 && (r.max_y() - 580.0).abs() < 1e-2, "{:?}", r);
+        assert!((r.min_x() - (800.0 - r.max_x())).abs() < 1e-2, "{:?}", r);
+    }
+
+    #[test]
+    fn test_scene_cache() {
+        use std::cell::Cell;
+
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        let built = Cell::new(0);
+        let frame = |ctx: &mut Context<NoBackend>| {
+            ctx.cached_scene(|_| {
+                built.set(built.get() + 1);
+                Scene::new()
+            });
+        };
+
+        frame(&mut ctx);
+        // nothing changed in between
+        ctx.request_redraw();
+        ctx.request_redraw();
+        frame(&mut ctx);
+        assert_eq!(built.get(), 1);
+
+        ctx.zoom_by(1.0);
+        frame(&mut ctx);
+        assert_eq!(built.get(), 2);
+
+        ctx.begin_selection(Vector2F::zero());
+        frame(&mut ctx);
+        assert_eq!(built.get(), 3);
+
+        ctx.invalidate_scene();
+        frame(&mut ctx);
+        frame(&mut ctx);
+        assert_eq!(built.get(), 4);
+    }
+
+    #[test]
+    fn test_fit_content() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+
+        // without content bounds it fits the page
+        ctx.fit_content();
+        let fit_page = ctx.fit_scale(InitialFit::Page).unwrap();
+        assert_eq!(ctx.scale, fit_page);
+
+        let content = RectF::new(Vector2F::new(60.0, 80.0), Vector2F::new(100.0, 120.0));
+        ctx.set_content_bounds(Some(content));
+        ctx.fit_content();
+        assert!(ctx.scale > fit_page);
+        assert!((ctx.view_transform() * content.center() - Vector2F::new(400.0, 300.0)).length() < 1e-2);
+
+        ctx.num_pages = 2;
+        ctx.next_page();
+        assert_eq!(ctx.content_bounds, None);
+    }
+
+    #[test]
+    fn test_zoom_at() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.move_to(Vector2F::new(100.0, 100.0));
+
+        let cursor = Vector2F::new(600.0, 200.0);
+        let before = ctx.window_to_page(cursor);
+        ctx.zoom_at(ctx.scale * 2.0, cursor);
+        let after = ctx.window_to_page(cursor);
+        assert!((before - after).length() < 1e-3);
+
+        ctx.zoom_at(1e6, cursor);
+        assert_eq!(ctx.scale, MAX_ZOOM * DEFAULT_SCALE);
+    }
+
+    #[test]
+    fn test_view_observer() {
+        use std::cell::RefCell;
+
+        let mut ctx = test_context();
+        ctx.num_pages = 5;
+        let seen = Rc::new(RefCell::new(vec![]));
+        let seen2 = seen.clone();
+        ctx.on_view_change(Box::new(move |state| seen2.borrow_mut().push(*state)));
+
+        ctx.next_page();
+        ctx.goto_page(3);
+        // no change, no call
+        ctx.goto_page(3);
+        ctx.request_redraw();
+        assert_eq!(seen.borrow().iter().map(|s| s.page_nr).collect::<Vec<_>>(), [1, 3]);
+
+        ctx.set_zoom(2.0 * DEFAULT_SCALE);
+        let last = *seen.borrow().last().unwrap();
+        assert_eq!(last.scale, 2.0 * DEFAULT_SCALE);
+        assert_eq!(last.page_nr, 3);
+        assert_eq!(last.num_pages, 5);
+    }
+
+    #[test]
+    fn test_initial_fit() {
+        let a0 = RectF::new(Vector2F::zero(), Vector2F::new(841.0, 1189.0));
+
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(a0);
+        assert!(ctx.scale < DEFAULT_SCALE);
+        assert_eq!(ctx.scale, 800.0 / 841.0);
+        // later pages keep the zoom
+        ctx.set_zoom(2.0);
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+        assert_eq!(ctx.scale, 2.0);
+
+        let mut ctx = test_context();
+        ctx.set_initial_fit(InitialFit::Page);
+        // bounds before the window size
+        ctx.set_bounds(a0);
+        assert_eq!(ctx.scale, DEFAULT_SCALE);
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        assert_eq!(ctx.scale, 600.0 / 1189.0);
+
+        let mut ctx = test_context();
+        ctx.set_initial_fit(InitialFit::Scale(DEFAULT_SCALE));
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(a0);
+        assert_eq!(ctx.scale, DEFAULT_SCALE);
+    }
+
+    #[test]
+    fn test_scroll_inverted() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+        let middle = Vector2F::new(105.0, 148.5);
+
+        ctx.move_to(middle);
+        ctx.scroll(Vector2F::new(0.0, 3.0), ScrollUnit::Lines);
+        let down = ctx.view_center.y() - middle.y();
+        assert!(down > 0.0);
+
+        ctx.set_scroll_inverted(true);
+        ctx.move_to(middle);
+        ctx.scroll(Vector2F::new(0.0, 3.0), ScrollUnit::Lines);
+        assert!((ctx.view_center.y() - middle.y() + down).abs() < 1e-4);
+
+        // twice the line height, twice the distance
+        ctx.set_scroll_factors(Vector2F::splat(1.0), Vector2F::splat(2.0));
+        ctx.move_to(middle);
+        ctx.scroll(Vector2F::new(0.0, 3.0), ScrollUnit::Lines);
+        assert!((ctx.view_center.y() - middle.y() + 2.0 * down).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_scroll_units() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0)));
+        // 100 x 75 mm of the page are visible
+        ctx.set_zoom(8.0);
+        ctx.set_scroll_factors(Vector2F::splat(1.0), Vector2F::splat(20.0));
+        let middle = Vector2F::new(105.0, 148.5);
+
+        ctx.move_to(middle);
+        ctx.scroll_pixels(Vector2F::new(0.0, 3.0));
+        let pixels = ctx.view_center.y() - middle.y();
+
+        ctx.move_to(middle);
+        ctx.scroll_lines(Vector2F::new(0.0, 3.0));
+        let lines = ctx.view_center.y() - middle.y();
+
+        // 3 pixels on screen, and 3 lines of 20 pixels
+        assert!((pixels - 3.0 / ctx.scale).abs() < 1e-4, "{}", pixels);
+        assert!((lines - 20.0 * pixels).abs() < 1e-3, "{} {}", lines, pixels);
+    }
+
+    #[test]
+    fn test_per_page_view_memory() {
+        let mut ctx = test_context();
+        ctx.num_pages = 3;
+        ctx.set_per_page_view_memory(true);
+        ctx.goto_page(1);
+        ctx.set_zoom(3.0 * DEFAULT_SCALE);
+        ctx.goto_page(0);
+        ctx.set_zoom(DEFAULT_SCALE);
+        ctx.goto_page(1);
+        assert_eq!(ctx.scale, 3.0 * DEFAULT_SCALE);
+        // never visited, keeps the current view
+        ctx.goto_page(2);
+        assert_eq!(ctx.scale, 3.0 * DEFAULT_SCALE);
+        ctx.goto_page(0);
+        assert_eq!(ctx.scale, DEFAULT_SCALE);
+
+        ctx.set_per_page_view_memory(false);
+        ctx.goto_page(1);
+        assert_eq!(ctx.scale, DEFAULT_SCALE);
+    }
+
+    #[test]
+    fn test_visible_page_rect() {
+        let mut ctx = test_context();
+        ctx.set_scale_factor(2.0);
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        // a tall page, the width fits
+        ctx.set_bounds(RectF::new(Vector2F::zero(), Vector2F::new(210.0, 2000.0)));
+        let height = 600.0 / ctx.scale;
+
+        let top = ctx.visible_page_rect();
+        assert!(top.origin_y().abs() < 1e-3, "{:?}", top);
+        assert!((top.width() - 210.0).abs() < 1e-3);
+
+        ctx.move_to(Vector2F::new(105.0, 1000.0));
+        let rect = ctx.visible_page_rect();
+        assert!(rect.origin_y() > 0.0);
+        assert!((rect.height() - height).abs() < 1e-3, "{:?} {}", rect, height);
+        assert!((rect.center().y() - 1000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_presentation_mode() {
+        let mut ctx = test_context();
+        ctx.num_pages = 3;
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        let a4 = RectF::new(Vector2F::zero(), Vector2F::new(210.0, 297.0));
+        ctx.set_bounds(a4);
+        // the width fits by default, the page is taller than the window
+        let scale = ctx.scale;
+        assert_eq!(Some(scale), ctx.fit_scale(InitialFit::Width));
+        assert!(!ctx.presentation_key(" "));
+
+        ctx.set_presentation_mode(true);
+        assert!(ctx.presentation_mode());
+        assert!(ctx.backend.fullscreen);
+        assert_eq!(Some(ctx.scale), ctx.fit_scale(InitialFit::Page));
+        // the whole page is visible
+        let visible = ctx.visible_page_rect();
+        assert!((visible.height() - a4.height()).abs() < 1e-2, "{:?}", visible);
+
+        assert!(ctx.presentation_key(" "));
+        assert_eq!(ctx.page_nr(), 1);
+        assert!(ctx.presentation_key("ArrowLeft"));
+        assert_eq!(ctx.page_nr(), 0);
+        assert!(!ctx.presentation_key("a"));
+
+        // back to the view from before
+        assert!(ctx.presentation_key("Escape"));
+        assert!(!ctx.presentation_mode());
+        assert!(!ctx.backend.fullscreen);
+        assert_eq!(ctx.scale, scale);
+    }
+
+    #[test]
+    fn test_page_gap() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_scale(2.0);
+        let heights = [297.0, 297.0, 210.0];
+        let offsets = ctx.page_offsets(&heights);
+        assert_eq!(offsets[0], 0.0);
+        assert_eq!(offsets[1], 297.0 + DEFAULT_PAGE_GAP / 2.0);
+
+        // 20 more logical pixels, at 2 pixels per unit
+        ctx.set_page_gap(DEFAULT_PAGE_GAP + 20.0);
+        let moved = ctx.page_offsets(&heights);
+        assert_eq!(moved[0], 0.0);
+        assert_eq!(moved[1] - offsets[1], 10.0);
+        assert_eq!(moved[2] - offsets[2], 20.0);
+        let shift = ctx.page_view_transform(moved[1]) * Vector2F::zero() - ctx.page_view_transform(offsets[1]) * Vector2F::zero();
+        assert_eq!(shift, Vector2F::new(0.0, 20.0));
+
+        ctx.set_canvas_color(ColorF::black());
+        assert_eq!(ctx.canvas_color(), ColorF::black());
+    }
+
+    #[test]
+    fn test_redraw_cycle() {
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        assert!(ctx.needs_redraw());
+        ctx.mark_drawn();
+        assert!(!ctx.needs_redraw());
+        assert_eq!(ctx.redraw_delay(0.0), None);
+        ctx.move_by(Vector2F::new(0.0, 10.0));
+        assert!(ctx.needs_redraw());
+        assert_eq!(ctx.redraw_delay(0.0), Some(0.0));
+
+        // at most 10 frames per second
+        ctx.set_min_frame_interval(Some(0.1));
+        ctx.mark_drawn_at(1.0);
+        assert!(!ctx.needs_redraw());
+        ctx.zoom_by(0.5);
+        let delay = ctx.redraw_delay(1.04).unwrap();
+        assert!((delay - 0.06).abs() < 1e-4, "{}", delay);
+        assert_eq!(ctx.redraw_delay(1.2), Some(0.0));
+        ctx.mark_drawn_at(1.2);
+        assert_eq!(ctx.redraw_delay(1.25), None);
+    }
+
+    #[test]
+    fn test_copy_all_text() {
+        let run = |y: f32, text: &str| TextRun {
+            rect: RectF::new(Vector2F::new(0.0, y), Vector2F::new(10.0 * text.len() as f32, 12.0)),
+            text: text.into(),
+            chars: (0..text.len()).map(|i| (i, 10.0 * i as f32)).collect(),
+        };
+        let mut ctx = test_context();
+        ctx.set_text_runs(vec![run(0.0, "Hello"), run(20.0, "World")]);
+        assert!(!ctx.copy_selection());
+        assert_eq!(ctx.backend.clipboard, None);
+
+        ctx.copy_all_text();
+        assert_eq!(ctx.backend.clipboard.as_deref(), Some("Hello\nWorld"));
+        assert_eq!(ctx.selected_text(), None);
+
+        // Ctrl+A, Ctrl+C
+        ctx.backend.clipboard = None;
+        ctx.select_all();
+        assert_eq!(ctx.selection_quads().len(), 2);
+        assert!(ctx.copy_selection());
+        assert_eq!(ctx.backend.clipboard.as_deref(), Some("Hello\nWorld"));
+    }
+}
@@ -1,10 +1,24 @@
 pub mod context;
 pub mod config;
 pub mod types;
+pub mod selection;
+pub mod links;
+pub mod thumbnail;
+pub mod session;
+pub mod search;
+pub mod pages;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 
-pub use context::{Context, ViewBackend, DEFAULT_SCALE};
+pub use context::{Context, InitialFit, ScrollUnit, ViewBackend, ViewState, DEFAULT_SCALE, DEFAULT_PAGE_GAP, MIN_ZOOM, MAX_ZOOM};
 pub use config::{Config, Icon, view_box};
-pub use types::{Emitter, Interactive};
+pub use types::{Emitter, Interactive, ViewError};
+pub use selection::{Selection, SelectionMode, TextRun, draw_highlight, runs_text};
+pub use links::{Link, LinkTarget, Tooltip, page_links, page_space_links, page_tooltips, page_space_tooltips};
+pub use thumbnail::{fit_transform, render_page_fit, render_thumbnail, thumbnail_scale};
+pub use session::Session;
+pub use search::{document_text, search_document, SearchOptions, SearchResult};
+pub use pages::PageCache;
 
 use pathfinder_geometry::vector::Vector2I;
 
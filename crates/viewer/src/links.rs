@@ -0,0 +1,254 @@
+use std::fmt;
+
+use inkrender::page_transform;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pdf::object::{Page, PlainRef, Rectangle, Resolve};
+use pdf::primitive::{Dictionary, Primitive};
+
+/// Where a link points to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LinkTarget {
+    /// a page of the same document (0-based)
+    Page(usize),
+    /// an external resource
+    Uri(String),
+}
+
+impl fmt::Display for LinkTarget {
+    /// What a tooltip shows for the link: the URI, or "Page 5" (1-based).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinkTarget::Page(page) => write!(f, "Page {}", page + 1),
+            LinkTarget::Uri(uri) => f.write_str(uri),
+        }
+    }
+}
+
+/// A clickable area on a page.
+#[derive(Clone, Debug)]
+pub struct Link {
+    pub rect: RectF,
+    pub target: LinkTarget,
+}
+
+impl Link {
+    /// Map the link rect through `tr`, e.g. from PDF user space into page space.
+    pub fn transformed(self, tr: Transform2F) -> Link {
+        Link { rect: tr * self.rect, target: self.target }
+    }
+}
+
+/// The topmost link containing `pos`.
+pub fn link_at(links: &[Link], pos: Vector2F) -> Option<&Link> {
+    links.iter().rev().find(|link| link.rect.contains_point(pos))
+}
+
+/// An annotation with a text to show on hover, e.g. a comment.
+#[derive(Clone, Debug)]
+pub struct Tooltip {
+    pub rect: RectF,
+    pub text: String,
+}
+
+impl Tooltip {
+    /// Map the rect through `tr`, see `Link::transformed`.
+    pub fn transformed(self, tr: Transform2F) -> Tooltip {
+        Tooltip { rect: tr * self.rect, text: self.text }
+    }
+}
+
+/// The text of the topmost tooltip containing `pos`.
+pub fn tooltip_at(tooltips: &[Tooltip], pos: Vector2F) -> Option<&str> {
+    tooltips.iter().rev().find(|tip| tip.rect.contains_point(pos)).map(|tip| tip.text.as_str())
+}
+
+/// Extract the link annotations of `page`. Rects are in PDF user space.
+///
+/// `page_nr` maps the reference of a page to its number.
+pub fn page_links(
+    page: &Page,
+    resolve: &impl Resolve,
+    page_nr: impl Fn(PlainRef) -> Option<usize>,
+) -> Vec<Link> {
+    let annots = match page.annotations.load(resolve) {
+        Ok(annots) => annots,
+        Err(e) => {
+            log::warn!("can't load annotations: {:?}", e);
+            return vec![];
+        }
+    };
+    annots
+        .iter()
+        .filter(|annot| annot.subtype.as_str() == "Link")
+        .filter_map(|annot| {
+            let rect = annot_rect(annot.rect?);
+            let target = link_target(&annot.other, resolve, &page_nr)?;
+            Some(Link { rect, target })
+        })
+        .collect()
+}
+
+/// Like `page_links`, with the rects in page space (see `inkrender::page_transform`),
+/// which follows `/Rotate` and is what `Context::window_to_page` maps clicks to.
+pub fn page_space_links(
+    page: &Page,
+    resolve: &impl Resolve,
+    page_nr: impl Fn(PlainRef) -> Option<usize>,
+) -> Vec<Link> {
+    let tr = page_transform(page);
+    page_links(page, resolve, page_nr).into_iter().map(|link| link.transformed(tr)).collect()
+}
+
+/// The `/Contents` of the annotations of `page` that are not links, e.g. comments.
+/// Rects are in PDF user space.
+///
+/// Popups are skipped, their text is that of the annotation they belong to.
+pub fn page_tooltips(page: &Page, resolve: &impl Resolve) -> Vec<Tooltip> {
+    let annots = match page.annotations.load(resolve) {
+        Ok(annots) => annots,
+        Err(e) => {
+            log::warn!("can't load annotations: {:?}", e);
+            return vec![];
+        }
+    };
+    annots
+        .iter()
+        .filter(|annot| !matches!(annot.subtype.as_str(), "Link" | "Popup"))
+        .filter_map(|annot| {
+            let rect = annot_rect(annot.rect?);
+            let text = annot.contents.as_ref()?.to_string_lossy();
+            let text = text.trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(Tooltip { rect, text: text.into() })
+        })
+        .collect()
+}
+
+/// Like `page_tooltips`, with the rects in page space, see `page_space_links`.
+pub fn page_space_tooltips(page: &Page, resolve: &impl Resolve) -> Vec<Tooltip> {
+    let tr = page_transform(page);
+    page_tooltips(page, resolve).into_iter().map(|tip| tip.transformed(tr)).collect()
+}
+
+fn annot_rect(r: Rectangle) -> RectF {
+    RectF::from_points(
+        Vector2F::new(r.left.min(r.right), r.bottom.min(r.top)),
+        Vector2F::new(r.left.max(r.right), r.bottom.max(r.top)),
+    )
+}
+
+fn link_target(
+    dict: &Dictionary,
+    resolve: &impl Resolve,
+    page_nr: &impl Fn(PlainRef) -> Option<usize>,
+) -> Option<LinkTarget> {
+    if let Some(dest) = dict.get("Dest") {
+        return dest_page(dest, resolve, page_nr).map(LinkTarget::Page);
+    }
+    let action = dict.get("A")?.clone().resolve(resolve).ok()?.into_dictionary().ok()?;
+    match action.get("S")?.as_name().ok()? {
+        "URI" => {
+            let uri = action.get("URI")?.clone().resolve(resolve).ok()?;
+            Some(LinkTarget::Uri(uri.as_string().ok()?.to_string_lossy()))
+        }
+        "GoTo" => dest_page(action.get("D")?, resolve, page_nr).map(LinkTarget::Page),
+        s => {
+            log::debug!("unsupported link action {}", s);
+            None
+        }
+    }
+}
+
+/// explicit destinations only: `[page /XYZ left top zoom]` and friends
+fn dest_page(
+    dest: &Primitive,
+    resolve: &impl Resolve,
+    page_nr: &impl Fn(PlainRef) -> Option<usize>,
+) -> Option<usize> {
+    let dest = dest.clone().resolve(resolve).ok()?;
+    match dest.as_array().ok()?.first()? {
+        &Primitive::Reference(r) => page_nr(r),
+        // remote destinations use the page number
+        &Primitive::Integer(n) => usize::try_from(n).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_context;
+    use crate::context::Context;
+    use crate::types::{Emitter, Interactive};
+    use pathfinder_renderer::scene::Scene;
+
+    struct App;
+    impl Interactive for App {
+        type Event = ();
+        type Backend = crate::test_util::NoBackend;
+        fn scene(&mut self, _ctx: &mut Context<Self::Backend>) -> Scene {
+            Scene::new()
+        }
+        fn init(&mut self, _ctx: &mut Context<Self::Backend>, _sender: Emitter<()>) {}
+    }
+
+    #[test]
+    fn test_click_link() {
+        let mut ctx = test_context();
+        ctx.num_pages = 10;
+        ctx.set_links(vec![
+            Link { rect: RectF::new(Vector2F::new(10.0, 10.0), Vector2F::new(50.0, 10.0)), target: LinkTarget::Page(4) },
+            Link {
+                rect: RectF::new(Vector2F::new(10.0, 30.0), Vector2F::new(50.0, 10.0)),
+                target: LinkTarget::Uri("https://example.com/".into()),
+            },
+        ]);
+        assert_eq!(ctx.link_at(Vector2F::new(20.0, 15.0)), Some(&LinkTarget::Page(4)));
+        assert_eq!(ctx.link_at(Vector2F::new(5.0, 15.0)), None);
+
+        let mut app = App;
+        app.mouse_click(&mut ctx, Vector2F::new(30.0, 35.0));
+        assert_eq!(ctx.backend.opened, vec!["https://example.com/".to_string()]);
+        assert_eq!(ctx.page_nr(), 0);
+
+        app.mouse_click(&mut ctx, Vector2F::new(20.0, 15.0));
+        assert_eq!(ctx.page_nr(), 4);
+
+        // links belong to a page
+        assert!(ctx.links.is_empty());
+    }
+
+    #[test]
+    fn test_hover_tooltip() {
+        let mut ctx = test_context();
+        ctx.num_pages = 10;
+        ctx.set_links(vec![
+            Link { rect: RectF::new(Vector2F::new(10.0, 10.0), Vector2F::new(50.0, 10.0)), target: LinkTarget::Page(4) },
+            Link {
+                rect: RectF::new(Vector2F::new(10.0, 30.0), Vector2F::new(50.0, 10.0)),
+                target: LinkTarget::Uri("https://example.com/".into()),
+            },
+        ]);
+        ctx.set_tooltips(vec![Tooltip { rect: RectF::new(Vector2F::new(10.0, 50.0), Vector2F::splat(10.0)), text: "a comment".into() }]);
+
+        let mut app = App;
+        app.cursor_moved(&mut ctx, Vector2F::new(30.0, 35.0));
+        assert_eq!(ctx.backend.tooltip.as_deref(), Some("https://example.com/"));
+        app.cursor_moved(&mut ctx, Vector2F::new(20.0, 15.0));
+        assert_eq!(ctx.backend.tooltip.as_deref(), Some("Page 5"));
+        app.cursor_moved(&mut ctx, Vector2F::new(15.0, 55.0));
+        assert_eq!(ctx.backend.tooltip.as_deref(), Some("a comment"));
+        app.cursor_moved(&mut ctx, Vector2F::new(5.0, 5.0));
+        assert_eq!(ctx.backend.tooltip, None);
+
+        // a page change hides the tooltip of the old page
+        app.cursor_moved(&mut ctx, Vector2F::new(30.0, 35.0));
+        ctx.goto_page(1);
+        assert_eq!(ctx.backend.tooltip, None);
+        assert!(ctx.tooltips.is_empty());
+    }
+}
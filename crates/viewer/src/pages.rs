@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use inkrender::page_bounds;
+use pathfinder_geometry::rect::RectF;
+use pdf::error::PdfError;
+use pdf::object::PageRc;
+
+/// The pages of one document and their bounds, by index.
+///
+/// Continuous and thumbnail views ask for the bounds of every page on every frame,
+/// each page is only looked up once. Call `clear` when the document changes.
+#[derive(Default)]
+pub struct PageCache {
+    pages: HashMap<usize, (PageRc, RectF)>,
+}
+
+impl PageCache {
+    pub fn new() -> Self {
+        PageCache::default()
+    }
+
+    /// Page `nr` and its bounds (see `page_bounds`). `get_page` is called with `nr` if the
+    /// page is not cached yet, e.g. `|nr| file.get_page(nr)`. Errors are not cached.
+    pub fn get(
+        &mut self,
+        nr: usize,
+        get_page: impl FnOnce(u32) -> Result<PageRc, PdfError>,
+    ) -> Result<(PageRc, RectF), PdfError> {
+        if let Some((page, bounds)) = self.pages.get(&nr) {
+            return Ok((page.clone(), *bounds));
+        }
+        let page = get_page(nr as u32)?;
+        let bounds = page_bounds(&page);
+        self.pages.insert(nr, (page.clone(), bounds));
+        Ok((page, bounds))
+    }
+
+    /// The bounds of page `nr`, see `get`.
+    pub fn bounds(&mut self, nr: usize, get_page: impl FnOnce(u32) -> Result<PageRc, PdfError>) -> Result<RectF, PdfError> {
+        self.get(nr, get_page).map(|(_, bounds)| bounds)
+    }
+
+    /// Forget all pages, for a new document.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
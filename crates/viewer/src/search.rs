@@ -0,0 +1,238 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pdf::any::AnySync;
+use pdf::error::PdfError;
+use pdf::file::{Cache as PdfCache, File as PdfFile, Log};
+use pdf::object::{Page, Resolve};
+
+use crate::selection::{runs_text, TextRun};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Off by default: "fox" finds "Fox" too.
+    pub case_sensitive: bool,
+    /// Only matches that are not part of a longer word.
+    pub whole_word: bool,
+}
+
+/// A match of `search_document`.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// 0-based
+    pub page: usize,
+    /// One rect per text run of the match, in page space.
+    pub quads: Vec<RectF>,
+    /// The match with some text around it, for a results list.
+    pub snippet: String,
+}
+
+// chars of context on each side of a snippet
+const SNIPPET_CONTEXT: usize = 30;
+
+/// The text runs of `page` in page space (see `inkrender::page_transform`).
+pub fn page_text_runs(cache: &mut Cache, resolve: &impl Resolve, page: &Page) -> Result<Vec<TextRun>, PdfError> {
+    let mut backend = SceneBackend::new(cache);
+    backend.collect_text();
+    render_page(&mut backend, resolve, page, Transform2F::default())?;
+    let (_, spans) = backend.finish_with_text();
+    Ok(spans.iter().map(TextRun::from).collect())
+}
+
+/// Find `query` in every page of `file`, in page order.
+///
+/// Pages are split between one thread per core (on wasm they are searched in turn),
+/// each with its own render cache. Pages that fail to load are skipped.
+pub fn search_document<B, OC, SC, L>(
+    file: &PdfFile<B, OC, SC, L>,
+    query: &str,
+    options: SearchOptions,
+) -> Vec<SearchResult>
+    where
+        B: pdf::backend::Backend + Sync,
+        OC: PdfCache<Result<AnySync, Arc<PdfError>>> + Sync,
+        SC: PdfCache<Result<Arc<[u8]>, Arc<PdfError>>> + Sync,
+        L: Log + Sync
+{
+    if query.is_empty() {
+        return vec![];
+    }
+    let num_pages = file.num_pages() as usize;
+    let search_pages = |pages: Range<usize>| -> Vec<SearchResult> {
+        let mut cache = Cache::new();
+        let resolver = file.resolver();
+        let mut results = vec![];
+        for nr in pages {
+            let runs = file.get_page(nr as u32).and_then(|page| page_text_runs(&mut cache, &resolver, &page));
+            match runs {
+                Ok(runs) => results.extend(search_runs(&runs, nr, query, options)),
+                Err(e) => log::warn!("can't search page {}: {:?}", nr, e),
+            }
+        }
+        results
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    return search_pages(0..num_pages);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).clamp(1, num_pages.max(1));
+        let chunk = num_pages.div_ceil(threads);
+        let search_pages = &search_pages;
+        std::thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|i| {
+                    let pages = (i * chunk).min(num_pages)..((i + 1) * chunk).min(num_pages);
+                    s.spawn(move || search_pages(pages))
+                })
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap_or_default()).collect()
+        })
+    }
+}
+
+/// All text of `file`, page by page, e.g. to copy it with `Context::copy_text`.
+///
+/// Pages are separated by a blank line. Pages that fail to load are skipped.
+pub fn document_text<B, OC, SC, L>(file: &PdfFile<B, OC, SC, L>) -> String
+    where
+        B: pdf::backend::Backend,
+        OC: PdfCache<Result<AnySync, Arc<PdfError>>>,
+        SC: PdfCache<Result<Arc<[u8]>, Arc<PdfError>>>,
+        L: Log
+{
+    let mut cache = Cache::new();
+    let resolver = file.resolver();
+    let mut pages = vec![];
+    for nr in 0..file.num_pages() {
+        match file.get_page(nr).and_then(|page| page_text_runs(&mut cache, &resolver, &page)) {
+            Ok(runs) => pages.push(runs_text(&runs)),
+            Err(e) => log::warn!("can't extract the text of page {}: {:?}", nr, e),
+        }
+    }
+    pages.join("\n\n")
+}
+
+/// Find `query` in the text runs of one page.
+///
+/// Runs are read in order. Runs that continue each other on the same line are joined,
+/// others are separated by a space, so matches can span runs and lines.
+pub fn search_runs(runs: &[TextRun], page: usize, query: &str, options: SearchOptions) -> Vec<SearchResult> {
+    let fold = |c: char| if options.case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let query: Vec<char> = query.chars().map(fold).collect();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    // every char of the page, with the run and char index it belongs to
+    let mut text: Vec<(char, Option<(usize, usize)>)> = vec![];
+    let mut last: Option<RectF> = None;
+    for (i, run) in runs.iter().enumerate() {
+        if let Some(last) = last {
+            let same_line = run.rect.min_y() < last.max_y() && run.rect.max_y() > last.min_y();
+            let gap = run.rect.min_x() - last.max_x();
+            if !(same_line && gap.abs() < 0.15 * run.rect.height()) {
+                text.push((' ', None));
+            }
+        }
+        for (j, &(offset, _)) in run.chars.iter().enumerate() {
+            let end = run.chars.get(j + 1).map_or(run.text.len(), |&(offset, _)| offset);
+            text.extend(run.text[offset..end].chars().map(|c| (c, Some((i, j)))));
+        }
+        last = Some(run.rect);
+    }
+
+    let is_word = |idx: Option<usize>| idx.and_then(|i| text.get(i)).map_or(false, |&(c, _)| c.is_alphanumeric());
+    let mut results = vec![];
+    let mut start = 0;
+    while start + query.len() <= text.len() {
+        let end = start + query.len();
+        let found = text[start..end].iter().zip(&query).all(|(&(c, _), &q)| fold(c) == q)
+            && !(options.whole_word && (is_word(start.checked_sub(1)) || is_word(Some(end))));
+        if !found {
+            start += 1;
+            continue;
+        }
+        results.push(SearchResult {
+            page,
+            quads: quads(runs, &text[start..end]),
+            snippet: snippet(&text, start..end),
+        });
+        start = end;
+    }
+    results
+}
+
+/// One rect per run covered by `matched`.
+fn quads(runs: &[TextRun], matched: &[(char, Option<(usize, usize)>)]) -> Vec<RectF> {
+    let mut quads: Vec<(usize, usize, usize)> = vec![];
+    for &(run, idx) in matched.iter().filter_map(|(_, pos)| pos.as_ref()) {
+        match quads.last_mut() {
+            Some((r, _, to)) if *r == run => *to = idx + 1,
+            _ => quads.push((run, idx, idx + 1)),
+        }
+    }
+    quads
+        .into_iter()
+        .map(|(i, from, to)| {
+            let run = &runs[i];
+            RectF::from_points(
+                Vector2F::new(run.char_x(from), run.rect.min_y()),
+                Vector2F::new(run.char_x(to), run.rect.max_y()),
+            )
+        })
+        .collect()
+}
+
+fn snippet(text: &[(char, Option<(usize, usize)>)], range: Range<usize>) -> String {
+    let from = range.start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (range.end + SNIPPET_CONTEXT).min(text.len());
+    let mut snippet = String::new();
+    if from > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(text[from..to].iter().map(|&(c, _)| c));
+    if to < text.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(x: f32, y: f32, text: &str) -> TextRun {
+        TextRun {
+            rect: RectF::new(Vector2F::new(x, y), Vector2F::new(10.0 * text.len() as f32, 12.0)),
+            text: text.into(),
+            chars: (0..text.len()).map(|i| (i, 10.0 * i as f32)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_search_runs() {
+        // "brown " and "fox" continue each other, "Foxes" is on the next line
+        let runs = vec![run(0.0, 0.0, "brown "), run(60.0, 0.0, "fox"), run(0.0, 20.0, "Foxes")];
+
+        let results = search_runs(&runs, 3, "fox", SearchOptions::default());
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].page, 3);
+        assert_eq!(results[0].snippet, "brown fox Foxes");
+        assert_eq!(results[0].quads, [RectF::new(Vector2F::new(60.0, 0.0), Vector2F::new(30.0, 12.0))]);
+
+        // across runs
+        let results = search_runs(&runs, 0, "n fox", SearchOptions::default());
+        assert_eq!(results[0].quads.len(), 2);
+
+        let whole_word = SearchOptions { whole_word: true, ..SearchOptions::default() };
+        assert_eq!(search_runs(&runs, 0, "fox", whole_word).len(), 1);
+        let case_sensitive = SearchOptions { case_sensitive: true, ..SearchOptions::default() };
+        assert_eq!(search_runs(&runs, 0, "Fox", case_sensitive).len(), 1);
+    }
+}
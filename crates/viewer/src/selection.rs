@@ -0,0 +1,229 @@
+use pathfinder_color::ColorU;
+use pathfinder_content::outline::Outline;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_renderer::paint::Paint;
+use pathfinder_renderer::scene::{DrawPath, Scene};
+
+use inkrender::TextSpan;
+
+/// A run of text on a page, as used for hit testing.
+///
+/// All coordinates are in page space.
+#[derive(Clone, Debug)]
+pub struct TextRun {
+    pub rect: RectF,
+    pub text: String,
+    /// byte offset into `text` and horizontal start of every char (relative to `rect.min_x()`)
+    pub chars: Vec<(usize, f32)>,
+}
+
+impl TextRun {
+    /// Map the run through `tr`, e.g. from device space into page space.
+    pub fn transformed(self, tr: Transform2F) -> TextRun {
+        let rect = tr * self.rect;
+        let scale = if self.rect.width() != 0.0 { rect.width() / self.rect.width() } else { 0.0 };
+        TextRun {
+            rect,
+            text: self.text,
+            chars: self.chars.into_iter().map(|(offset, x)| (offset, x * scale)).collect(),
+        }
+    }
+    pub(crate) fn char_x(&self, idx: usize) -> f32 {
+        match self.chars.get(idx) {
+            Some(&(_, x)) => self.rect.min_x() + x,
+            None => self.rect.max_x(),
+        }
+    }
+    fn byte_offset(&self, idx: usize) -> usize {
+        match self.chars.get(idx) {
+            Some(&(offset, _)) => offset,
+            None => self.text.len(),
+        }
+    }
+    /// index of the char boundary closest to `x`
+    fn boundary_at(&self, x: f32) -> usize {
+        let n = self.chars.len();
+        (0..n)
+            .find(|&i| x < 0.5 * (self.char_x(i) + self.char_x(i + 1)))
+            .unwrap_or(n)
+    }
+    fn distance(&self, p: Vector2F) -> f32 {
+        let dx = (self.rect.min_x() - p.x()).max(p.x() - self.rect.max_x()).max(0.0);
+        let dy = (self.rect.min_y() - p.y()).max(p.y() - self.rect.max_y()).max(0.0);
+        dx * dx + dy * dy
+    }
+}
+
+impl<'a> From<&'a TextSpan> for TextRun {
+    fn from(span: &'a TextSpan) -> TextRun {
+        let (min, max) = span.extent;
+        let scale = if max > min { span.rect.width() / (max - min) } else { 0.0 };
+        TextRun {
+            rect: span.rect,
+            text: span.text.clone(),
+            chars: span.chars.iter().map(|c| (c.offset, (c.pos - min) * scale)).collect(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// select everything in stream order between the two anchors
+    Text,
+    /// select the chars inside the rectangle spanned by the two anchors
+    Rect,
+    /// select every run, the anchors are not used (see `Context::select_all`)
+    All,
+}
+
+#[derive(Clone, Debug)]
+pub struct Selection {
+    pub mode: SelectionMode,
+    pub start: Vector2F,
+    pub end: Vector2F,
+}
+
+impl Selection {
+    pub fn new(mode: SelectionMode, start: Vector2F) -> Self {
+        Selection { mode, start, end: start }
+    }
+
+    pub fn update(&mut self, end: Vector2F) {
+        self.end = end;
+    }
+
+    /// The selected char range `start .. end` of every covered run.
+    fn ranges(&self, runs: &[TextRun]) -> Vec<(usize, usize, usize)> {
+        match self.mode {
+            SelectionMode::Text => {
+                let (start, end) = match (anchor(runs, self.start), anchor(runs, self.end)) {
+                    (Some(a), Some(b)) => (a.min(b), a.max(b)),
+                    _ => return vec![],
+                };
+                (start.0..=end.0)
+                    .map(|i| {
+                        let from = if i == start.0 { start.1 } else { 0 };
+                        let to = if i == end.0 { end.1 } else { runs[i].chars.len() };
+                        (i, from, to)
+                    })
+                    .filter(|&(_, from, to)| from < to)
+                    .collect()
+            }
+            SelectionMode::Rect => {
+                let rect = RectF::from_points(self.start.min(self.end), self.start.max(self.end));
+                runs.iter()
+                    .enumerate()
+                    .filter(|(_, run)| run.rect.intersects(rect))
+                    .map(|(i, run)| {
+                        let from = run.boundary_at(rect.min_x());
+                        let to = run.boundary_at(rect.max_x());
+                        (i, from, to)
+                    })
+                    .filter(|&(_, from, to)| from < to)
+                    .collect()
+            }
+            SelectionMode::All => {
+                runs.iter()
+                    .enumerate()
+                    .map(|(i, run)| (i, 0, run.chars.len()))
+                    .filter(|&(_, from, to)| from < to)
+                    .collect()
+            }
+        }
+    }
+
+    /// The highlight quads, one per covered run.
+    pub fn quads(&self, runs: &[TextRun]) -> Vec<RectF> {
+        self.ranges(runs)
+            .into_iter()
+            .map(|(i, from, to)| {
+                let run = &runs[i];
+                RectF::from_points(
+                    Vector2F::new(run.char_x(from), run.rect.min_y()),
+                    Vector2F::new(run.char_x(to), run.rect.max_y()),
+                )
+            })
+            .collect()
+    }
+
+    /// The selected text. Runs on different lines are separated by a newline.
+    pub fn text(&self, runs: &[TextRun]) -> String {
+        let mut text = String::new();
+        let mut last: Option<RectF> = None;
+        for (i, from, to) in self.ranges(runs) {
+            let run = &runs[i];
+            if let Some(last) = last {
+                if run.rect.min_y() >= last.max_y() || run.rect.max_y() <= last.min_y() {
+                    text.push('\n');
+                }
+            }
+            text.push_str(&run.text[run.byte_offset(from)..run.byte_offset(to)]);
+            last = Some(run.rect);
+        }
+        text
+    }
+}
+
+/// All text of `runs` in stream order, separated like `Selection::text`.
+pub fn runs_text(runs: &[TextRun]) -> String {
+    Selection::new(SelectionMode::All, Vector2F::zero()).text(runs)
+}
+
+/// The (run, char boundary) closest to `p`.
+fn anchor(runs: &[TextRun], p: Vector2F) -> Option<(usize, usize)> {
+    let (idx, run) = runs.iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.distance(p).total_cmp(&b.distance(p)))?;
+    Some((idx, run.boundary_at(p.x())))
+}
+
+/// Draw the selection quads (in page space) into `scene`.
+pub fn draw_highlight(scene: &mut Scene, quads: &[RectF], transform: Transform2F, color: ColorU) {
+    let paint = scene.push_paint(&Paint::from_color(color));
+    for &quad in quads {
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(transform * quad), paint));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(x: f32, y: f32, text: &str) -> TextRun {
+        TextRun {
+            rect: RectF::new(Vector2F::new(x, y), Vector2F::new(10.0 * text.len() as f32, 12.0)),
+            text: text.into(),
+            chars: (0..text.len()).map(|i| (i, 10.0 * i as f32)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_select_across_runs() {
+        let runs = vec![run(0.0, 0.0, "Hello "), run(60.0, 0.0, "World"), run(0.0, 20.0, "Next")];
+
+        // from the second 'l' in "Hello" to the 'l' in "World"
+        let mut selection = Selection::new(SelectionMode::Text, Vector2F::new(31.0, 5.0));
+        selection.update(Vector2F::new(92.0, 5.0));
+        assert_eq!(selection.text(&runs), "lo Wor");
+        assert_eq!(selection.quads(&runs).len(), 2);
+
+        // dragging backwards selects the same text
+        let mut selection = Selection::new(SelectionMode::Text, Vector2F::new(92.0, 5.0));
+        selection.update(Vector2F::new(31.0, 5.0));
+        assert_eq!(selection.text(&runs), "lo Wor");
+
+        let mut selection = Selection::new(SelectionMode::Text, Vector2F::new(62.0, 5.0));
+        selection.update(Vector2F::new(21.0, 25.0));
+        assert_eq!(selection.text(&runs), "World\nNe");
+    }
+
+    #[test]
+    fn test_select_rect() {
+        let runs = vec![run(0.0, 0.0, "abcd"), run(0.0, 20.0, "efgh")];
+        let mut selection = Selection::new(SelectionMode::Rect, Vector2F::new(9.0, 0.0));
+        selection.update(Vector2F::new(31.0, 30.0));
+        assert_eq!(selection.text(&runs), "bc\nfg");
+    }
+}
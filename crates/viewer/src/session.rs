@@ -0,0 +1,200 @@
+use std::rc::Rc;
+
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_renderer::scene::Scene;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::types::{Emitter, Interactive, ViewError};
+
+/// The open documents of a tabbed viewer.
+///
+/// All documents share the `Config` and with it the resource loader. Render caches
+/// stay with the documents, image cache entries are keyed by object references
+/// which are only unique within a file.
+///
+/// Input and `scene` go to the active document, the others keep their view state.
+pub struct Session<I: Interactive> {
+    config: Rc<Config>,
+    documents: Vec<(I, Context<I::Backend>)>,
+    active: usize,
+}
+
+impl<I: Interactive> Session<I> {
+    pub fn new(config: Rc<Config>) -> Self {
+        Session { config, documents: Vec::new(), active: 0 }
+    }
+
+    pub fn config(&self) -> &Rc<Config> {
+        &self.config
+    }
+
+    /// Open `view` in a new context and make it the active document.
+    ///
+    /// Returns the index of the document.
+    pub fn add_document(&mut self, mut view: I, backend: I::Backend, sender: Emitter<I::Event>) -> usize {
+        let mut ctx = Context::new(self.config.clone(), backend);
+        view.init(&mut ctx, sender);
+        self.documents.push((view, ctx));
+        self.active = self.documents.len() - 1;
+        self.active
+    }
+
+    /// Close the document at `index`.
+    ///
+    /// If it was active, the next document (or the previous one, for the last) becomes active.
+    pub fn close_document(&mut self, index: usize) -> Option<I> {
+        if index >= self.documents.len() {
+            return None;
+        }
+        let (mut view, mut ctx) = self.documents.remove(index);
+        view.exit(&mut ctx);
+
+        if index < self.active || self.active >= self.documents.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        if let Some((_, ctx)) = self.documents.get_mut(self.active) {
+            ctx.request_redraw();
+        }
+        Some(view)
+    }
+
+    /// Make the document at `index` active. Returns false if there is none.
+    pub fn switch_to(&mut self, index: usize) -> bool {
+        match self.documents.get_mut(index) {
+            Some((_, ctx)) => {
+                self.active = index;
+                ctx.request_redraw();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Index of the active document, `None` if there are no documents.
+    pub fn active_index(&self) -> Option<usize> {
+        if self.documents.is_empty() { None } else { Some(self.active) }
+    }
+
+    pub fn active(&mut self) -> Option<(&mut I, &mut Context<I::Backend>)> {
+        self.documents.get_mut(self.active).map(|(view, ctx)| (view, ctx))
+    }
+
+    /// Titles of all documents, e.g. for the tab bar.
+    pub fn titles(&self) -> impl Iterator<Item = String> + '_ {
+        self.documents.iter().map(|(view, _)| view.title())
+    }
+
+    /// Scene of the active document, an empty scene without documents.
+    ///
+    /// Reuses the last scene while the view is unchanged, see `Context::cached_scene`.
+    pub fn scene(&mut self) -> Scene {
+        match self.active() {
+            Some((view, ctx)) => ctx.cached_scene(|ctx| view.scene(ctx)),
+            None => Scene::new(),
+        }
+    }
+
+    pub fn try_scene(&mut self) -> Result<Scene, ViewError> {
+        match self.active() {
+            Some((view, ctx)) => ctx.try_cached_scene(|ctx| view.try_scene(ctx)),
+            None => Ok(Scene::new()),
+        }
+    }
+
+    pub fn event(&mut self, event: I::Event) {
+        if let Some((view, ctx)) = self.active() {
+            view.event(ctx, event);
+        }
+    }
+
+    pub fn text_input(&mut self, input: String) {
+        if let Some((view, ctx)) = self.active() {
+            view.text_input(ctx, input);
+        }
+    }
+
+    pub fn cursor_moved(&mut self, pos: Vector2F) {
+        if let Some((view, ctx)) = self.active() {
+            view.cursor_moved(ctx, pos);
+        }
+    }
+
+    pub fn mouse_click(&mut self, pos: Vector2F) {
+        if let Some((view, ctx)) = self.active() {
+            view.mouse_click(ctx, pos);
+        }
+    }
+
+    pub fn idle(&mut self) {
+        if let Some((view, ctx)) = self.active() {
+            view.idle(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{NoBackend, NoResources};
+
+    #[derive(Default)]
+    struct Doc {
+        events: Vec<u32>,
+        clicks: usize,
+    }
+    impl Interactive for Doc {
+        type Event = u32;
+        type Backend = NoBackend;
+        fn scene(&mut self, _ctx: &mut Context<Self::Backend>) -> Scene {
+            Scene::new()
+        }
+        fn event(&mut self, _ctx: &mut Context<Self::Backend>, event: u32) {
+            self.events.push(event);
+        }
+        fn mouse_click(&mut self, _ctx: &mut Context<Self::Backend>, _pos: Vector2F) {
+            self.clicks += 1;
+        }
+        fn init(&mut self, _ctx: &mut Context<Self::Backend>, _sender: Emitter<u32>) {}
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::new(Rc::new(Config::new(Box::new(NoResources))));
+        assert_eq!(session.active_index(), None);
+
+        assert_eq!(session.add_document(Doc::default(), NoBackend::default(), Emitter { inner: 0 }), 0);
+        assert_eq!(session.add_document(Doc::default(), NoBackend::default(), Emitter { inner: 0 }), 1);
+        // the new document is active
+        session.event(1);
+
+        assert!(session.switch_to(0));
+        assert!(!session.switch_to(2));
+        session.event(2);
+        session.mouse_click(Vector2F::zero());
+
+        let (first, _) = session.active().unwrap();
+        assert_eq!(first.events, [2]);
+        assert_eq!(first.clicks, 1);
+
+        let first = session.close_document(0).unwrap();
+        assert_eq!(first.events, [2]);
+        assert_eq!(session.active_index(), Some(0));
+        let (second, _) = session.active().unwrap();
+        assert_eq!(second.events, [1]);
+        assert_eq!(second.clicks, 0);
+
+        session.close_document(0);
+        assert!(session.is_empty());
+        assert_eq!(session.active_index(), None);
+        session.event(3);
+    }
+}
@@ -0,0 +1,52 @@
+//! Stand-ins for the platform parts of a viewer, for tests.
+//!
+//! Built for the unit tests and with the `test-util` feature, which the integration tests enable.
+
+use std::rc::Rc;
+
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_resources::ResourceLoader;
+
+use crate::config::{Config, Icon};
+use crate::context::{Context, ViewBackend};
+
+/// Finds no resources.
+pub struct NoResources;
+impl ResourceLoader for NoResources {
+    fn slurp(&self, path: &str) -> Result<Vec<u8>, std::io::Error> {
+        Err(std::io::Error::new(std::io::ErrorKind::NotFound, path.to_string()))
+    }
+}
+
+/// Records what the viewer asked the platform to do.
+#[derive(Default)]
+pub struct NoBackend {
+    pub opened: Vec<String>,
+    pub tooltip: Option<String>,
+    pub fullscreen: bool,
+    pub clipboard: Option<String>,
+}
+impl ViewBackend for NoBackend {
+    fn resize(&mut self, _size: Vector2F) {}
+    fn get_scroll_factors(&self) -> (Vector2F, Vector2F) {
+        (Vector2F::splat(1.0), Vector2F::splat(1.0))
+    }
+    fn set_icon(&mut self, _icon: Icon) {}
+    fn open_uri(&mut self, uri: &str) {
+        self.opened.push(uri.into());
+    }
+    fn set_tooltip(&mut self, tooltip: Option<String>) {
+        self.tooltip = tooltip;
+    }
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+    fn set_clipboard(&mut self, text: &str) {
+        self.clipboard = Some(text.into());
+    }
+}
+
+/// A context with the default config and a `NoBackend`.
+pub fn test_context() -> Context<NoBackend> {
+    Context::new(Rc::new(Config::new(Box::new(NoResources))), NoBackend::default())
+}
@@ -0,0 +1,64 @@
+use inkrender::{page_bounds, render_page, Cache, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pathfinder_renderer::scene::Scene;
+use pdf::error::PdfError;
+use pdf::object::{Page, Resolve};
+
+/// Size of the page (in mm) as displayed, i.e. with `/Rotate` applied.
+pub fn rotated_page_size(page: &Page) -> Vector2F {
+    let size = page_bounds(page).size();
+    if page.rotate % 180 == 0 {
+        size
+    } else {
+        Vector2F::new(size.y(), size.x())
+    }
+}
+
+/// The scale that makes the longer side of `page` `max_dim` pixels long.
+pub fn thumbnail_scale(page: &Page, max_dim: u32) -> f32 {
+    let size = rotated_page_size(page);
+    max_dim as f32 / size.x().max(size.y())
+}
+
+/// Render `page` so that its longer side is `max_dim` pixels long.
+///
+/// The view box of the scene starts at the origin.
+pub fn render_thumbnail(
+    cache: &mut Cache,
+    resolve: &impl Resolve,
+    page: &Page,
+    max_dim: u32,
+) -> Result<Scene, PdfError> {
+    let transform = Transform2F::from_scale(thumbnail_scale(page, max_dim));
+    let mut backend = SceneBackend::new(cache);
+    render_page(&mut backend, resolve, page, transform)?;
+    Ok(backend.finish())
+}
+
+/// The transform for `render_page` that fits `page` into `width` x `height` pixels,
+/// keeping its aspect ratio, centered.
+pub fn fit_transform(page: &Page, width: u32, height: u32) -> Transform2F {
+    let size = rotated_page_size(page);
+    let target = Vector2F::new(width as f32, height as f32);
+    let scale = (target.x() / size.x()).min(target.y() / size.y());
+    Transform2F::from_translation((target - size * scale) * 0.5) * Transform2F::from_scale(scale)
+}
+
+/// Render `page` into exactly `width` x `height` pixels, letterboxed (see `fit_transform`).
+///
+/// The view box covers all of it, the bars get the background of the rasterizer.
+pub fn render_page_fit(
+    cache: &mut Cache,
+    resolve: &impl Resolve,
+    page: &Page,
+    width: u32,
+    height: u32,
+) -> Result<Scene, PdfError> {
+    let mut backend = SceneBackend::new(cache);
+    render_page(&mut backend, resolve, page, fit_transform(page, width, height))?;
+    let mut scene = backend.finish();
+    scene.set_view_box(RectF::new(Vector2F::zero(), Vector2F::new(width as f32, height as f32)));
+    Ok(scene)
+}
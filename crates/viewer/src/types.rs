@@ -1,7 +1,11 @@
+use std::fmt;
+
 use pathfinder_geometry::vector::Vector2F;
 use pathfinder_renderer::scene::Scene;
+use pdf::error::PdfError;
 
 use crate::context::{Context, ViewBackend};
+use crate::links::LinkTarget;
 
 pub struct Emitter<E> {
     pub inner: E,
@@ -15,6 +19,38 @@ impl<E: Clone> Clone for Emitter<E> {
     }
 }
 
+/// Why a view could not produce its scene.
+#[derive(Debug)]
+pub enum ViewError {
+    /// Loading or rendering the page failed
+    Pdf(PdfError),
+    Other(String),
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ViewError::Pdf(e) => write!(f, "failed to render the page: {}", e),
+            ViewError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for ViewError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ViewError::Pdf(e) => Some(e),
+            ViewError::Other(_) => None,
+        }
+    }
+}
+
+impl From<PdfError> for ViewError {
+    fn from(e: PdfError) -> Self {
+        ViewError::Pdf(e)
+    }
+}
+
 /// Core trait for interactive PDF viewers
 /// Implementations must handle scene rendering and user interactions
 pub trait Interactive: 'static {
@@ -24,6 +60,12 @@ pub trait Interactive: 'static {
     /// Generate the scene to render
     fn scene(&mut self, ctx: &mut Context<Self::Backend>) -> Scene;
 
+    /// Generate the scene to render, or the reason it can't be.
+    /// Viewers that can fail should implement this and let `scene` fall back to an empty page.
+    fn try_scene(&mut self, ctx: &mut Context<Self::Backend>) -> Result<Scene, ViewError> {
+        Ok(self.scene(ctx))
+    }
+
     /// Handle single character input
     fn char_input(&mut self, _ctx: &mut Context<Self::Backend>, _input: char) {}
 
@@ -34,8 +76,27 @@ pub trait Interactive: 'static {
         }
     }
 
-    /// Handle cursor movement
-    fn cursor_moved(&mut self, _ctx: &mut Context<Self::Backend>, _pos: Vector2F) {}
+    /// Handle cursor movement to `pos` (in page space, like `mouse_click`).
+    /// Shows the tooltip of the link or annotation under the cursor by default.
+    fn cursor_moved(&mut self, ctx: &mut Context<Self::Backend>, pos: Vector2F) {
+        ctx.hover(pos);
+    }
+
+    /// Handle a click at `pos` (in page space, see `Context::window_to_page`).
+    /// Follows the link under the cursor by default.
+    fn mouse_click(&mut self, ctx: &mut Context<Self::Backend>, pos: Vector2F) {
+        if let Some(target) = ctx.link_at(pos).cloned() {
+            self.on_link(ctx, target);
+        }
+    }
+
+    /// Follow a link
+    fn on_link(&mut self, ctx: &mut Context<Self::Backend>, target: LinkTarget) {
+        match target {
+            LinkTarget::Page(page) => ctx.goto_page(page),
+            LinkTarget::Uri(uri) => ctx.backend.open_uri(&uri),
+        }
+    }
 
     /// Handle exit/close request
     fn exit(&mut self, _ctx: &mut Context<Self::Backend>) {}
@@ -48,6 +109,18 @@ pub trait Interactive: 'static {
     /// Handle custom events
     fn event(&mut self, _ctx: &mut Context<Self::Backend>, _event: Self::Event) {}
 
+    /// A document with `num_pages` pages was loaded, also the first one.
+    /// Resets the view to its first page by default, viewers that override it should
+    /// drop their per-document caches and call `ctx.load_document` as well.
+    fn on_document_loaded(&mut self, ctx: &mut Context<Self::Backend>, num_pages: usize) {
+        ctx.load_document(num_pages);
+    }
+
+    /// The current document is about to be replaced or closed.
+    fn on_document_unloaded(&mut self, ctx: &mut Context<Self::Backend>) {
+        ctx.unload_document();
+    }
+
     /// Initialize the viewer
     fn init(&mut self, ctx: &mut Context<Self::Backend>, sender: Emitter<Self::Event>);
 
@@ -59,3 +132,105 @@ pub trait Interactive: 'static {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{test_context, NoBackend};
+
+    // fails on pages past the end, like a PDF view with a broken page
+    struct App;
+    impl Interactive for App {
+        type Event = ();
+        type Backend = NoBackend;
+        fn scene(&mut self, ctx: &mut Context<Self::Backend>) -> Scene {
+            self.try_scene(ctx).unwrap_or_else(|_| Scene::new())
+        }
+        fn try_scene(&mut self, ctx: &mut Context<Self::Backend>) -> Result<Scene, ViewError> {
+            if ctx.page_nr() >= ctx.num_pages {
+                return Err(PdfError::PageOutOfBounds { page_nr: ctx.page_nr() as u32, max: ctx.num_pages as u32 }.into());
+            }
+            Ok(Scene::new())
+        }
+        fn init(&mut self, _ctx: &mut Context<Self::Backend>, _sender: Emitter<()>) {}
+    }
+
+    struct Plain;
+    impl Interactive for Plain {
+        type Event = ();
+        type Backend = NoBackend;
+        fn scene(&mut self, _ctx: &mut Context<Self::Backend>) -> Scene {
+            Scene::new()
+        }
+        fn init(&mut self, _ctx: &mut Context<Self::Backend>, _sender: Emitter<()>) {}
+    }
+
+    #[test]
+    fn test_try_scene() {
+        let mut ctx = test_context();
+        ctx.num_pages = 0;
+        match App.try_scene(&mut ctx) {
+            Err(ViewError::Pdf(PdfError::PageOutOfBounds { page_nr: 0, max: 0 })) => {}
+            other => panic!("expected a render error, got {:?}", other.map(|_| ())),
+        }
+        // scene still gives an (empty) page
+        App.scene(&mut ctx);
+
+        ctx.num_pages = 1;
+        assert!(App.try_scene(&mut ctx).is_ok());
+        // the default forwards to scene
+        assert!(Plain.try_scene(&mut ctx).is_ok());
+    }
+
+    // counts the hooks, keeps the default behavior
+    #[derive(Default)]
+    struct Documents {
+        loaded: Vec<usize>,
+        unloaded: usize,
+    }
+    impl Interactive for Documents {
+        type Event = ();
+        type Backend = NoBackend;
+        fn scene(&mut self, _ctx: &mut Context<Self::Backend>) -> Scene {
+            Scene::new()
+        }
+        fn on_document_loaded(&mut self, ctx: &mut Context<Self::Backend>, num_pages: usize) {
+            self.loaded.push(num_pages);
+            ctx.load_document(num_pages);
+        }
+        fn on_document_unloaded(&mut self, ctx: &mut Context<Self::Backend>) {
+            self.unloaded += 1;
+            ctx.unload_document();
+        }
+        fn init(&mut self, _ctx: &mut Context<Self::Backend>, _sender: Emitter<()>) {}
+    }
+
+    #[test]
+    fn test_document_lifecycle() {
+        let mut ctx = test_context();
+        let mut app = Documents::default();
+        app.on_document_loaded(&mut ctx, 3);
+        ctx.goto_page(2);
+        ctx.set_tooltips(vec![crate::links::Tooltip {
+            rect: pathfinder_geometry::rect::RectF::new(Vector2F::zero(), Vector2F::splat(10.0)),
+            text: "note".into(),
+        }]);
+        assert_eq!(ctx.page_nr(), 2);
+
+        // a second document replaces the first
+        app.on_document_unloaded(&mut ctx);
+        app.on_document_loaded(&mut ctx, 5);
+        assert_eq!(app.loaded, [3, 5]);
+        assert_eq!(app.unloaded, 1);
+        assert_eq!(ctx.page_nr(), 0);
+        assert_eq!(ctx.num_pages, 5);
+        assert!(ctx.tooltips.is_empty());
+
+        // the defaults do the same
+        Plain.on_document_loaded(&mut ctx, 2);
+        ctx.next_page();
+        Plain.on_document_unloaded(&mut ctx);
+        Plain.on_document_loaded(&mut ctx, 4);
+        assert_eq!((ctx.page_nr(), ctx.num_pages), (0, 4));
+    }
+}
@@ -0,0 +1,42 @@
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::transform2d::Transform2F;
+use pathfinder_geometry::vector::Vector2F;
+use pdf::file::FileOptions;
+use viewer::test_util::test_context;
+use viewer::thumbnail::rotated_page_size;
+use viewer::InitialFit;
+
+const SCALE: f32 = 25.4 / 72.0;
+
+#[test]
+fn test_fit_content() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/corner_content.pdf");
+    let file = FileOptions::cached().open(path).unwrap();
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    // a letter page with a single 100 pt square at [400 600 500 700]
+    let mut cache = Cache::new();
+    let mut backend = SceneBackend::new(&mut cache);
+    render_page(&mut backend, &resolver, &page, Transform2F::default()).unwrap();
+    let bounds = backend.content_bounds().unwrap();
+    let expected = RectF::new(Vector2F::new(400.0, 92.0), Vector2F::splat(100.0)) * SCALE;
+    assert!(
+        (bounds.origin() - expected.origin()).length() < 0.1 && (bounds.size() - expected.size()).length() < 0.1,
+        "{:?} != {:?}", bounds, expected
+    );
+
+    let mut ctx = test_context();
+    ctx.set_window_size(Vector2F::new(800.0, 600.0));
+    ctx.set_bounds(RectF::new(Vector2F::zero(), rotated_page_size(&page)));
+    let fit_page = ctx.fit_scale(InitialFit::Page).unwrap();
+
+    ctx.set_content_bounds(Some(bounds));
+    ctx.fit_content();
+    assert!(ctx.scale > 2.0 * fit_page, "{} vs {}", ctx.scale, fit_page);
+    // the square fills the window height
+    let visible = ctx.visible_page_rect();
+    assert!((visible.center() - bounds.center()).length() < 0.1, "{:?} {:?}", visible, bounds);
+    assert!((visible.height() - bounds.height()).abs() < 0.1, "{:?} {:?}", visible, bounds);
+}
@@ -0,0 +1,35 @@
+use std::cell::Cell;
+
+use pdf::file::FileOptions;
+use viewer::PageCache;
+
+#[test]
+fn test_page_cache() {
+    // two letter pages
+    let file = FileOptions::cached().open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/search.pdf")).unwrap();
+    let lookups = Cell::new(0);
+    let get_page = |nr| {
+        lookups.set(lookups.get() + 1);
+        file.get_page(nr)
+    };
+
+    let mut pages = PageCache::new();
+    let bounds = pages.bounds(0, get_page).unwrap();
+    assert_eq!(pages.bounds(0, get_page).unwrap(), bounds);
+    let (page, _) = pages.get(0, get_page).unwrap();
+    assert_eq!(lookups.get(), 1);
+    assert_eq!(inkrender::page_bounds(&page), bounds);
+    assert!((bounds.width() - 612.0 * 25.4 / 72.0).abs() < 1e-3, "{:?}", bounds);
+
+    pages.bounds(1, get_page).unwrap();
+    assert_eq!(lookups.get(), 2);
+    // errors are not kept
+    assert!(pages.bounds(5, get_page).is_err());
+    assert!(pages.bounds(5, get_page).is_err());
+    assert_eq!(lookups.get(), 4);
+
+    // a new document
+    pages.clear();
+    pages.bounds(0, get_page).unwrap();
+    assert_eq!(lookups.get(), 5);
+}
@@ -0,0 +1,55 @@
+use inkrender::{render_page, Cache, SceneBackend};
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use pdf::file::FileOptions;
+use viewer::test_util::test_context;
+use viewer::thumbnail::rotated_page_size;
+use viewer::{page_space_links, LinkTarget};
+
+const SCALE: f32 = 25.4 / 72.0;
+
+#[test]
+fn test_rotated_links() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/rotated_links.pdf");
+    let file = FileOptions::cached().open(path).unwrap();
+    let resolver = file.resolver();
+    let mut cache = Cache::new();
+
+    // 100 x 200 pt pages with a link at [10 20 30 60], rotated clockwise by /Rotate
+    let cases = [
+        (0, RectF::from_points(Vector2F::new(10.0, 140.0), Vector2F::new(30.0, 180.0))),
+        (90, RectF::from_points(Vector2F::new(20.0, 10.0), Vector2F::new(60.0, 30.0))),
+        (180, RectF::from_points(Vector2F::new(70.0, 20.0), Vector2F::new(90.0, 60.0))),
+        (270, RectF::from_points(Vector2F::new(140.0, 70.0), Vector2F::new(180.0, 90.0))),
+    ];
+    for (nr, (rotate, expected)) in cases.into_iter().enumerate() {
+        let page = file.get_page(nr as u32).unwrap();
+        assert_eq!(page.rotate, rotate);
+
+        let links = page_space_links(&page, &resolver, |_| None);
+        assert_eq!(links.len(), 1);
+        let rect = links[0].rect;
+        let expected = expected * SCALE;
+        assert!(
+            (rect.origin() - expected.origin()).length() < 1e-3 && (rect.size() - expected.size()).length() < 1e-3,
+            "{}: {:?} != {:?}", rotate, rect, expected
+        );
+
+        let mut ctx = test_context();
+        ctx.set_window_size(Vector2F::new(800.0, 600.0));
+        ctx.set_bounds(RectF::new(Vector2F::zero(), rotated_page_size(&page)));
+        ctx.set_zoom(2.0);
+        ctx.set_links(links);
+
+        // where the center of the link ends up in the rendered scene
+        let mut backend = SceneBackend::new(&mut cache);
+        let root = render_page(&mut backend, &resolver, &page, ctx.view_transform()).unwrap();
+        let click = root * Vector2F::new(20.0, 40.0);
+        let target = LinkTarget::Uri(format!("https://example.com/{}", rotate));
+        assert_eq!(ctx.link_at(ctx.window_to_page(click)), Some(&target), "{}", rotate);
+
+        // just outside of the link, 5pt above its top edge
+        let miss = root * Vector2F::new(20.0, 65.0);
+        assert_eq!(ctx.link_at(ctx.window_to_page(miss)), None, "{}", rotate);
+    }
+}
@@ -0,0 +1,34 @@
+use pdf::file::FileOptions;
+use viewer::{document_text, search_document, SearchOptions};
+
+#[test]
+fn test_search_document() {
+    // page 1: "The quick brown fox" / "Foxes are foxy", page 2: "A fox again"
+    let file = FileOptions::cached().open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/search.pdf")).unwrap();
+
+    let results = search_document(&file, "fox", SearchOptions::default());
+    assert_eq!(results.len(), 4);
+    assert_eq!(results.iter().map(|r| r.page).collect::<Vec<_>>(), [0, 0, 0, 1]);
+    for result in &results {
+        assert!(result.snippet.to_lowercase().contains("fox"), "{:?}", result.snippet);
+        assert_eq!(result.quads.len(), 1);
+        assert!(result.quads[0].width() > 0.0);
+    }
+    assert!(results[0].snippet.contains("brown fox"));
+
+    let whole_word = SearchOptions { whole_word: true, ..SearchOptions::default() };
+    assert_eq!(search_document(&file, "fox", whole_word).len(), 2);
+
+    let case_sensitive = SearchOptions { case_sensitive: true, ..SearchOptions::default() };
+    assert_eq!(search_document(&file, "fox", case_sensitive).len(), 3);
+    assert_eq!(search_document(&file, "Fox", case_sensitive).len(), 1);
+
+    assert!(search_document(&file, "wolf", SearchOptions::default()).is_empty());
+}
+
+#[test]
+fn test_document_text() {
+    let file = FileOptions::cached().open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/search.pdf")).unwrap();
+    let text = document_text(&file);
+    assert_eq!(text, "The quick brown fox\nFoxes are foxy\n\nA fox again");
+}
@@ -0,0 +1,43 @@
+use inkrender::Cache;
+use pdf::file::FileOptions;
+use pathfinder_geometry::rect::RectF;
+use pathfinder_geometry::vector::Vector2F;
+use viewer::{fit_transform, render_page_fit, render_thumbnail};
+
+#[test]
+fn test_landscape_thumbnail() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/landscape.pdf");
+    let file = FileOptions::cached().open(path).unwrap();
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let scene = render_thumbnail(&mut cache, &resolver, &page, 200).unwrap();
+    let view_box = scene.view_box();
+
+    // 792 x 612 pt
+    assert!((view_box.width() - 200.0).abs() < 1e-3);
+    assert!((view_box.height() - 200.0 * 612.0 / 792.0).abs() < 1e-3);
+    assert_eq!(view_box.origin(), Vector2F::zero());
+}
+
+#[test]
+fn test_render_page_fit() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/landscape.pdf");
+    let file = FileOptions::cached().open(path).unwrap();
+    let resolver = file.resolver();
+    let page = file.get_page(0).unwrap();
+
+    let mut cache = Cache::new();
+    let scene = render_page_fit(&mut cache, &resolver, &page, 200, 260).unwrap();
+    assert_eq!(scene.view_box(), RectF::new(Vector2F::zero(), Vector2F::new(200.0, 260.0)));
+
+    // the width fills the box, bars above and below
+    let size = Vector2F::new(792.0, 612.0) * (25.4 / 72.0);
+    let placed = fit_transform(&page, 200, 260) * RectF::new(Vector2F::zero(), size);
+    let height = 200.0 * 612.0 / 792.0;
+    assert!((placed.width() - 200.0).abs() < 1e-3, "{:?}", placed);
+    assert!((placed.height() - height).abs() < 1e-3, "{:?}", placed);
+    assert!(placed.min_x().abs() < 1e-3);
+    assert!((placed.min_y() - 0.5 * (260.0 - height)).abs() < 1e-3, "{:?}", placed);
+}
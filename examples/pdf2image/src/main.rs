@@ -1,7 +1,7 @@
 use argh::FromArgs;
 use pdf::file::{File, FileOptions};
 use pdf_render::{Cache, SceneBackend, render_page};
-use rasterize::Rasterizer;
+use rasterize::{ClearMode, Rasterizer};
 use pathfinder_geometry::transform2d::Transform2F;
 use std::error::Error;
 
@@ -40,7 +40,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     render_page(&mut backend, &resolver, &page, Transform2F::from_scale(opt.dpi / 25.4))?;
 
-    let image = Rasterizer::new().rasterize(backend.finish(), None);
+    let image = Rasterizer::new().rasterize(backend.finish(), ClearMode::OpaqueWhite);
 
     image.save(opt.image)?;
 
@@ -1,6 +1,6 @@
 use pathfinder_svg::SVGScene;
 use usvg::{Tree, Options};
-use rasterize::Rasterizer;
+use rasterize::{ClearMode, Rasterizer};
 
 fn main() {
     let mut args = std::env::args();
@@ -13,6 +13,6 @@ fn main() {
     let tree = Tree::from_data(&input_data, &Options::default()).unwrap();
     let scene = SVGScene::from_tree(&tree).scene;
 
-    let image = Rasterizer::new().rasterize(scene, None);
+    let image = Rasterizer::new().rasterize(scene, ClearMode::OpaqueWhite);
     image.save(&output).unwrap();
 }
@@ -9,6 +9,17 @@ use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::renderer::PdfRenderer;
+use crate::scroll::{PageImageCache, ScrollLayout};
+use viewer::DEFAULT_PAGE_GAP;
+
+/// Resolution of rendered pages at 100% zoom
+const RENDER_DPI: f32 = 150.0;
+/// Logical pixels per page unit (mm) at 100% zoom
+const DISPLAY_SCALE: f32 = 96.0 / 25.4;
+/// Pages this far outside the viewport are rendered ahead of time
+const PRERENDER_MARGIN: f32 = 400.0;
+/// Number of page images kept around in continuous mode
+const PAGE_IMAGE_CACHE_SIZE: usize = 16;
 
 /// Main PDF Viewer Application State
 pub struct PdfViewerApp {
@@ -28,6 +39,14 @@ pub struct PdfViewerApp {
     focus_handle: FocusHandle,
     /// Cached rendered page image path
     current_page_image: Option<Arc<std::path::Path>>,
+    /// Show all pages stacked vertically instead of one at a time
+    continuous: bool,
+    /// Page positions in continuous mode
+    scroll_layout: ScrollLayout,
+    /// Scroll position of the page container in continuous mode
+    scroll_handle: ScrollHandle,
+    /// Rendered pages for continuous mode
+    page_images: PageImageCache,
 }
 
 impl PdfViewerApp {
@@ -42,6 +61,10 @@ impl PdfViewerApp {
             error_message: None,
             focus_handle: cx.focus_handle(),
             current_page_image: None,
+            continuous: false,
+            scroll_layout: ScrollLayout::default(),
+            scroll_handle: ScrollHandle::new(),
+            page_images: PageImageCache::new(PAGE_IMAGE_CACHE_SIZE),
         }
     }
 
@@ -58,6 +81,8 @@ impl PdfViewerApp {
                 self.current_page = 0;
                 self.num_pages = num_pages;
                 self.current_page_image = None;
+                self.page_images.clear();
+                self.update_scroll_layout();
 
                 log::info!("✓ PDF loaded successfully with {} pages", num_pages);
 
@@ -76,41 +101,52 @@ impl PdfViewerApp {
         }
     }
 
+    /// Render a page to a temporary PNG file
+    fn render_page_image(
+        renderer: &mut PdfRenderer,
+        page_num: usize,
+        zoom: f32,
+    ) -> Result<Arc<std::path::Path>, String> {
+        log::info!("Rendering page {} with zoom {}...", page_num, zoom);
+
+        let dpi = RENDER_DPI * zoom;
+
+        // Render the page (this might take time for complex PDFs)
+        let image = renderer
+            .render_page_to_image(page_num, dpi)
+            .map_err(|e| format!("Failed to render page: {}", e))?;
+
+        let temp_path = std::env::temp_dir().join(format!(
+            "inkstone_page_{}_{}.png",
+            page_num,
+            (zoom * 100.0) as i32
+        ));
+
+        // Save the image
+        image
+            .save(&temp_path)
+            .map_err(|e| format!("Failed to save page: {}", e))?;
+
+        log::info!("✓ Page rendered to: {:?}", temp_path);
+        Ok(temp_path.into())
+    }
+
     /// Render the current page synchronously
     fn render_current_page(&mut self, cx: &mut Context<Self>) {
+        if self.continuous {
+            // pages are rendered as they are scrolled into view
+            cx.notify();
+            return;
+        }
         if let Some(renderer) = &mut self.pdf_renderer {
-            let page_num = self.current_page;
-            let zoom = self.zoom_level;
-
-            log::info!("Rendering page {} with zoom {}...", page_num, zoom);
-
-            let dpi = 150.0 * zoom;
-
-            // Render the page (this might take time for complex PDFs)
-            match renderer.render_page_to_image(page_num, dpi) {
-                Ok(image) => {
-                    let temp_path = std::env::temp_dir().join(format!(
-                        "inkstone_page_{}_{}.png",
-                        page_num,
-                        (zoom * 100.0) as i32
-                    ));
-
-                    // Save the image
-                    match image.save(&temp_path) {
-                        Ok(_) => {
-                            log::info!("✓ Page rendered to: {:?}", temp_path);
-                            self.current_page_image = Some(temp_path.into());
-                            self.error_message = None;
-                        }
-                        Err(e) => {
-                            log::error!("Failed to save rendered page: {}", e);
-                            self.error_message = Some(format!("Failed to save page: {}", e));
-                        }
-                    }
+            match Self::render_page_image(renderer, self.current_page, self.zoom_level) {
+                Ok(path) => {
+                    self.current_page_image = Some(path);
+                    self.error_message = None;
                 }
                 Err(e) => {
-                    log::error!("Failed to render page: {}", e);
-                    self.error_message = Some(format!("Failed to render page: {}", e));
+                    log::error!("{}", e);
+                    self.error_message = Some(e);
                 }
             }
 
@@ -118,11 +154,112 @@ impl PdfViewerApp {
         }
     }
 
+    /// Recompute the page positions for continuous mode
+    fn update_scroll_layout(&mut self) {
+        let heights = match &mut self.pdf_renderer {
+            Some(renderer) => (0..self.num_pages)
+                .map(|page| {
+                    let height = renderer.page_bounds(page).map(|r| r.height()).unwrap_or(0.0);
+                    height * DISPLAY_SCALE * self.zoom_level
+                })
+                .collect(),
+            None => vec![],
+        };
+        self.scroll_layout = ScrollLayout::new(heights, DEFAULT_PAGE_GAP);
+    }
+
+    /// Switch between single page and continuous scroll mode
+    pub fn toggle_continuous(&mut self, cx: &mut Context<Self>) {
+        self.continuous = !self.continuous;
+        if self.continuous {
+            self.scroll_to_page(self.current_page);
+        } else {
+            self.current_page_image = None;
+        }
+        self.render_current_page(cx);
+    }
+
+    /// Whether all pages are shown in a scrollable column
+    pub fn is_continuous(&self) -> bool {
+        self.continuous
+    }
+
+    fn scroll_to_page(&mut self, page: usize) {
+        let y = self.scroll_layout.page_offset(page);
+        self.scroll_handle.set_offset(point(px(0.0), px(-y)));
+    }
+
+    /// Track the current page and start rendering the pages near the viewport.
+    ///
+    /// The rasterizer worker renders them in the background, they show a placeholder
+    /// until their image is in `page_images`.
+    fn update_visible_pages(&mut self, cx: &mut Context<Self>) {
+        let Some(renderer) = &mut self.pdf_renderer else {
+            return;
+        };
+        let scroll_y = -f32::from(self.scroll_handle.offset().y);
+        let viewport_height = f32::from(self.scroll_handle.bounds().size.height);
+        if self.scroll_layout.num_pages() > 0 {
+            self.current_page = self.scroll_layout.current_page(scroll_y, viewport_height);
+        }
+
+        let zoom = self.zoom_level;
+        for page in self.scroll_layout.visible_pages(scroll_y, viewport_height, PRERENDER_MARGIN) {
+            if self.page_images.get(page, zoom).is_some() {
+                continue;
+            }
+            // pages that fail stay marked, so they are not tried again on every frame
+            let Some(ticket) = self.page_images.start(page, zoom) else {
+                continue;
+            };
+            let render = match renderer.render_page_to_image_async(page, RENDER_DPI * zoom) {
+                Ok(render) => render,
+                Err(e) => {
+                    log::error!("{}", e);
+                    continue;
+                }
+            };
+            // one file per render, a render of the previous file may still be saving
+            let path = std::env::temp_dir().join(format!(
+                "inkstone_page_{}_{}_{}.png",
+                page,
+                (zoom * 100.0) as i32,
+                ticket
+            ));
+            cx.spawn(async move |this, cx| {
+                let saved = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let image = render
+                            .await
+                            .map_err(|e| format!("Failed to rasterize page: {}", e))?;
+                        image
+                            .save(&path)
+                            .map_err(|e| format!("Failed to save page: {}", e))?;
+                        Ok::<_, String>(path)
+                    })
+                    .await;
+
+                _ = this.update(cx, |view, cx| {
+                    match saved {
+                        Ok(path) => view.page_images.finish(page, zoom, ticket, path.into()),
+                        Err(e) => log::error!("{}", e),
+                    }
+                    cx.notify();
+                });
+            })
+            .detach();
+        }
+    }
+
     /// Navigate to next page
     pub fn next_page(&mut self, cx: &mut Context<Self>) {
         if self.current_page + 1 < self.num_pages {
             self.current_page += 1;
             self.current_page_image = None;
+            if self.continuous {
+                self.scroll_to_page(self.current_page);
+            }
             self.render_current_page(cx);
             cx.notify();
         }
@@ -133,6 +270,9 @@ impl PdfViewerApp {
         if self.current_page > 0 {
             self.current_page -= 1;
             self.current_page_image = None;
+            if self.continuous {
+                self.scroll_to_page(self.current_page);
+            }
             self.render_current_page(cx);
             cx.notify();
         }
@@ -143,6 +283,9 @@ impl PdfViewerApp {
         if page < self.num_pages && page != self.current_page {
             self.current_page = page;
             self.current_page_image = None;
+            if self.continuous {
+                self.scroll_to_page(page);
+            }
             self.render_current_page(cx);
             cx.notify();
         }
@@ -152,6 +295,7 @@ impl PdfViewerApp {
     pub fn zoom_in(&mut self, cx: &mut Context<Self>) {
         self.zoom_level *= 1.2;
         self.current_page_image = None;
+        self.update_scroll_layout();
         self.render_current_page(cx);
         cx.notify();
     }
@@ -160,6 +304,7 @@ impl PdfViewerApp {
     pub fn zoom_out(&mut self, cx: &mut Context<Self>) {
         self.zoom_level /= 1.2;
         self.current_page_image = None;
+        self.update_scroll_layout();
         self.render_current_page(cx);
         cx.notify();
     }
@@ -169,6 +314,7 @@ impl PdfViewerApp {
         if self.zoom_level != 1.0 {
             self.zoom_level = 1.0;
             self.current_page_image = None;
+            self.update_scroll_layout();
             self.render_current_page(cx);
             cx.notify();
         }
@@ -232,6 +378,9 @@ impl PdfViewerApp {
 impl Render for PdfViewerApp {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let focus_handle = self.focus_handle.clone();
+        if self.continuous {
+            self.update_visible_pages(cx);
+        }
 
         v_flex()
             .size_full()
@@ -253,6 +402,8 @@ impl Render for PdfViewerApp {
                     this.zoom_out(cx);
                 } else if event.keystroke.key == "0" && event.keystroke.modifiers.platform {
                     this.reset_zoom(cx);
+                } else if event.keystroke.key == "c" {
+                    this.toggle_continuous(cx);
                 }
             }))
             .child(self.render_toolbar(cx))
@@ -339,6 +490,13 @@ impl PdfViewerApp {
                                 this.zoom_in(cx);
                             },
                         )))
+                        .child(
+                            Button::new("continuous")
+                                .label(if self.continuous { "Single page" } else { "Continuous" })
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.toggle_continuous(cx);
+                                })),
+                        )
                         .child(
                             Button::new("zoom-reset")
                                 .label("100%")
@@ -350,8 +508,39 @@ impl PdfViewerApp {
             )
     }
 
+    /// Render all pages in a vertically scrolling column
+    fn render_continuous(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let layout = &self.scroll_layout;
+        div()
+            .id("pages")
+            .flex_1()
+            .w_full()
+            .overflow_y_scroll()
+            .track_scroll(&self.scroll_handle)
+            // re-render after scrolling to pick up the new position
+            .on_scroll_wheel(cx.listener(|_, _: &ScrollWheelEvent, _, cx| cx.notify()))
+            .bg(gpui::rgb(0x252526))
+            .child(
+                v_flex()
+                    .items_center()
+                    .w_full()
+                    .gap(px(layout.gap()))
+                    .children((0..layout.num_pages()).map(|page| {
+                        let height = px(layout.page_height(page));
+                        match self.page_images.peek(page, self.zoom_level) {
+                            Some(path) => div().h(height).child(img(path).h(height)),
+                            // placeholder until the page is rendered
+                            None => div().h(height).w(px(600.0)).bg(gpui::rgb(0x3e3e42)),
+                        }
+                    })),
+            )
+    }
+
     /// Render the main content area
-    fn render_main_content(&self, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render_main_content(&self, cx: &mut Context<Self>) -> AnyElement {
+        if self.continuous && self.error_message.is_none() && self.has_pdf() {
+            return self.render_continuous(cx).into_any_element();
+        }
         v_flex()
             .flex_1()
             .items_center()
@@ -450,9 +639,15 @@ impl PdfViewerApp {
                                 Label::new("• Cmd+0 - Reset zoom")
                                     .text_sm()
                                     .text_color(gpui::rgb(0x606060)),
+                            )
+                            .child(
+                                Label::new("• C - Toggle continuous scrolling")
+                                    .text_sm()
+                                    .text_color(gpui::rgb(0x606060)),
                             ),
                     )
             })
+            .into_any_element()
     }
 
     /// Render the status bar
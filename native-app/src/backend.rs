@@ -8,6 +8,9 @@ pub struct GpuiBackend {
     pixel_scroll_factor: Vector2F,
     line_scroll_factor: Vector2F,
     icon: Option<Icon>,
+    tooltip: Option<String>,
+    fullscreen: bool,
+    clipboard: Option<String>,
 }
 
 impl GpuiBackend {
@@ -16,6 +19,31 @@ impl GpuiBackend {
             pixel_scroll_factor: Vector2F::splat(1.0),
             line_scroll_factor: Vector2F::splat(10.0),
             icon: None,
+            tooltip: None,
+            fullscreen: false,
+            clipboard: None,
+        }
+    }
+
+    /// The tooltip of the link or annotation under the cursor, for the window to show.
+    pub fn tooltip(&self) -> Option<&str> {
+        self.tooltip.as_deref()
+    }
+
+    /// Whether the window should be fullscreen, see `apply_fullscreen`.
+    pub fn fullscreen(&self) -> bool {
+        self.fullscreen
+    }
+
+    /// Text the viewer copied since the last call, for the window to put on the clipboard.
+    pub fn take_clipboard(&mut self) -> Option<String> {
+        self.clipboard.take()
+    }
+
+    /// Bring `window` into the fullscreen state asked for by the viewer.
+    pub fn apply_fullscreen(&self, window: &mut Window) {
+        if window.is_fullscreen() != self.fullscreen {
+            window.toggle_fullscreen();
         }
     }
 }
@@ -35,6 +63,31 @@ impl ViewBackend for GpuiBackend {
         // Note: GPUI 0.2 window icon setting might need window handle
         // For now, we just store it
     }
+
+    fn open_uri(&mut self, uri: &str) {
+        #[cfg(target_os = "macos")]
+        let opener = "open";
+        #[cfg(target_os = "windows")]
+        let opener = "explorer";
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        let opener = "xdg-open";
+
+        if let Err(e) = std::process::Command::new(opener).arg(uri).spawn() {
+            log::warn!("failed to open {}: {}", uri, e);
+        }
+    }
+
+    fn set_tooltip(&mut self, tooltip: Option<String>) {
+        self.tooltip = tooltip;
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = fullscreen;
+    }
+
+    fn set_clipboard(&mut self, text: &str) {
+        self.clipboard = Some(text.into());
+    }
 }
 
 impl Default for GpuiBackend {
@@ -1,5 +1,6 @@
 pub mod app;
 pub mod renderer;
+pub mod scroll;
 
 pub use app::PdfViewerApp;
 pub use renderer::PdfRenderer;
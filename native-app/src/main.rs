@@ -3,6 +3,7 @@ use gpui_component::Root;
 
 mod app;
 mod renderer;
+mod scroll;
 
 use app::PdfViewerApp;
 
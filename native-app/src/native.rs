@@ -1,15 +1,16 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use log::info;
-use pathfinder_geometry::vector::Vector2F;
 use pathfinder_renderer::scene::Scene;
 use pdf::any::AnySync;
 use pdf::backend::Backend;
 use pdf::error::PdfError;
 use pdf::file::{ Cache as PdfCache, File as PdfFile, Log };
-use inkrender::{ page_bounds, render_page, Cache, SceneBackend };
+use pdf::object::PlainRef;
+use inkrender::{ page_bounds, render_file_pages, Cache };
 
-use viewer::{ Context, Emitter, Interactive, ViewBackend };
+use viewer::{ page_space_links, page_space_tooltips, Context, Emitter, Interactive, ViewBackend, ViewError };
 use crate::backend::GpuiBackend;
 
 /// PDF viewer implementation that works with any backend
@@ -17,6 +18,8 @@ pub struct PdfView<B: Backend, OC, SC, L> {
     file: PdfFile<B, OC, SC, L>,
     num_pages: usize,
     cache: Cache,
+    // link destinations refer to pages by reference
+    page_refs: HashMap<PlainRef, usize>,
 }
 
 impl<B, OC, SC, L> PdfView<B, OC, SC, L>
@@ -27,10 +30,16 @@ impl<B, OC, SC, L> PdfView<B, OC, SC, L>
         L: Log
 {
     pub fn new(file: PdfFile<B, OC, SC, L>) -> Self {
+        let page_refs = file
+            .pages()
+            .enumerate()
+            .filter_map(|(i, page)| Some((page.ok()?.get_ref().get_inner(), i)))
+            .collect();
         PdfView {
             num_pages: file.num_pages() as usize,
             file,
             cache: Cache::new(),
+            page_refs,
         }
     }
 
@@ -63,7 +72,7 @@ impl<B, OC, SC, L> Interactive
     }
 
     fn init(&mut self, ctx: &mut Context<Self::Backend>, _sender: Emitter<Self::Event>) {
-        ctx.num_pages = self.num_pages;
+        self.on_document_loaded(ctx, self.num_pages);
 
         // Set icon if logo is available
         if
@@ -77,19 +86,27 @@ impl<B, OC, SC, L> Interactive
     }
 
     fn scene(&mut self, ctx: &mut Context<Self::Backend>) -> Scene {
-        info!("drawing page {}", ctx.page_nr());
+        self.try_scene(ctx).unwrap_or_else(|e| {
+            log::error!("{}", e);
+            Scene::new()
+        })
+    }
 
-        let page = self.file.get_page(ctx.page_nr as u32).unwrap();
+    fn try_scene(&mut self, ctx: &mut Context<Self::Backend>) -> Result<Scene, ViewError> {
+        info!("drawing page {}", ctx.page_nr());
 
-        ctx.set_bounds(page_bounds(&page));
+        let nr = ctx.page_nr as u32;
+        let scene = render_file_pages(&self.file, &mut self.cache, nr..nr + 1, |page| {
+            ctx.set_bounds(page_bounds(page));
+            ctx.view_transform()
+        })
+            .next()
+            .ok_or_else(|| ViewError::Other(format!("page {} out of range", nr)))??;
 
-        let mut backend = SceneBackend::new(&mut self.cache);
+        let page = self.file.get_page(nr)?;
         let resolver = self.file.resolver();
-        render_page(&mut backend, &resolver, &page, ctx.view_transform()).unwrap();
-        backend.finish()
-    }
-
-    fn cursor_moved(&mut self, _ctx: &mut Context<Self::Backend>, pos: Vector2F) {
-        // Can be implemented for hover effects
+        ctx.set_links(page_space_links(&page, &resolver, |r| self.page_refs.get(&r).copied()));
+        ctx.set_tooltips(page_space_tooltips(&page, &resolver));
+        Ok(scene)
     }
 }
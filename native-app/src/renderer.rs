@@ -11,8 +11,9 @@ use pdf::file::{ File as PdfFile, FileOptions, NoLog, SyncCache };
 use pdf::object::PlainRef;
 use image::RgbaImage;
 
-use inkrender::{ page_bounds, render_page, Cache as RenderCache, SceneBackend };
-use rasterize::Rasterizer;
+use inkrender::{ load_attachments, render_file_pages, render_page, Attachment, Cache as RenderCache, FormDefaults, LayerId, LayerInfo, Layers, SceneBackend, StructNode, TextLine, Word, page_lines, page_warnings, page_words };
+use rasterize::{ RasterizeBackend, RasterizeWorker, RenderFuture };
+use viewer::{ render_page_fit, PageCache };
 
 type PdfFileType = PdfFile<
     Vec<u8>,
@@ -26,6 +27,11 @@ pub struct PdfRenderer {
     file: Arc<PdfFileType>,
     num_pages: usize,
     cache: RenderCache,
+    layers: Layers,
+    // pages and their bounds, looked up once
+    pages: PageCache,
+    // started on the first render to an image
+    rasterizer: Option<RasterizeWorker>,
 }
 
 impl PdfRenderer {
@@ -38,13 +44,65 @@ impl PdfRenderer {
 
         let num_pages = file.num_pages() as usize;
 
+        let layers = Layers::load(file.trailer.root.get_ref().get_inner(), &file.resolver())
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read layers: {}", e);
+                Layers::default()
+            });
+        let mut cache = RenderCache::new();
+        cache.set_hidden_layers(layers.hidden());
+
         Ok(Self {
             file: Arc::new(file),
             num_pages,
-            cache: RenderCache::new(),
+            cache,
+            layers,
+            pages: PageCache::new(),
+            rasterizer: None,
         })
     }
 
+    /// The optional content groups (layers) of the document
+    pub fn layers(&self) -> Vec<LayerInfo> {
+        self.layers.layers().to_vec()
+    }
+
+    /// Show or hide a layer, by name or reference. Returns false if there is no such layer.
+    pub fn set_layer_visible<'a>(&mut self, layer: impl Into<LayerId<'a>>, visible: bool) -> bool {
+        let found = self.layers.set_visible(layer, visible);
+        self.cache.set_hidden_layers(self.layers.hidden());
+        found
+    }
+
+    /// Draw the values of form fields as page content from now on, e.g. before printing.
+    /// Fields that have an appearance stream look the same either way.
+    pub fn flatten_forms(&mut self) {
+        let form = FormDefaults::load(self.file.trailer.root.get_ref().get_inner(), &self.file.resolver())
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read the form: {}", e);
+                FormDefaults::default()
+            });
+        self.cache.set_flatten_forms(Some(form));
+    }
+
+    /// Files embedded in the document, empty if they can't be read
+    pub fn attachments(&self) -> Vec<Attachment> {
+        load_attachments(self.file.trailer.root.get_ref().get_inner(), &self.file.resolver())
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read attachments: {}", e);
+                vec![]
+            })
+    }
+
+    /// The logical structure (headings, paragraphs, lists ...) of a tagged document
+    pub fn structure_tree(&self) -> Option<StructNode> {
+        StructNode::load(self.file.trailer.root.get_ref().get_inner(), &self.file.resolver())
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read the structure tree: {}", e);
+                None
+            })
+    }
+
     /// Get the total number of pages
     pub fn num_pages(&self) -> usize {
         self.num_pages
@@ -60,23 +118,49 @@ impl PdfRenderer {
             return Err(format!("Page {} out of range (total pages: {})", page_num, self.num_pages));
         }
 
-        // Get the page
-        let page = self.file
-            .get_page(page_num as u32)
-            .map_err(|e| format!("Failed to get page: {}", e))?;
+        let nr = page_num as u32;
+        render_file_pages(&*self.file, &mut self.cache, nr..nr + 1, |_| transform)
+            .next()
+            .ok_or_else(|| format!("Page {} out of range (total pages: {})", page_num, self.num_pages))?
+            .map_err(|e| format!("Failed to render page: {}", e))
+    }
 
-        // Create a scene backend
-        let mut backend = SceneBackend::new(&mut self.cache);
+    /// The words of a page with their rects in page space (mm, y down)
+    pub fn page_words(&mut self, page_num: usize) -> Result<Vec<Word>, String> {
+        if page_num >= self.num_pages {
+            return Err(format!("Page {} out of range (total pages: {})", page_num, self.num_pages));
+        }
 
-        // Get the resolver
-        let resolver = self.file.resolver();
+        let file = &self.file;
+        let (page, _) = self.pages
+            .get(page_num, |nr| file.get_page(nr))
+            .map_err(|e| format!("Failed to get page: {}", e))?;
 
-        // Render the page
-        render_page(&mut backend, &resolver, &page, transform).map_err(|e|
+        let mut backend = SceneBackend::new(&mut self.cache);
+        backend.collect_text();
+        render_page(&mut backend, &self.file.resolver(), &page, Transform2F::default()).map_err(|e|
             format!("Failed to render page: {}", e)
         )?;
+        let (_, spans) = backend.finish_with_text();
+
+        Ok(page_words(&spans))
+    }
+
+    /// The lines of a page, see `page_words`
+    pub fn page_lines(&mut self, page_num: usize) -> Result<Vec<TextLine>, String> {
+        Ok(page_lines(&self.page_words(page_num)?))
+    }
+
+    /// Missing or broken fonts and images of a page, checked without rendering it
+    pub fn page_warnings(&self, page_num: usize) -> Vec<String> {
+        if page_num >= self.num_pages {
+            return vec![format!("Page {} out of range (total pages: {})", page_num, self.num_pages)];
+        }
 
-        Ok(backend.finish())
+        match self.file.get_page(page_num as u32) {
+            Ok(page) => page_warnings(&page, &self.file.resolver()),
+            Err(e) => vec![format!("Failed to get page: {}", e)],
+        }
     }
 
     /// Render a specific page to an image (RGBA)
@@ -85,32 +169,77 @@ impl PdfRenderer {
         page_num: usize,
         dpi: f32,
     ) -> Result<RgbaImage, String> {
+        self.render_page_to_image_async(page_num, dpi)?
+            .wait()
+            .map_err(|e| format!("Failed to rasterize page: {}", e))
+    }
+
+    /// Like `render_page_to_image`, but returns once the scene is built.
+    /// The image is ready when the returned future resolves.
+    pub fn render_page_to_image_async(
+        &mut self,
+        page_num: usize,
+        dpi: f32,
+    ) -> Result<RenderFuture, String> {
+        // started first, the scene depends on its backend
+        self.rasterizer()?;
         let scale = Transform2F::from_scale(dpi / 25.4);
         let scene = self.render_page(page_num, scale)?;
-        
-        // Spawn a separate thread to do OpenGL rendering
-        // This prevents conflicts with the main UI rendering thread
-        let handle = std::thread::spawn(move || {
-            let mut rasterizer = Rasterizer::new();
-            rasterizer.rasterize(scene, Some(ColorF::white()))
-        });
-        
-        // Wait for the rendering to complete
-        handle.join()
-            .map_err(|_| "Rendering thread panicked".to_string())
+
+        // Rasterize on the worker thread, which owns its own OpenGL context
+        // so it does not conflict with the main UI rendering thread
+        Ok(self.rasterizer()?.render(scene, Some(ColorF::white())))
     }
 
-    /// Get the bounding box of a page
-    pub fn page_bounds(&self, page_num: usize) -> Result<RectF, String> {
+    /// Render a page to exactly `width` x `height` pixels, centered on `background`
+    pub fn render_page_fit(
+        &mut self,
+        page_num: usize,
+        width: u32,
+        height: u32,
+        background: ColorF,
+    ) -> Result<RgbaImage, String> {
         if page_num >= self.num_pages {
             return Err(format!("Page {} out of range (total pages: {})", page_num, self.num_pages));
         }
 
-        let page = self.file
-            .get_page(page_num as u32)
+        self.rasterizer()?;
+        let file = &self.file;
+        let (page, _) = self.pages
+            .get(page_num, |nr| file.get_page(nr))
             .map_err(|e| format!("Failed to get page: {}", e))?;
 
-        Ok(page_bounds(&page))
+        let scene = render_page_fit(&mut self.cache, &self.file.resolver(), &page, width, height)
+            .map_err(|e| format!("Failed to render page: {}", e))?;
+
+        self.rasterizer()?
+            .render_blocking(scene, background)
+            .map_err(|e| format!("Failed to rasterize page: {}", e))
+    }
+
+    /// The rasterizer worker; without a GL context it uses the CPU rasterizer,
+    /// and transparency groups are drawn in place from then on
+    pub fn rasterizer(&mut self) -> Result<&RasterizeWorker, String> {
+        if self.rasterizer.is_none() {
+            let worker = RasterizeWorker::new(RasterizeBackend::Auto)
+                .map_err(|e| format!("Failed to start the rasterizer: {}", e))?;
+            // the CPU rasterizer can't composite render targets
+            self.cache.set_offscreen(worker.backend() != RasterizeBackend::Cpu);
+            self.rasterizer = Some(worker);
+        }
+        Ok(self.rasterizer.as_ref().unwrap())
+    }
+
+    /// Get the bounding box of a page
+    pub fn page_bounds(&mut self, page_num: usize) -> Result<RectF, String> {
+        if page_num >= self.num_pages {
+            return Err(format!("Page {} out of range (total pages: {})", page_num, self.num_pages));
+        }
+
+        let file = &self.file;
+        self.pages
+            .bounds(page_num, |nr| file.get_page(nr))
+            .map_err(|e| format!("Failed to get page: {}", e))
     }
 
     /// Get PDF metadata (title, author, etc.)
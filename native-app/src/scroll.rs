@@ -0,0 +1,213 @@
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Vertical layout of all pages for continuous scrolling.
+///
+/// All values are in logical pixels, `scroll_y` is the distance from the top of the first page.
+#[derive(Clone, Debug, Default)]
+pub struct ScrollLayout {
+    /// top of each page
+    offsets: Vec<f32>,
+    heights: Vec<f32>,
+    gap: f32,
+}
+
+impl ScrollLayout {
+    pub fn new(heights: Vec<f32>, gap: f32) -> Self {
+        let mut offsets = Vec::with_capacity(heights.len());
+        let mut y = 0.0;
+        for &h in &heights {
+            offsets.push(y);
+            y += h + gap;
+        }
+        ScrollLayout { offsets, heights, gap }
+    }
+
+    pub fn num_pages(&self) -> usize {
+        self.heights.len()
+    }
+
+    pub fn total_height(&self) -> f32 {
+        match self.heights.last() {
+            Some(h) => self.offsets[self.offsets.len() - 1] + h,
+            None => 0.0,
+        }
+    }
+
+    /// Top of page `page`.
+    pub fn page_offset(&self, page: usize) -> f32 {
+        self.offsets.get(page).copied().unwrap_or_else(|| self.total_height())
+    }
+
+    /// Space between two pages.
+    pub fn gap(&self) -> f32 {
+        self.gap
+    }
+
+    pub fn page_height(&self, page: usize) -> f32 {
+        self.heights.get(page).copied().unwrap_or(0.0)
+    }
+
+    /// The page under `y`. Points in the gap belong to the page above.
+    pub fn page_at(&self, y: f32) -> usize {
+        match self.offsets.partition_point(|&top| top <= y) {
+            0 => 0,
+            n => n - 1,
+        }
+    }
+
+    /// The page at the middle of the viewport.
+    pub fn current_page(&self, scroll_y: f32, viewport_height: f32) -> usize {
+        self.page_at(scroll_y + 0.5 * viewport_height)
+    }
+
+    /// Pages that intersect the viewport extended by `margin` above and below.
+    ///
+    /// These are the ones that should be rendered.
+    pub fn visible_pages(&self, scroll_y: f32, viewport_height: f32, margin: f32) -> Range<usize> {
+        if self.heights.is_empty() {
+            return 0..0;
+        }
+        let top = scroll_y - margin;
+        let bottom = scroll_y + viewport_height + margin;
+        let start = (0..self.num_pages())
+            .find(|&i| self.offsets[i] + self.heights[i] >= top)
+            .unwrap_or(self.num_pages());
+        let end = self.offsets.partition_point(|&t| t < bottom);
+        start..end.max(start)
+    }
+}
+
+/// Rendered page images, least recently used ones are dropped first.
+///
+/// Pages rendered in the background are marked with `start` and added with `finish`.
+pub struct PageImageCache {
+    capacity: usize,
+    // (page, zoom in percent), most recently used last
+    entries: VecDeque<((usize, i32), Arc<Path>)>,
+    // renders in progress and their ticket
+    pending: HashMap<(usize, i32), u64>,
+    next_ticket: u64,
+}
+
+impl PageImageCache {
+    pub fn new(capacity: usize) -> Self {
+        PageImageCache {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            pending: HashMap::new(),
+            next_ticket: 0,
+        }
+    }
+
+    fn key(page: usize, zoom: f32) -> (usize, i32) {
+        (page, (zoom * 100.0) as i32)
+    }
+
+    pub fn get(&mut self, page: usize, zoom: f32) -> Option<Arc<Path>> {
+        let key = Self::key(page, zoom);
+        let idx = self.entries.iter().position(|(k, _)| *k == key)?;
+        let entry = self.entries.remove(idx)?;
+        let path = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(path)
+    }
+
+    /// Look up without changing the order.
+    pub fn peek(&self, page: usize, zoom: f32) -> Option<Arc<Path>> {
+        let key = Self::key(page, zoom);
+        self.entries.iter().find(|(k, _)| *k == key).map(|(_, path)| path.clone())
+    }
+
+    pub fn insert(&mut self, page: usize, zoom: f32, path: Arc<Path>) {
+        let key = Self::key(page, zoom);
+        self.entries.retain(|(k, _)| *k != key);
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, path));
+    }
+
+    /// Mark `page` as being rendered. Returns the ticket to pass to `finish`,
+    /// `None` if it is rendered already.
+    pub fn start(&mut self, page: usize, zoom: f32) -> Option<u64> {
+        let key = Self::key(page, zoom);
+        if self.pending.contains_key(&key) {
+            return None;
+        }
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.pending.insert(key, ticket);
+        Some(ticket)
+    }
+
+    /// Add the image of a render started with `start`.
+    ///
+    /// It is dropped if the cache was cleared since, e.g. because another file was opened.
+    pub fn finish(&mut self, page: usize, zoom: f32, ticket: u64, path: Arc<Path>) {
+        let key = Self::key(page, zoom);
+        if self.pending.get(&key) == Some(&ticket) {
+            self.pending.remove(&key);
+            self.insert(page, zoom, path);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_page() {
+        let layout = ScrollLayout::new(vec![1000.0; 10], 20.0);
+        assert_eq!(layout.current_page(0.0, 800.0), 0);
+        // the middle of the viewport is still on the first page
+        assert_eq!(layout.current_page(500.0, 800.0), 0);
+        assert_eq!(layout.current_page(700.0, 800.0), 1);
+        assert_eq!(layout.current_page(5.0 * 1020.0 - 400.0, 800.0), 5);
+        assert_eq!(layout.current_page(1e6, 800.0), 9);
+
+        assert_eq!(layout.visible_pages(0.0, 800.0, 0.0), 0..1);
+        assert_eq!(layout.visible_pages(3000.0, 800.0, 500.0), 2..5);
+    }
+
+    #[test]
+    fn test_page_image_cache() {
+        let mut cache = PageImageCache::new(2);
+        cache.insert(0, 1.0, Path::new("0").into());
+        cache.insert(1, 1.0, Path::new("1").into());
+        assert!(cache.get(0, 1.0).is_some());
+        cache.insert(2, 1.0, Path::new("2").into());
+        // page 1 was used least recently
+        assert!(cache.peek(1, 1.0).is_none());
+        assert!(cache.peek(0, 1.0).is_some());
+        assert!(cache.peek(0, 1.2).is_none());
+    }
+
+    #[test]
+    fn test_page_image_cache_pending() {
+        let mut cache = PageImageCache::new(4);
+        let ticket = cache.start(0, 1.0).unwrap();
+        // started once
+        assert!(cache.start(0, 1.0).is_none());
+        assert!(cache.peek(0, 1.0).is_none());
+        cache.finish(0, 1.0, ticket, Path::new("0").into());
+        assert!(cache.peek(0, 1.0).is_some());
+
+        // a render of the previous file is dropped
+        let old = cache.start(1, 1.0).unwrap();
+        cache.clear();
+        let new = cache.start(1, 1.0).unwrap();
+        cache.finish(1, 1.0, old, Path::new("old").into());
+        assert!(cache.peek(1, 1.0).is_none());
+        cache.finish(1, 1.0, new, Path::new("new").into());
+        assert_eq!(cache.peek(1, 1.0).as_deref(), Some(Path::new("new")));
+    }
+}
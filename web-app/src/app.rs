@@ -1,4 +1,5 @@
 use dioxus::prelude::*;
+use inkrender::Attachment;
 use crate::components::{ Header, Toolbar, PDFCanvas, Sidebar };
 
 #[derive(Clone, Copy, PartialEq)]
@@ -17,6 +18,8 @@ pub struct AppState {
     pub sidebar_visible: bool,
     pub file_loaded: bool,
     pub file_name: Option<String>,
+    // embedded files of the document, listed in the sidebar
+    pub attachments: Vec<Attachment>,
 }
 
 impl Default for AppState {
@@ -29,6 +32,7 @@ impl Default for AppState {
             sidebar_visible: false,
             file_loaded: false,
             file_name: None,
+            attachments: vec![],
         }
     }
 }
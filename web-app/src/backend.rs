@@ -4,14 +4,22 @@ use pathfinder_geometry::vector::Vector2F;
 /// WebGL backend for Dioxus-based PDF viewer
 pub struct DioxusBackend {
     window_size: Vector2F,
+    // shows the tooltip, e.g. as the title of the canvas
+    tooltip_observer: Option<Box<dyn FnMut(Option<&str>)>>,
 }
 
 impl DioxusBackend {
     pub fn new() -> Self {
         Self {
             window_size: Vector2F::new(800.0, 600.0),
+            tooltip_observer: None,
         }
     }
+
+    /// Called with the tooltip to show, `None` to hide it.
+    pub fn on_tooltip(&mut self, observer: impl FnMut(Option<&str>) + 'static) {
+        self.tooltip_observer = Some(Box::new(observer));
+    }
 }
 
 impl ViewBackend for DioxusBackend {
@@ -34,4 +42,50 @@ impl ViewBackend for DioxusBackend {
         // For now, we'll leave it as a no-op
         // Future: Could use web-sys to update the favicon
     }
+
+    fn open_uri(&mut self, uri: &str) {
+        let opened = web_sys::window().map(|window| window.open_with_url_and_target(uri, "_blank"));
+        if !matches!(opened, Some(Ok(Some(_)))) {
+            log::warn!("failed to open {}", uri);
+        }
+    }
+
+    fn set_tooltip(&mut self, tooltip: Option<String>) {
+        if let Some(observer) = self.tooltip_observer.as_mut() {
+            observer(tooltip.as_deref());
+        }
+    }
+
+    fn set_fullscreen(&mut self, fullscreen: bool) {
+        let document = match web_sys::window().and_then(|window| window.document()) {
+            Some(document) => document,
+            None => return,
+        };
+        let result = if fullscreen {
+            match document.document_element() {
+                Some(element) => element.request_fullscreen(),
+                None => return,
+            }
+        } else if document.fullscreen_element().is_some() {
+            document.exit_fullscreen();
+            Ok(())
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            log::warn!("fullscreen is not available: {:?}", e);
+        }
+    }
+    fn set_clipboard(&mut self, text: &str) {
+        let clipboard = match web_sys::window() {
+            Some(window) => window.navigator().clipboard(),
+            None => return,
+        };
+        let written = wasm_bindgen_futures::JsFuture::from(clipboard.write_text(text));
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Err(e) = written.await {
+                log::warn!("can't write to the clipboard: {:?}", e);
+            }
+        });
+    }
 }
@@ -61,7 +61,7 @@ pub fn Sidebar(app_state: Signal<AppState>) -> Element {
                 match *active_tab.read() {
                     SidebarTab::Thumbnails => rsx! { ThumbnailsView { app_state: app_state } },
                     SidebarTab::Bookmarks => rsx! { BookmarksView {} },
-                    SidebarTab::Attachments => rsx! { AttachmentsView {} },
+                    SidebarTab::Attachments => rsx! { AttachmentsView { app_state: app_state } },
                 }
             }
         }
@@ -125,11 +125,51 @@ fn BookmarksView() -> Element {
 }
 
 #[component]
-fn AttachmentsView() -> Element {
+fn AttachmentsView(app_state: Signal<AppState>) -> Element {
+    let state = app_state.read();
+
     rsx! {
         div {
-            style: "text-align: center; color: #666; padding: 24px;",
-            "No attachments"
+            class: "attachments",
+            style: "display: flex; flex-direction: column; gap: 4px;",
+
+            if state.attachments.is_empty() {
+                div {
+                    style: "text-align: center; color: #666; padding: 24px;",
+                    "No attachments"
+                }
+            } else {
+                for (i, attachment) in state.attachments.iter().enumerate() {
+                    div {
+                        key: "{i}",
+                        title: "{attachment.name}",
+                        style: "padding: 8px; border-radius: 4px; background: #2d2d2d;",
+
+                        div {
+                            style: "font-size: 13px; overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                            "{attachment.name}"
+                        }
+
+                        div {
+                            style: "margin-top: 2px; font-size: 12px; color: #999;",
+                            "{format_size(attachment.data.len())}"
+                            if let Some(mime) = attachment.mime.as_ref() {
+                                " · {mime}"
+                            }
+                        }
+                    }
+                }
+            }
         }
     }
 }
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f32 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f32 / (1024.0 * 1024.0))
+    }
+}
@@ -3,6 +3,7 @@ use web_sys::{ HtmlCanvasElement, WebGl2RenderingContext };
 use wasm_bindgen::JsCast;
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 use pathfinder_webgl::WebGlDevice;
 use pathfinder_renderer::{
@@ -10,14 +11,16 @@ use pathfinder_renderer::{
         options::{ DestFramebuffer, RendererMode, RendererOptions, RendererLevel },
         renderer::Renderer,
     },
-    options::{ BuildOptions, RenderTransform },
+    options::BuildOptions,
     concurrent::executor::SequentialExecutor,
+    scene::Scene,
 };
 use pathfinder_geometry::{ vector::{ Vector2F, Vector2I }, rect::RectI, transform2d::Transform2F };
-use pathfinder_color::ColorF;
 use pathfinder_resources::embedded::EmbeddedResourceLoader;
 
-use viewer::{ Context, Config, Emitter, Interactive };
+use inkrender::{Attachment, ClearMode, RenderOptions};
+use viewer::{ Context, Config, Emitter, Interactive, ScrollUnit, ViewError, ViewState };
+use dioxus::html::{ geometry::WheelDelta, Key };
 use crate::backend::DioxusBackend;
 use crate::pdf_app::{ PdfViewerApp, ViewerEvent };
 
@@ -26,6 +29,23 @@ pub struct WebGlRenderer {
     renderer: Renderer<WebGlDevice>,
     app: PdfViewerApp,
     context: Context<DioxusBackend>,
+    // active touch points by pointer id
+    pointers: HashMap<i32, Vector2F>,
+    // distance between the two touch points and the zoom when the pinch started
+    pinch: Option<(f32, f32)>,
+    // told about failed renders, and with `None` once a page renders again
+    error_observer: Option<Box<dyn FnMut(Option<&ViewError>)>>,
+    render_options: RenderOptions,
+}
+
+/// how much one pixel of wheel movement zooms
+const WHEEL_ZOOM_SPEED: f32 = 0.002;
+/// wheel and pinch zooming draw at most this many frames per second
+const MAX_ZOOM_FPS: f32 = 60.0;
+
+/// seconds since the epoch, for the frame interval
+fn now() -> f64 {
+    js_sys::Date::now() / 1000.0
 }
 
 impl WebGlRenderer {
@@ -34,28 +54,36 @@ impl WebGlRenderer {
         scale_factor: f32
     ) -> Result<Self, wasm_bindgen::JsValue> {
         // Get WebGL2 context
-        let context = canvas
+        let gl_context = canvas
             .get_context("webgl2")?
             .ok_or_else(|| wasm_bindgen::JsValue::from_str("Failed to get WebGL2 context"))?
             .dyn_into::<WebGl2RenderingContext>()?;
 
-        let width = 1200;
-        let height = 800;
-        canvas.set_width(width);
-        canvas.set_height(height);
+        // logical size, the canvas is backed by device pixels
+        let window_size = Vector2F::new(1200.0, 800.0);
+        let framebuffer_size = (window_size * scale_factor).ceil().to_i32();
+        canvas.set_width(framebuffer_size.x() as u32);
+        canvas.set_height(framebuffer_size.y() as u32);
 
-        let framebuffer_size = Vector2I::new(width as i32, height as i32);
+        // Create viewer context with a separate resource loader for Config
+        let config_resource_loader = EmbeddedResourceLoader::new();
+        let config = Rc::new(Config::new(Box::new(config_resource_loader)));
+        let backend = DioxusBackend::new();
+        let mut context = Context::new(config, backend);
+        context.set_window_size(window_size);
+        context.set_scale_factor(scale_factor);
+        context.set_min_frame_interval(Some(1.0 / MAX_ZOOM_FPS));
 
         // Create renderer
         let renderer_resource_loader = EmbeddedResourceLoader::new();
-        let device = WebGlDevice::new(context);
+        let device = WebGlDevice::new(gl_context);
 
         let render_mode = RendererMode {
             level: RendererLevel::D3D9,
         };
 
         let render_options = RendererOptions {
-            background_color: Some(ColorF::new(0.95, 0.95, 0.95, 1.0)),
+            background_color: ClearMode::Color(context.canvas_color()).background_color(),
             dest: DestFramebuffer::Default {
                 viewport: RectI::new(Vector2I::zero(), framebuffer_size),
                 window_size: framebuffer_size,
@@ -70,14 +98,6 @@ impl WebGlRenderer {
             render_options
         );
 
-        // Create viewer context with a separate resource loader for Config
-        let config_resource_loader = EmbeddedResourceLoader::new();
-        let config = Rc::new(Config::new(Box::new(config_resource_loader)));
-        let backend = DioxusBackend::new();
-        let mut context = Context::new(config, backend);
-        context.set_window_size(framebuffer_size.to_f32());
-        context.set_scale_factor(scale_factor);
-
         // Create viewer app
         let mut app = PdfViewerApp::new();
 
@@ -89,50 +109,158 @@ impl WebGlRenderer {
             renderer,
             app,
             context,
+            pointers: HashMap::new(),
+            pinch: None,
+            error_observer: None,
+            render_options: RenderOptions::default(),
         })
     }
 
     pub fn load_pdf(&mut self, data: Vec<u8>) -> Result<usize, String> {
+        if self.app.is_loaded() {
+            self.app.on_document_unloaded(&mut self.context);
+        }
         let num_pages = self.app.load_pdf(data)?;
-        self.context.num_pages = num_pages;
-        self.context.request_redraw();
+        self.app.on_document_loaded(&mut self.context, num_pages);
         Ok(num_pages)
     }
 
     pub fn render(&mut self) {
-        // Generate scene using Interactive trait
-        let mut scene = self.app.scene(&mut self.context);
+        // Generate scene using Interactive trait, unless the view is unchanged
+        let app = &mut self.app;
+        let result = self.context.try_cached_scene(|ctx| app.try_scene(ctx));
+        if let Some(observer) = self.error_observer.as_mut() {
+            observer(result.as_ref().err());
+        }
+        let mut scene = result.unwrap_or_else(|e| {
+            log::error!("{}", e);
+            let mut scene = Scene::new();
+            scene.set_view_box(self.context.view_box());
+            scene
+        });
 
         // Build and render the scene
-        let options = BuildOptions {
-            transform: RenderTransform::Transform2D(Transform2F::default()),
-            dilation: Vector2F::default(),
-            subpixel_aa_enabled: true,
-        };
+        scene.build_and_render(&mut self.renderer, self.build_options(), SequentialExecutor);
+
+        self.context.mark_drawn_at(now());
+    }
 
-        scene.build_and_render(&mut self.renderer, options, SequentialExecutor);
+    /// Render if a frame was requested and the minimum frame interval has passed.
+    pub fn render_if_due(&mut self) {
+        if self.context.redraw_delay(now()) == Some(0.0) {
+            self.render();
+        }
+    }
+
+    /// Milliseconds until `render_if_due` draws the requested frame, `None` without one.
+    pub fn frame_delay(&self) -> Option<u32> {
+        self.context.redraw_delay(now()).map(|delay| (delay * 1000.0).ceil() as u32)
+    }
+
+    /// Anti-aliasing and dilation, shared with the native rasterizer.
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.render_options = options;
+        self.context.request_redraw();
+    }
 
-        self.context.redraw_requested = false;
+    fn build_options(&self) -> BuildOptions {
+        // the scene is already in window coordinates
+        self.render_options.build_options(Transform2F::default())
     }
 
     pub fn handle_event(&mut self, event: ViewerEvent) {
         self.app.event(&mut self.context, event);
-        if self.context.redraw_requested {
+        if self.context.needs_redraw() {
             self.render();
         }
     }
 
+    /// Like `handle_event`, for events that come in bursts.
+    ///
+    /// Draws at most every `MAX_ZOOM_FPS`th of a second, the last frame has to be
+    /// drawn later with `render_if_due`, see `frame_delay`.
+    fn handle_event_throttled(&mut self, event: ViewerEvent) {
+        self.app.event(&mut self.context, event);
+        self.render_if_due();
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         let new_size = Vector2F::new(width as f32, height as f32);
         self.context.set_window_size(new_size);
 
         log::info!("Resized to {}x{}", width, height);
 
-        if self.context.redraw_requested {
+        if self.context.needs_redraw() {
             self.render();
         }
     }
 
+    /// Ctrl+wheel (which is also what trackpad pinches produce) zooms around `pos`.
+    ///
+    /// Returns true if the event was used.
+    pub fn wheel(&mut self, delta_y: f32, ctrl: bool, pos: Vector2F) -> bool {
+        if !ctrl {
+            return false;
+        }
+        let zoom = self.context.scale * (-delta_y * WHEEL_ZOOM_SPEED).exp();
+        self.handle_event_throttled(ViewerEvent::SetZoomAt(zoom, pos));
+        true
+    }
+
+    /// Ctrl (Cmd on macOS) shortcuts: A selects the page text, C copies it,
+    /// Shift+C copies the text of the whole document.
+    ///
+    /// Returns true if the key was used.
+    pub fn key_down(&mut self, key: &str, command: bool, shift: bool) -> bool {
+        if !command {
+            return false;
+        }
+        let event = match (key.to_lowercase().as_str(), shift) {
+            ("a", false) => ViewerEvent::SelectAll,
+            ("c", false) => ViewerEvent::Copy,
+            ("c", true) => ViewerEvent::CopyDocument,
+            _ => return false,
+        };
+        self.handle_event(event);
+        true
+    }
+
+    pub fn pointer_down(&mut self, id: i32, pos: Vector2F) {
+        self.pointers.insert(id, pos);
+        self.pinch = self.pinch_distance().map(|d| (d, self.context.scale));
+    }
+
+    /// Two-finger pinch zooms around the centroid of the touch points.
+    pub fn pointer_move(&mut self, id: i32, pos: Vector2F) {
+        match self.pointers.get_mut(&id) {
+            Some(p) => *p = pos,
+            None => {
+                // no button pressed, the mouse hovers
+                self.handle_event(ViewerEvent::Hover(pos));
+                return;
+            }
+        }
+        if let (Some((start_distance, start_zoom)), Some(distance)) = (self.pinch, self.pinch_distance()) {
+            let centroid = self.pointers.values().fold(Vector2F::zero(), |a, &b| a + b) * 0.5;
+            let zoom = start_zoom * distance / start_distance;
+            self.handle_event_throttled(ViewerEvent::SetZoomAt(zoom, centroid));
+        }
+    }
+
+    pub fn pointer_up(&mut self, id: i32) {
+        self.pointers.remove(&id);
+        self.pinch = self.pinch_distance().map(|d| (d, self.context.scale));
+    }
+
+    fn pinch_distance(&self) -> Option<f32> {
+        if self.pointers.len() != 2 {
+            return None;
+        }
+        let mut points = self.pointers.values();
+        let (&a, &b) = (points.next()?, points.next()?);
+        Some((a - b).length()).filter(|&d| d > 0.0)
+    }
+
     pub fn get_page_info(&self) -> (usize, usize) {
         (self.context.page_nr + 1, self.context.num_pages)
     }
@@ -140,6 +268,31 @@ impl WebGlRenderer {
     pub fn get_zoom(&self) -> f32 {
         self.context.scale
     }
+
+    /// Files embedded in the loaded document
+    pub fn attachments(&self) -> Vec<Attachment> {
+        self.app.attachments()
+    }
+
+    /// "Natural" scrolling, see `Context::set_scroll_inverted`.
+    pub fn set_scroll_inverted(&mut self, inverted: bool) {
+        self.context.set_scroll_inverted(inverted);
+    }
+
+    /// See `Context::on_view_change`.
+    pub fn on_view_change(&mut self, observer: impl FnMut(&ViewState) + 'static) {
+        self.context.on_view_change(Box::new(observer));
+    }
+
+    /// See `DioxusBackend::on_tooltip`.
+    pub fn on_tooltip(&mut self, observer: impl FnMut(Option<&str>) + 'static) {
+        self.context.backend.on_tooltip(observer);
+    }
+
+    /// Called after every render with the error, if the page failed to render.
+    pub fn on_render_error(&mut self, observer: impl FnMut(Option<&ViewError>) + 'static) {
+        self.error_observer = Some(Box::new(observer));
+    }
 }
 
 /// Main application state
@@ -149,6 +302,10 @@ struct AppState {
     total_pages: usize,
     zoom: f32,
     file_loaded: bool,
+    natural_scrolling: bool,
+    render_error: Option<String>,
+    // of the link or annotation under the mouse
+    tooltip: Option<String>,
 }
 
 impl Default for AppState {
@@ -158,10 +315,24 @@ impl Default for AppState {
             total_pages: 0,
             zoom: 1.0,
             file_loaded: false,
+            natural_scrolling: false,
+            render_error: None,
+            tooltip: None,
         }
     }
 }
 
+/// Draw the frame that throttled zooming left pending, once it is due.
+fn schedule_frame(renderer: &Rc<RefCell<WebGlRenderer>>) {
+    if let Some(delay) = renderer.borrow().frame_delay() {
+        let renderer = renderer.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            gloo_timers::future::TimeoutFuture::new(delay).await;
+            renderer.borrow_mut().render_if_due();
+        });
+    }
+}
+
 #[component]
 pub fn InteractiveApp() -> Element {
     let mut app_state = use_signal(AppState::default);
@@ -184,8 +355,24 @@ pub fn InteractiveApp() -> Element {
                     let scale_factor = window.device_pixel_ratio() as f32;
 
                     match WebGlRenderer::new(&canvas, scale_factor) {
-                        Ok(gl_renderer) => {
+                        Ok(mut gl_renderer) => {
                             log::info!("WebGL renderer created successfully");
+                            // keep the toolbar in sync, whatever changed the view
+                            gl_renderer.on_view_change(move |state| {
+                                let mut toolbar = app_state.write();
+                                toolbar.current_page = state.page_nr + 1;
+                                toolbar.total_pages = state.num_pages;
+                                toolbar.zoom = state.scale;
+                            });
+                            gl_renderer.on_render_error(move |error| {
+                                let error = error.map(|e| e.to_string());
+                                if app_state.read().render_error != error {
+                                    app_state.write().render_error = error;
+                                }
+                            });
+                            gl_renderer.on_tooltip(move |tooltip| {
+                                app_state.write().tooltip = tooltip.map(String::from);
+                            });
                             *renderer_clone.write() = Some(Rc::new(RefCell::new(gl_renderer)));
                         }
                         Err(e) => log::error!("Failed to create WebGL renderer: {:?}", e),
@@ -216,12 +403,9 @@ pub fn InteractiveApp() -> Element {
                         let mut renderer_mut = renderer_ref.borrow_mut();
                         match renderer_mut.load_pdf(data) {
                             Ok(num_pages) => {
-                                log::info!("PDF loaded with {} pages", num_pages);
+                                let attachments = renderer_mut.attachments();
+                                log::info!("PDF loaded with {} pages and {} attachments", num_pages, attachments.len());
                                 renderer_mut.render();
-
-                                let (current, total) = renderer_mut.get_page_info();
-                                app_state.write().current_page = current;
-                                app_state.write().total_pages = total;
                                 app_state.write().file_loaded = true;
                             }
                             Err(e) => log::error!("Failed to load PDF: {}", e),
@@ -235,35 +419,91 @@ pub fn InteractiveApp() -> Element {
     }; // Navigation handlers
     let handle_prev = move |_| {
         if let Some(renderer_ref) = renderer.read().as_ref() {
-            let mut renderer_mut = renderer_ref.borrow_mut();
-            renderer_mut.handle_event(ViewerEvent::PrevPage);
-            let (current, _) = renderer_mut.get_page_info();
-            app_state.write().current_page = current;
+            renderer_ref.borrow_mut().handle_event(ViewerEvent::PrevPage);
         }
     };
 
     let handle_next = move |_| {
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            renderer_ref.borrow_mut().handle_event(ViewerEvent::NextPage);
+        }
+    };
+
+    let handle_canvas_click = move |evt: MouseEvent| {
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            let pos = evt.element_coordinates();
+            renderer_ref.borrow_mut().handle_event(ViewerEvent::Click(Vector2F::new(pos.x as f32, pos.y as f32)));
+        }
+    };
+
+    let handle_wheel = move |evt: WheelEvent| {
         if let Some(renderer_ref) = renderer.read().as_ref() {
             let mut renderer_mut = renderer_ref.borrow_mut();
-            renderer_mut.handle_event(ViewerEvent::NextPage);
-            let (current, _) = renderer_mut.get_page_info();
-            app_state.write().current_page = current;
+            let pos = evt.element_coordinates();
+            let delta = evt.delta().strip_units();
+            let pos = Vector2F::new(pos.x as f32, pos.y as f32);
+            if !renderer_mut.wheel(delta.y as f32, evt.modifiers().ctrl(), pos) {
+                let unit = match evt.delta() {
+                    WheelDelta::Lines(_) => ScrollUnit::Lines,
+                    _ => ScrollUnit::Pixels,
+                };
+                renderer_mut.handle_event(ViewerEvent::Scroll(Vector2F::new(delta.x as f32, delta.y as f32), unit));
+            }
+            // don't zoom or scroll the whole page
+            evt.prevent_default();
+            drop(renderer_mut);
+            schedule_frame(renderer_ref);
+        }
+    };
+
+    let handle_key_down = move |evt: KeyboardEvent| {
+        if let (Some(renderer_ref), Key::Character(key)) = (renderer.read().as_ref(), evt.key()) {
+            let modifiers = evt.modifiers();
+            if renderer_ref.borrow_mut().key_down(&key, modifiers.ctrl() || modifiers.meta(), modifiers.shift()) {
+                // not the browser's select all or copy
+                evt.prevent_default();
+            }
+        }
+    };
+
+    let toggle_natural_scrolling = move |_| {
+        let natural = !app_state.read().natural_scrolling;
+        app_state.write().natural_scrolling = natural;
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            renderer_ref.borrow_mut().set_scroll_inverted(natural);
+        }
+    };
+
+    let handle_pointer_down = move |evt: PointerEvent| {
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            let pos = evt.element_coordinates();
+            renderer_ref.borrow_mut().pointer_down(evt.pointer_id(), Vector2F::new(pos.x as f32, pos.y as f32));
+        }
+    };
+
+    let handle_pointer_move = move |evt: PointerEvent| {
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            let pos = evt.element_coordinates();
+            renderer_ref.borrow_mut().pointer_move(evt.pointer_id(), Vector2F::new(pos.x as f32, pos.y as f32));
+            schedule_frame(renderer_ref);
+        }
+    };
+
+    let handle_pointer_up = move |evt: PointerEvent| {
+        if let Some(renderer_ref) = renderer.read().as_ref() {
+            renderer_ref.borrow_mut().pointer_up(evt.pointer_id());
         }
     };
 
     let handle_zoom_in = move |_| {
         if let Some(renderer_ref) = renderer.read().as_ref() {
-            let mut renderer_mut = renderer_ref.borrow_mut();
-            renderer_mut.handle_event(ViewerEvent::ZoomIn);
-            app_state.write().zoom = renderer_mut.get_zoom();
+            renderer_ref.borrow_mut().handle_event(ViewerEvent::ZoomIn);
         }
     };
 
     let handle_zoom_out = move |_| {
         if let Some(renderer_ref) = renderer.read().as_ref() {
-            let mut renderer_mut = renderer_ref.borrow_mut();
-            renderer_mut.handle_event(ViewerEvent::ZoomOut);
-            app_state.write().zoom = renderer_mut.get_zoom();
+            renderer_ref.borrow_mut().handle_event(ViewerEvent::ZoomOut);
         }
     };
 
@@ -341,6 +581,16 @@ pub fn InteractiveApp() -> Element {
                             style: "padding: 8px 16px; background: #0e639c; border-radius: 4px; cursor: pointer;",
                             "+"
                         }
+
+                        label {
+                            style: "display: flex; gap: 4px; align-items: center; cursor: pointer;",
+                            input {
+                                r#type: "checkbox",
+                                checked: app_state.read().natural_scrolling,
+                                onchange: toggle_natural_scrolling,
+                            }
+                            "Natural scrolling"
+                        }
                     }
                 }
             }
@@ -348,13 +598,81 @@ pub fn InteractiveApp() -> Element {
             // Canvas container
             div {
                 class: "canvas-container",
-                style: "flex: 1; display: flex; justify-content: center; align-items: center; overflow: auto; background: #2d2d2d;",
+                style: "flex: 1; display: flex; justify-content: center; align-items: center; overflow: auto; background: #2d2d2d; position: relative;",
                 
                 canvas {
                     id: "{canvas_id}",
-                    style: "display: block; box-shadow: 0 4px 12px rgba(0, 0, 0, 0.5);",
+                    onclick: handle_canvas_click,
+                    onwheel: handle_wheel,
+                    onpointerdown: handle_pointer_down,
+                    onpointermove: handle_pointer_move,
+                    onpointerup: handle_pointer_up,
+                    onpointercancel: handle_pointer_up,
+                    onkeydown: handle_key_down,
+                    // focusable, for the keyboard shortcuts
+                    tabindex: "0",
+                    title: app_state.read().tooltip.clone().unwrap_or_default(),
+                    // the backing store is scaled by the device pixel ratio
+                    style: "display: block; width: 1200px; height: 800px; touch-action: none; box-shadow: 0 4px 12px rgba(0, 0, 0, 0.5);",
+                }
+
+                if let Some(error) = app_state.read().render_error.clone() {
+                    div {
+                        class: "render-error",
+                        style: "position: absolute; top: 50%; left: 50%; transform: translate(-50%, -50%); padding: 16px 24px; background: #5a1d1d; border: 1px solid #be1100; border-radius: 4px; max-width: 600px;",
+                        "{error}"
+                    }
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn test_renderer() -> WebGlRenderer {
+        let canvas = web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("canvas")
+            .unwrap()
+            .dyn_into::<HtmlCanvasElement>()
+            .unwrap();
+        WebGlRenderer::new(&canvas, 1.0).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn test_wheel_zoom() {
+        let mut renderer = test_renderer();
+        let pos = Vector2F::new(300.0, 200.0);
+        let zoom = renderer.get_zoom();
+
+        // plain scrolling doesn't zoom
+        assert!(!renderer.wheel(-100.0, false, pos));
+        assert_eq!(renderer.get_zoom(), zoom);
+
+        // wheel up zooms in
+        assert!(renderer.wheel(-100.0, true, pos));
+        let zoomed_in = renderer.get_zoom();
+        assert!(zoomed_in > zoom);
+
+        // wheel down zooms out
+        assert!(renderer.wheel(100.0, true, pos));
+        assert!(renderer.get_zoom() < zoomed_in);
+    }
+
+    #[wasm_bindgen_test]
+    fn test_render_options() {
+        let mut renderer = test_renderer();
+        assert!(!renderer.build_options().subpixel_aa_enabled);
+
+        renderer.set_render_options(RenderOptions { subpixel_aa: true, ..RenderOptions::default() });
+        assert!(renderer.build_options().subpixel_aa_enabled);
+    }
+}
@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use viewer::{ Interactive, Context, Emitter, Config };
+use viewer::{ Interactive, Context, Emitter, Config, TextRun, draw_highlight, page_space_links, page_space_tooltips, ScrollUnit, ViewError, PageCache, document_text };
 use pathfinder_renderer::scene::Scene;
 use pathfinder_geometry::{ vector::Vector2F, rect::RectF };
-use inkrender::{ Cache as RenderCache, SceneBackend, page_bounds, render_page };
+use pathfinder_color::ColorU;
+use inkrender::{ load_attachments, Attachment, Cache as RenderCache, SceneBackend, render_page };
 use pdf::file::{ File as PdfFile, FileOptions, NoLog, SyncCache };
 use pdf::any::AnySync;
 use pdf::PdfError;
@@ -19,10 +21,24 @@ pub enum ViewerEvent {
     ZoomIn,
     ZoomOut,
     SetZoom(f32),
+    /// zoom, keeping the window position (in logical pixels) fixed
+    SetZoomAt(f32, Vector2F),
+    /// click at a window position (in logical pixels)
+    Click(Vector2F),
+    /// the mouse moved to a window position (in logical pixels), without a button pressed
+    Hover(Vector2F),
+    /// wheel delta, without ctrl
+    Scroll(Vector2F, ScrollUnit),
+    /// Ctrl/Cmd+A
+    SelectAll,
+    /// Ctrl/Cmd+C: the selection, or the whole page if nothing is selected
+    Copy,
+    /// Ctrl/Cmd+Shift+C
+    CopyDocument,
 }
 
 /// PDF file type alias matching native-app pattern
-type PdfFileType = PdfFile<
+pub(crate) type PdfFileType = PdfFile<
     Vec<u8>,
     Arc<SyncCache<PlainRef, Result<AnySync, Arc<PdfError>>>>,
     Arc<SyncCache<PlainRef, Result<Arc<[u8]>, Arc<PdfError>>>>,
@@ -34,6 +50,8 @@ pub struct PdfViewerApp {
     pdf_file: Option<PdfFileType>,
     render_cache: RenderCache,
     emitter: Option<Emitter<ViewerEvent>>,
+    page_refs: HashMap<PlainRef, usize>,
+    pages: PageCache,
 }
 
 impl PdfViewerApp {
@@ -42,6 +60,8 @@ impl PdfViewerApp {
             pdf_file: None,
             render_cache: RenderCache::new(),
             emitter: None,
+            page_refs: HashMap::new(),
+            pages: PageCache::new(),
         }
     }
 
@@ -52,6 +72,13 @@ impl PdfViewerApp {
             .map_err(|e| format!("Failed to load PDF: {:?}", e))?;
 
         let num_pages = file.num_pages() as usize;
+        // link destinations refer to pages by reference
+        self.page_refs = file
+            .pages()
+            .enumerate()
+            .filter_map(|(i, page)| Some((page.ok()?.get_ref().get_inner(), i)))
+            .collect();
+        self.pages.clear();
         self.pdf_file = Some(file);
 
         Ok(num_pages)
@@ -68,6 +95,18 @@ impl PdfViewerApp {
         })
     }
 
+    /// Files embedded in the document, empty if they can't be read
+    pub fn attachments(&self) -> Vec<Attachment> {
+        let Some(file) = self.pdf_file.as_ref() else {
+            return vec![];
+        };
+        load_attachments(file.trailer.root.get_ref().get_inner(), &file.resolver())
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read attachments: {}", e);
+                vec![]
+            })
+    }
+
     /// Check if a PDF is loaded
     pub fn is_loaded(&self) -> bool {
         self.pdf_file.is_some()
@@ -79,25 +118,47 @@ impl Interactive for PdfViewerApp {
     type Backend = DioxusBackend;
 
     fn scene(&mut self, ctx: &mut Context<Self::Backend>) -> Scene {
+        self.try_scene(ctx).unwrap_or_else(|e| {
+            log::error!("{}", e);
+            let mut scene = Scene::new();
+            scene.set_view_box(ctx.view_box());
+            scene
+        })
+    }
+
+    fn try_scene(&mut self, ctx: &mut Context<Self::Backend>) -> Result<Scene, ViewError> {
         let mut backend = SceneBackend::new(&mut self.render_cache);
+        backend.collect_text();
+        let mut rendered = false;
+        let mut links = vec![];
+        let mut tooltips = vec![];
 
         if let Some(ref file) = self.pdf_file {
-            if let Ok(page) = file.get_page(ctx.page_nr as u32) {
-                let bounds = page_bounds(&page);
-                ctx.set_bounds(bounds);
+            let (page, bounds) = self.pages.get(ctx.page_nr, |nr| file.get_page(nr))?;
+            ctx.set_bounds(bounds);
 
-                let transform = ctx.view_transform();
-                let resolver = file.resolver();
+            let transform = ctx.view_transform();
+            let resolver = file.resolver();
 
-                if let Err(e) = render_page(&mut backend, &resolver, &page, transform) {
-                    log::error!("Failed to render page: {:?}", e);
-                }
-            }
+            render_page(&mut backend, &resolver, &page, transform)?;
+            rendered = true;
+            links = page_space_links(&page, &resolver, |r| self.page_refs.get(&r).copied());
+            tooltips = page_space_tooltips(&page, &resolver);
         }
 
-        let mut scene = backend.finish();
-        scene.set_view_box(RectF::new(Vector2F::default(), ctx.window_size));
-        scene
+        let (mut scene, spans) = backend.finish_with_text();
+
+        // text runs are kept in page space, so they stay valid while zooming and panning
+        if rendered {
+            let to_page = ctx.view_transform().inverse();
+            ctx.set_text_runs(spans.iter().map(|span| TextRun::from(span).transformed(to_page)).collect());
+            ctx.set_links(links);
+            ctx.set_tooltips(tooltips);
+            let quads = ctx.selection_quads();
+            draw_highlight(&mut scene, &quads, ctx.view_transform(), ColorU::new(0, 120, 215, 80));
+        }
+        scene.set_view_box(ctx.view_box());
+        Ok(scene)
     }
 
     fn init(&mut self, ctx: &mut Context<Self::Backend>, sender: Emitter<Self::Event>) {
@@ -105,10 +166,17 @@ impl Interactive for PdfViewerApp {
 
         // Set initial number of pages if PDF is loaded
         if let Some(ref file) = self.pdf_file {
-            ctx.num_pages = file.num_pages() as usize;
+            let num_pages = file.num_pages() as usize;
+            self.on_document_loaded(ctx, num_pages);
         }
     }
 
+    fn on_document_unloaded(&mut self, ctx: &mut Context<Self::Backend>) {
+        // fonts and images are cached by reference, which means nothing in the next file
+        self.render_cache = RenderCache::new();
+        ctx.unload_document();
+    }
+
     fn title(&self) -> String {
         match self.get_title() {
             Some(title) => format!("Inkstone - {}", title),
@@ -128,12 +196,29 @@ impl Interactive for PdfViewerApp {
             ViewerEvent::ZoomIn => ctx.zoom_by(0.5),
             ViewerEvent::ZoomOut => ctx.zoom_by(-0.5),
             ViewerEvent::SetZoom(zoom) => ctx.set_zoom(zoom),
+            ViewerEvent::SetZoomAt(zoom, pos) => ctx.zoom_at(zoom, pos),
+            ViewerEvent::Scroll(delta, unit) => ctx.scroll(delta, unit),
+            ViewerEvent::Click(pos) => {
+                let pos = ctx.window_to_page(pos);
+                self.mouse_click(ctx, pos);
+            }
+            ViewerEvent::Hover(pos) => {
+                let pos = ctx.window_to_page(pos);
+                self.cursor_moved(ctx, pos);
+            }
+            ViewerEvent::SelectAll => ctx.select_all(),
+            ViewerEvent::Copy => {
+                if !ctx.copy_selection() {
+                    ctx.copy_all_text();
+                }
+            }
+            ViewerEvent::CopyDocument => {
+                if let Some(ref file) = self.pdf_file {
+                    ctx.copy_text(&document_text(file));
+                }
+            }
         }
     }
-
-    fn cursor_moved(&mut self, _ctx: &mut Context<Self::Backend>, _pos: Vector2F) {
-        // Handle cursor movement if needed for features like tooltips
-    }
 }
 
 impl Default for PdfViewerApp {
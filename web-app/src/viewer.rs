@@ -5,7 +5,6 @@ use pathfinder_webgl::WebGlDevice;
 use pathfinder_renderer::{
     gpu::{ options::{ DestFramebuffer, RendererMode, RendererOptions }, renderer::Renderer },
     scene::Scene,
-    options::{ BuildOptions, RenderTransform },
     concurrent::executor::SequentialExecutor,
 };
 use pathfinder_geometry::{
@@ -13,14 +12,19 @@ use pathfinder_geometry::{
     rect::{ RectF, RectI },
     transform2d::Transform2F,
 };
-use pathfinder_color::ColorF;
 use pathfinder_resources::embedded::EmbeddedResourceLoader;
+use inkrender::{render_file_pages, Cache, ClearMode, RenderOptions};
+use pdf::file::FileOptions;
+
+use crate::pdf_app::PdfFileType;
 
 pub struct PDFRenderer {
     canvas: HtmlCanvasElement,
     renderer: Renderer<WebGlDevice>,
     framebuffer_size: Vector2I,
     resource_loader: EmbeddedResourceLoader,
+    file: Option<PdfFileType>,
+    cache: Cache,
 }
 
 impl PDFRenderer {
@@ -48,7 +52,7 @@ impl PDFRenderer {
         };
 
         let render_options = RendererOptions {
-            background_color: Some(ColorF::white()),
+            background_color: ClearMode::OpaqueWhite.background_color(),
             dest: DestFramebuffer::Default {
                 viewport: RectI::new(Vector2I::zero(), framebuffer_size),
                 window_size: framebuffer_size,
@@ -63,26 +67,34 @@ impl PDFRenderer {
             renderer,
             framebuffer_size,
             resource_loader,
+            file: None,
+            cache: Cache::new(),
         })
     }
 
     pub fn render_page(&mut self, page_num: usize, zoom: f32) {
         log::info!("Rendering page {} with zoom {}", page_num, zoom);
 
-        // Create a simple test scene for now
-        let mut scene = Scene::new();
-        let view_box = RectF::new(Vector2F::default(), self.framebuffer_size.to_f32());
-        scene.set_view_box(view_box);
-
-        // TODO: Integrate with pdf_view to render actual PDF content
-        // For now, just render a placeholder
+        // pages count from 1 here, in px at 1 px per pt
+        let nr = page_num.saturating_sub(1) as u32;
+        let page = self.file.as_ref().and_then(|file| {
+            render_file_pages(file, &mut self.cache, nr..nr + 1, |_| Transform2F::from_scale(72.0 / 25.4)).next()
+        });
+        let mut scene = match page {
+            Some(Ok(scene)) => scene,
+            page => {
+                if let Some(Err(e)) = page {
+                    log::error!("Failed to render page {}: {}", page_num, e);
+                }
+                // nothing loaded, a blank placeholder
+                let mut scene = Scene::new();
+                scene.set_view_box(RectF::new(Vector2F::default(), self.framebuffer_size.to_f32()));
+                scene
+            }
+        };
 
         let transform = Transform2F::from_scale(Vector2F::splat(zoom));
-        let options = BuildOptions {
-            transform: RenderTransform::Transform2D(transform),
-            dilation: Vector2F::default(),
-            subpixel_aa_enabled: false,
-        };
+        let options = RenderOptions::default().build_options(transform);
 
         scene.build_and_render(&mut self.renderer, options, SequentialExecutor);
 
@@ -100,9 +112,12 @@ impl PDFRenderer {
         log::info!("Framebuffer resized to {}x{}", width, height);
     }
 
-    pub fn load_pdf(&mut self, _data: &[u8]) -> Result<(), String> {
-        // TODO: Integrate with pdf crate to load and parse PDF
+    pub fn load_pdf(&mut self, data: &[u8]) -> Result<(), String> {
         log::info!("Loading PDF data...");
+        let file = FileOptions::cached()
+            .load(data.to_vec())
+            .map_err(|e| format!("Failed to load PDF: {:?}", e))?;
+        self.file = Some(file);
         Ok(())
     }
 }